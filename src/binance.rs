@@ -0,0 +1,3 @@
+mod client;
+
+pub use crate::binance::client::BinanceClient;
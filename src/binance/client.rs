@@ -0,0 +1,73 @@
+use reqwest::StatusCode;
+use serde_json::Value;
+
+use crate::clients::error::{ErrorCode, VenueError};
+use crate::clients::executor::{RequestExecutor, RetryPolicy};
+use crate::models::binance_models::DepthSnapshot;
+use crate::models::common::VenueKind;
+
+static VENUE: VenueKind = VenueKind::Binance;
+// deepest snapshot the futures api serves
+static SNAPSHOT_LIMIT: u32 = 1000;
+
+/**
+ * Public market data of the Binance futures REST api, only used to seed local books
+ */
+pub struct BinanceClient {
+    rest_url: String,
+    executor: RequestExecutor,
+}
+
+impl BinanceClient {
+    pub fn with_executor(executor: RequestExecutor, rest_url: &str) -> BinanceClient {
+        return BinanceClient {
+            rest_url: rest_url.to_string(),
+            executor,
+        };
+    }
+
+    /**
+     * Order book snapshot of a symbol, the stream symbol is accepted in either case
+     */
+    pub async fn get_depth_snapshot(&self, symbol: &str) -> Result<DepthSnapshot, VenueError> {
+        let url = format!(
+            "{}/fapi/v1/depth?symbol={}&limit={}",
+            self.rest_url,
+            symbol.to_uppercase(),
+            SNAPSHOT_LIMIT
+        );
+        let value = self
+            .executor
+            .execute(
+                &RetryPolicy::idempotent(),
+                |http: &reqwest::Client| http.get(&url),
+                parse_response,
+            )
+            .await?;
+
+        return VenueError::decode(VENUE, value);
+    }
+}
+
+/**
+ * Binance answers failures with an HTTP error status and a `{code, msg}` body
+ */
+fn parse_response(status: StatusCode, body: &str) -> Result<Value, VenueError> {
+    if status.is_success() {
+        return serde_json::from_str(body).map_err(|source| VenueError::Decode { venue: VENUE, source });
+    }
+
+    if status == StatusCode::TOO_MANY_REQUESTS {
+        return Err(VenueError::http(VENUE, status, body));
+    }
+
+    let error: Value = serde_json::from_str(body).unwrap_or_default();
+    return match (error["code"].as_i64(), error["msg"].as_str()) {
+        (Some(code), Some(message)) => Err(VenueError::Exchange {
+            venue: VENUE,
+            code: ErrorCode::Other(code.to_string()),
+            message: message.to_string(),
+        }),
+        _ => Err(VenueError::http(VENUE, status, body)),
+    };
+}
@@ -5,11 +5,5 @@ mod orders;
 
 pub use crate::bluefin::client::client::BluefinClient;
 pub use crate::bluefin::models::UserPosition;
-pub use crate::bluefin::models::OrderSettlementUpdate;
 pub use crate::bluefin::models::TradeOrderUpdate;
-pub use crate::bluefin::models::OrderUpdate;
-pub use crate::bluefin::models::AccountData;
-pub use crate::bluefin::models::AccountUpdateEventData;
-pub use crate::bluefin::models::parse_user_position;
-pub use crate::bluefin::models::parse_order_update;
-pub use crate::bluefin::models::parse_order_settlement_update;
\ No newline at end of file
+pub use crate::bluefin::models::BluefinPrivateEvent;
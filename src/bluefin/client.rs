@@ -1,7 +1,9 @@
 #![allow(dead_code)]
 pub mod client {
-    use std::net::TcpStream;
-    use tungstenite::connect;
+    use futures_util::{SinkExt, StreamExt};
+    use tokio::net::TcpStream;
+    use tokio_tungstenite::tungstenite::Message;
+    use tokio_tungstenite::{connect_async, MaybeTlsStream, WebSocketStream};
 
     #[allow(deprecated)]
     use base64::encode;
@@ -15,12 +17,19 @@ pub mod client {
     // custom modules
     use crate::bluefin::{
         models::{
-            parse_user_position, Auth, Error, OrderUpdate, PostResponse, UserPosition, Wallet,
+            parse_user_position, Auth, Error, OrderUpdate, UserPosition, Wallet,
         },
         orders::{create_order, to_order_request, Order},
     };
-    use tungstenite::stream::MaybeTlsStream;
     use crate::bluefin::models::AccountData;
+    use crate::clients::error::{ErrorCode, VenueError};
+    use crate::clients::executor::{RequestExecutor, RetryPolicy};
+    use crate::clients::rate_limit::TokenBucket;
+    use crate::models::common::VenueKind;
+    use crate::models::units::{Price, Qty};
+    use reqwest::{RequestBuilder, StatusCode};
+
+    static VENUE: VenueKind = VenueKind::Bluefin;
 
     pub struct BluefinClient {
         wallet: Wallet,
@@ -28,7 +37,7 @@ pub mod client {
         onboarding_url: String,
         websocket_url: String,
         pub auth_token: String,
-        client: reqwest::blocking::Client,
+        executor: RequestExecutor,
         leverage: u128,
         markets: HashMap<String, String>,
     }
@@ -81,39 +90,73 @@ pub mod client {
 
     impl BluefinClient {
         // creates client object, on-boards the user and fetches market ids
-        pub fn new(
+        pub async fn new(
+            wallet_key: &str,
+            api_gateway: &str,
+            onboarding_url: &str,
+            websocket_url: &str,
+            leverage: u128,
+        ) -> Result<BluefinClient, VenueError> {
+            return BluefinClient::with_executor(
+                RequestExecutor::for_venue(VenueKind::Bluefin, reqwest::Client::new()),
+                wallet_key,
+                api_gateway,
+                onboarding_url,
+                websocket_url,
+                leverage,
+            )
+            .await;
+        }
+
+        // same as `new` but sends its requests through a shared executor, connection pool and request budget
+        pub async fn with_executor(
+            executor: RequestExecutor,
             wallet_key: &str,
             api_gateway: &str,
             onboarding_url: &str,
             websocket_url: &str,
             leverage: u128,
-        ) -> BluefinClient {
+        ) -> Result<BluefinClient, VenueError> {
             let mut client = BluefinClient {
                 wallet: create_wallet(wallet_key),
                 api_gateway: api_gateway.to_string(),
                 onboarding_url: onboarding_url.to_string(),
                 websocket_url: websocket_url.to_string(),
                 auth_token: "".to_string(),
-                client: reqwest::blocking::Client::new(),
+                executor,
                 markets: HashMap::new(),
                 leverage,
             };
 
             // on-boards user on exchange
-            client.onboard();
+            client.onboard().await?;
 
             // get market ids from dapi
-            client.fetch_markets();
+            client.fetch_markets().await?;
 
             tracing::info!(
                 "Bluefin client initialized for wallet: {}",
                 client.wallet.address
             );
 
-            return client;
+            return Ok(client);
+        }
+
+        async fn send<B>(&self, policy: &RetryPolicy, build: B) -> Result<Value, VenueError>
+        where
+            B: Fn(&reqwest::Client) -> RequestBuilder,
+        {
+            return self.executor.execute(policy, build, parse_response).await;
         }
 
-        pub fn onboard(&mut self) {
+        /**
+         * Exposes the request budget so callers can check whether a burst of calls fits
+         */
+        pub fn budget(&self) -> &TokenBucket {
+            return self.executor.budget();
+        }
+
+        pub async fn onboard(&mut self) -> Result<(), VenueError> {
             let mut msg_dict = HashMap::new();
             msg_dict.insert("onboardingUrl", self.onboarding_url.clone());
 
@@ -138,80 +181,56 @@ pub mod client {
             body.insert("userAddress", self.wallet.address.clone());
             body.insert("isTermAccepted", "True".to_string());
 
-            let res = self
-                .client
-                .post(format!("{}/authorize", self.api_gateway))
-                .json(&body)
-                .send()
-                .unwrap()
-                .text()
-                .unwrap();
-
-            let response: Value = serde_json::from_str(&res).unwrap();
-            if response["error"].is_object() {
-                let error: Error = serde_json::from_str(&response["error"].to_string()).unwrap();
-                tracing::error!(
-                    "Error while onboarding - code: {}, message: {}",
-                    error.code,
-                    error.message
-                );
-                panic!()
-            }
+            let value = self
+                .send(&RetryPolicy::idempotent(), |http: &reqwest::Client| {
+                    http
+                        .post(format!("{}/authorize", self.api_gateway))
+                        .json(&body)
+                })
+                .await?;
 
-            let auth: Auth = serde_json::from_str(&res).unwrap();
+            let auth: Auth = VenueError::decode(VENUE, value)?;
             self.auth_token = auth.token;
+            return Ok(());
         }
 
-        pub fn fetch_markets(&mut self) {
+        pub async fn fetch_markets(&mut self) -> Result<(), VenueError> {
             let markets = ["ETH-PERP", "BTC-PERP"];
             for market in markets.iter() {
-                let res = self
-                    .client
-                    .get(format!("{}/meta?symbol={}", self.api_gateway, market))
-                    .send()
-                    .unwrap()
-                    .text()
-                    .unwrap();
-
-                let v: Value = serde_json::from_str(&res).expect("JSON Decoding failed");
-                let v1: Value = serde_json::from_str(&v["perpetualAddress"].to_string())
-                    .expect("JSON Decoding failed");
-                let market_id_value: Value =
-                    serde_json::from_str(&v1["id"].to_string()).expect("JSON Decoding failed");
-                let market_id = market_id_value.as_str().unwrap();
-
-                self.markets
-                    .insert(market.to_string(), market_id.to_string());
+                let v = self
+                    .send(&RetryPolicy::idempotent(), |http: &reqwest::Client| {
+                        http.get(format!("{}/meta?symbol={}", self.api_gateway, market))
+                    })
+                    .await?;
+
+                let market_id: String =
+                    VenueError::decode(VENUE, v["perpetualAddress"]["id"].clone())?;
+
+                self.markets.insert(market.to_string(), market_id);
             } // end of for loop
+            return Ok(());
         }
 
-        pub fn get_user_position(&self, market: &str) -> UserPosition {
+        pub async fn get_user_position(&self, market: &str) -> Result<UserPosition, VenueError> {
             let query = vec![("symbol", market)];
 
-            let res = self
-                .client
-                .get(format!("{}/userPosition", self.api_gateway))
-                .header(
-                    "Authorization",
-                    format!("Bearer {}", &self.auth_token.to_owned()),
-                )
-                .query(&query)
-                .send()
-                .unwrap()
-                .text()
-                .unwrap();
-
-            // let resp = serde_json::from_str(&res).unwrap();
-
-            let position: Value = serde_json::from_str(&res).expect("JSON Decoding failed");
+            let position = self
+                .send(&RetryPolicy::idempotent(), |http: &reqwest::Client| {
+                    http
+                        .get(format!("{}/userPosition", self.api_gateway))
+                        .header(
+                            "Authorization",
+                            format!("Bearer {}", &self.auth_token.to_owned()),
+                        )
+                        .query(&query)
+                })
+                .await?;
 
             // if user address key does not exist, implies that the user has no position
-            // assuming the get call did not throw error
-            // TODO check for error as well
             if position["userAddress"].is_string() {
-                return parse_user_position(position);
+                return Ok(parse_user_position(position));
             } else {
-                return UserPosition {
+                return Ok(UserPosition {
                     symbol: market.to_string(),
                     side: false,
                     quantity: 0,
@@ -219,27 +238,23 @@ pub mod client {
                     margin: 0,
                     leverage: 0,
                     unrealized_profit: 0
-                };
+                });
             };
         }
 
-        pub fn get_user_account(&self) -> AccountData {
-
-            let res = self
-                .client
-                .get(format!("{}/account", self.api_gateway))
-                .header(
-                    "Authorization",
-                    format!("Bearer {}", &self.auth_token.to_owned()),
-                )
-                .send()
-                .unwrap()
-                .text()
-                .unwrap();
-
-            let account: AccountData = serde_json::from_str(&res).expect("JSON Decoding failed");
-
-            account
+        pub async fn get_user_account(&self) -> Result<AccountData, VenueError> {
+            let account = self
+                .send(&RetryPolicy::idempotent(), |http: &reqwest::Client| {
+                    http
+                        .get(format!("{}/account", self.api_gateway))
+                        .header(
+                            "Authorization",
+                            format!("Bearer {}", &self.auth_token.to_owned()),
+                        )
+                })
+                .await?;
+
+            return VenueError::decode(VENUE, account);
         }
 
         pub fn create_limit_ioc_order(
@@ -247,8 +262,8 @@ pub mod client {
             market: &str,
             is_buy: bool,
             reduce_only: bool,
-            price: f64,
-            quantity: f64,
+            price: Price,
+            quantity: Qty,
             leverage: Option<u128>,
         ) -> Order {
             // assuming market will exist in markets map
@@ -274,7 +289,7 @@ pub mod client {
             market: &str,
             is_buy: bool,
             reduce_only: bool,
-            quantity: f64,
+            quantity: Qty,
             leverage: Option<u128>,
         ) -> Order {
             // assuming market will exist in markets map
@@ -308,43 +323,36 @@ pub mod client {
             return order_signature;
         }
 
-        pub fn post_signed_order(&self, order: Order, signature: String) -> PostResponse {
+        pub async fn post_signed_order(&self, order: Order, signature: String) -> Result<(), VenueError> {
             let order_request = to_order_request(order, signature);
 
-            let res = self
-                .client
-                .post(format!("{}/orders", self.api_gateway))
-                .header(
-                    "Authorization",
-                    format!("Bearer {}", &self.auth_token.to_owned()),
-                )
-                .json(&order_request)
-                .send()
-                .unwrap()
-                .text()
-                .unwrap();
-
-            tracing::debug!("Response: {}", res);
-
-            let response: Value = serde_json::from_str(&res).expect("JSON Decoding failed");
-
-            if response["error"].is_object() {
-                let error: Error = serde_json::from_str(&response["error"].to_string()).unwrap();
-                tracing::warn!("Order placement failed: {}", error.message);
-                return PostResponse { error: Some(error) };
-            } else {
-                return PostResponse { error: None };
+            let result = self
+                .send(&RetryPolicy::orders(), |http: &reqwest::Client| {
+                    http
+                        .post(format!("{}/orders", self.api_gateway))
+                        .header(
+                            "Authorization",
+                            format!("Bearer {}", &self.auth_token.to_owned()),
+                        )
+                        .json(&order_request)
+                })
+                .await;
+
+            if let Err(e) = &result {
+                tracing::warn!("Order placement failed: {}", e);
             }
+            return result.map(|_| ());
         }
 
-        pub fn listen_to_web_socket(&self) {
+        pub async fn listen_to_web_socket(&self) {
             // helper function to connect with websocket
-            fn connect_socket(
+            async fn connect_socket(
                 url: String,
                 auth_token: String,
-            ) -> tungstenite::WebSocket<MaybeTlsStream<TcpStream>> {
-                let (mut bluefin_socket, _) =
-                    connect(url::Url::parse(url.as_str()).unwrap()).expect("Failed to connect");
+            ) -> WebSocketStream<MaybeTlsStream<TcpStream>> {
+                let (mut bluefin_socket, _) = connect_async(url::Url::parse(url.as_str()).unwrap())
+                    .await
+                    .expect("Failed to connect");
                 tracing::info!("Connected to Bluefin stream at url:{}.", &url);
 
                 let request = json!([
@@ -359,9 +367,7 @@ pub mod client {
                 ]);
 
                 // Send message
-                if let Err(err) =
-                    bluefin_socket.send(tungstenite::protocol::Message::Text(request.to_string()))
-                {
+                if let Err(err) = bluefin_socket.send(Message::Text(request.to_string())).await {
                     tracing::error!("Error subscribing to user updates: {err:?}");
                 } else {
                     tracing::info!("Subscribed to user update room");
@@ -371,15 +377,15 @@ pub mod client {
             }
 
             let mut bluefin_socket =
-                connect_socket(self.websocket_url.clone(), self.auth_token.clone());
+                connect_socket(self.websocket_url.clone(), self.auth_token.clone()).await;
 
             loop {
-                let read = bluefin_socket.read();
+                let read = bluefin_socket.next().await;
 
                 match read {
-                    Ok(message) => {
+                    Some(Ok(message)) => {
                         let msg = match message {
-                            tungstenite::Message::Text(s) => s,
+                            Message::Text(s) => s,
                             _ => "Error handling message".to_string(),
                         };
 
@@ -388,8 +394,6 @@ pub mod client {
                             continue;
                         }
 
-                        // println!("{}", msg);
-
                         if msg.contains("OrderUpdate") {
                             let v: Value = serde_json::from_str(&msg).unwrap();
                             let order_update: OrderUpdate =
@@ -405,16 +409,45 @@ pub mod client {
                         };
                     }
 
-                    Err(e) => {
-                        tracing::warn!("Error during message handling: {:?}", e);
+                    other => {
+                        tracing::warn!("Error during message handling: {:?}", other);
                         bluefin_socket =
                             connect_socket(self.websocket_url.clone(), self.auth_token.clone())
+                                .await
                     }
                 }
             }
         }
     }
 
+    /**
+     * Bluefin reports rejections in an `error` object, those are turned into exchange errors
+     */
+    fn parse_response(status: StatusCode, body: &str) -> Result<Value, VenueError> {
+        tracing::debug!("Response: {}", body);
+
+        let value: Value = match serde_json::from_str(body) {
+            Ok(value) => value,
+            Err(_) if !status.is_success() => return Err(VenueError::http(VENUE, status, body)),
+            Err(source) => return Err(VenueError::Decode { venue: VENUE, source }),
+        };
+
+        if value["error"].is_object() {
+            let error: Error = VenueError::decode(VENUE, value["error"].clone())?;
+            return Err(VenueError::Exchange {
+                venue: VENUE,
+                code: ErrorCode::from_bluefin(error.code),
+                message: error.message,
+            });
+        }
+
+        if !status.is_success() {
+            return Err(VenueError::http(VENUE, status, body));
+        }
+
+        return Ok(value);
+    }
+
     #[test]
     fn should_create_wallet() {
         let wallet =
@@ -425,15 +458,17 @@ pub mod client {
         );
     }
 
-    #[test]
-    fn should_create_bluefin_client() {
+    #[tokio::test]
+    async fn should_create_bluefin_client() {
         let bluefin_client = BluefinClient::new(
             "c501312ca9eb1aaac6344edbe160e41d3d8d79570e6440f2a84f7d9abf462270",
             "https://dapi.api.sui-staging.bluefin.io",
             "https://testnet.bluefin.io",
             "wss://notifications.api.sui-staging.bluefin.io",
             1,
-        );
+        )
+        .await
+        .expect("Could not create bluefin client");
 
         assert_eq!(
             bluefin_client.wallet.address,
@@ -457,33 +492,39 @@ pub mod client {
         );
     }
 
-    #[test]
-
-    fn should_get_user_position_on_bluefin() {
+    #[tokio::test]
+    async fn should_get_user_position_on_bluefin() {
         let bluefin_client = BluefinClient::new(
             "c501312ca9eb1aaac6344edbe160e41d3d8d79570e6440f2a84f7d9abf462270",
             "https://dapi.api.sui-staging.bluefin.io",
             "https://testnet.bluefin.io",
             "wss://notifications.api.sui-staging.bluefin.io",
             1,
-        );
-
-        let position: UserPosition = bluefin_client.get_user_position("ETH-PERP");
+        )
+        .await
+        .expect("Could not create bluefin client");
+
+        let position: UserPosition = bluefin_client
+            .get_user_position("ETH-PERP")
+            .await
+            .expect("Could not get position");
         assert_eq!(position.quantity, 0);
     }
 
-    #[test]
-    fn should_create_limit_ioc_order() {
+    #[tokio::test]
+    async fn should_create_limit_ioc_order() {
         let bluefin_client = BluefinClient::new(
             "c501312ca9eb1aaac6344edbe160e41d3d8d79570e6440f2a84f7d9abf462270",
             "https://dapi.api.sui-staging.bluefin.io",
             "https://testnet.bluefin.io",
             "wss://notifications.api.sui-staging.bluefin.io",
             1,
-        );
+        )
+        .await
+        .expect("Could not create bluefin client");
 
         let order =
-            bluefin_client.create_limit_ioc_order("ETH-PERP", true, false, 1600.1, 0.33, Some(1));
+            bluefin_client.create_limit_ioc_order("ETH-PERP", true, false, "1600.1".parse().unwrap(), "0.33".parse().unwrap(), Some(1));
         // println!("{:#?}", order);
         assert_eq!(order.orderType, "LIMIT");
         assert_eq!(order.timeInForce, "IOC");
@@ -493,85 +534,96 @@ pub mod client {
         assert_eq!(order.leverage, 1000000000000000000);
     }
 
-    #[test]
-    fn should_create_signed_order() {
+    #[tokio::test]
+    async fn should_create_signed_order() {
         let bluefin_client = BluefinClient::new(
             "c501312ca9eb1aaac6344edbe160e41d3d8d79570e6440f2a84f7d9abf462270",
             "https://dapi.api.sui-staging.bluefin.io",
             "https://testnet.bluefin.io",
             "wss://notifications.api.sui-staging.bluefin.io",
             1,
-        );
+        )
+        .await
+        .expect("Could not create bluefin client");
 
         let order =
-            bluefin_client.create_limit_ioc_order("ETH-PERP", true, false, 1600.0, 0.33, Some(1));
+            bluefin_client.create_limit_ioc_order("ETH-PERP", true, false, "1600.0".parse().unwrap(), "0.33".parse().unwrap(), Some(1));
         let _signature = bluefin_client.sign_order(order);
     }
 
-    #[test]
-    fn should_revert_when_placing_order_due_to_insufficient_balance() {
+    #[tokio::test]
+    async fn should_revert_when_placing_order_due_to_insufficient_balance() {
         let bluefin_client = BluefinClient::new(
             "c501312ca9eb1aaac6344edbe160e41d3d8d79570e6440f2a84f7d9abf462270",
             "https://dapi.api.sui-staging.bluefin.io",
             "https://testnet.bluefin.io",
             "wss://notifications.api.sui-staging.bluefin.io",
             1,
-        );
+        )
+        .await
+        .expect("Could not create bluefin client");
 
         let order =
-            bluefin_client.create_limit_ioc_order("ETH-PERP", true, false, 1600.67, 10.0, Some(1));
+            bluefin_client.create_limit_ioc_order("ETH-PERP", true, false, "1600.67".parse().unwrap(), "10.0".parse().unwrap(), Some(1));
 
         let signature = bluefin_client.sign_order(order.clone());
-        let status = bluefin_client.post_signed_order(order.clone(), signature);
-        assert_eq!(status.error.unwrap().code, 3055);
+        let status = bluefin_client.post_signed_order(order.clone(), signature).await;
+        let error = status.expect_err("Order should have been rejected");
+        assert_eq!(error.code(), Some(&ErrorCode::InsufficientBalance));
     }
 
-    #[test]
-    fn should_place_order_successfully() {
+    #[tokio::test]
+    async fn should_place_order_successfully() {
         let bluefin_client = BluefinClient::new(
             "c501312ca9eb1aaac6344edbe160e41d3d8d79570e6440f2a84f7d9abf462270",
             "https://dapi.api.sui-staging.bluefin.io",
             "https://testnet.bluefin.io",
             "wss://notifications.api.sui-staging.bluefin.io",
             3,
-        );
+        )
+        .await
+        .expect("Could not create bluefin client");
 
         let order =
-            bluefin_client.create_limit_ioc_order("ETH-PERP", true, false, 1600.67, 0.01, None);
+            bluefin_client.create_limit_ioc_order("ETH-PERP", true, false, "1600.67".parse().unwrap(), "0.01".parse().unwrap(), None);
 
         let signature = bluefin_client.sign_order(order.clone());
-        let status = bluefin_client.post_signed_order(order.clone(), signature);
+        let status = bluefin_client.post_signed_order(order.clone(), signature).await;
 
-        assert!(status.error.is_none(), "Error while placing order");
+        assert!(status.is_ok(), "Error while placing order");
     }
 
-    #[test]
-    fn should_place_a_market_order() {
+    #[tokio::test]
+    async fn should_place_a_market_order() {
         let bluefin_client = BluefinClient::new(
             "c501312ca9eb1aaac6344edbe160e41d3d8d79570e6440f2a84f7d9abf462270",
             "https://dapi.api.sui-staging.bluefin.io",
             "https://testnet.bluefin.io",
             "wss://notifications.api.sui-staging.bluefin.io",
             3,
-        );
+        )
+        .await
+        .expect("Could not create bluefin client");
 
-        let order = bluefin_client.create_market_order("ETH-PERP", false, false, 0.01, None);
+        let order = bluefin_client.create_market_order("ETH-PERP", false, false, "0.01".parse().unwrap(), None);
 
         let signature = bluefin_client.sign_order(order.clone());
-        let status = bluefin_client.post_signed_order(order.clone(), signature);
-        assert!(status.error.is_none(), "Error while placing order");
+        let status = bluefin_client.post_signed_order(order.clone(), signature).await;
+        assert!(status.is_ok(), "Error while placing order");
     }
 
-    #[test]
-    fn should_connect_bluefin_websocket() {
+    #[tokio::test]
+    async fn should_connect_bluefin_websocket() {
         let bluefin_client = BluefinClient::new(
             "c501312ca9eb1aaac6344edbe160e41d3d8d79570e6440f2a84f7d9abf462270",
             "https://dapi.api.sui-staging.bluefin.io",
             "https://testnet.bluefin.io",
             "wss://notifications.api.sui-staging.bluefin.io",
             1,
-        );
+        )
+        .await
+        .expect("Could not create bluefin client");
 
-        let _ = bluefin_client.listen_to_web_socket();
+        bluefin_client.listen_to_web_socket().await;
     }
 }
@@ -14,11 +14,6 @@ pub struct Error {
     pub message: String,
 }
 
-#[derive(Deserialize, Debug)]
-pub struct PostResponse {
-    pub error: Option<Error>,
-}
-
 #[derive(Deserialize, Debug)]
 pub struct UserPosition {
     pub symbol: String,
@@ -226,4 +221,49 @@ pub struct MarketData {
     pub expected_pnl: f64,
     #[serde(deserialize_with = "deserialize_to_f64_via_decimal")]
     pub selected_leverage: f64,
-}
\ No newline at end of file
+}
+/**
+ * Events of the userUpdates room, parsed once from the `eventName` of the frame
+ */
+#[derive(Debug)]
+pub enum BluefinPrivateEvent {
+    OrderSettlementUpdate(OrderSettlementUpdate),
+    OrderUpdate(OrderUpdate),
+    PositionUpdate(UserPosition),
+    UserTrade(TradeOrderUpdate),
+    AccountDataUpdate(AccountData),
+}
+
+impl BluefinPrivateEvent {
+    /**
+     * Returns None for events the bot does not use
+     */
+    pub fn parse(text: &str) -> Option<BluefinPrivateEvent> {
+        let v: Value = serde_json::from_str(text).ok()?;
+        let event = match v["eventName"].as_str()? {
+            "OrderSettlementUpdate" => {
+                BluefinPrivateEvent::OrderSettlementUpdate(parse_order_settlement_update(v["data"].clone()))
+            }
+            "OrderUpdate" => BluefinPrivateEvent::OrderUpdate(parse_order_update(v["data"]["order"].clone())),
+            "PositionUpdate" => BluefinPrivateEvent::PositionUpdate(parse_user_position(v["data"]["position"].clone())),
+            "UserTrade" => BluefinPrivateEvent::UserTrade(parse_user_trade_order_update(v["data"]["trade"].clone())),
+            "AccountDataUpdate" => {
+                let update: AccountUpdateEventData = serde_json::from_value(v).ok()?;
+                BluefinPrivateEvent::AccountDataUpdate(update.data.account_data)
+            }
+            _ => return None,
+        };
+        return Some(event);
+    }
+
+    // market of the event, account data is account wide
+    pub fn symbol(&self) -> Option<&str> {
+        return match self {
+            BluefinPrivateEvent::OrderSettlementUpdate(update) => Some(&update.symbol),
+            BluefinPrivateEvent::OrderUpdate(update) => Some(&update.symbol),
+            BluefinPrivateEvent::PositionUpdate(position) => Some(&position.symbol),
+            BluefinPrivateEvent::UserTrade(trade) => Some(&trade.symbol),
+            BluefinPrivateEvent::AccountDataUpdate(_) => None,
+        };
+    }
+}
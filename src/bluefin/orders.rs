@@ -3,6 +3,7 @@ use serde::{Deserialize, Serialize};
 use sha256::digest;
 use web3_unit_converter::Unit;
 
+use crate::models::units::{Price, Qty};
 use crate::utils;
 #[derive(Debug, Clone)]
 #[allow(non_snake_case)]
@@ -147,27 +148,20 @@ pub fn create_order(
     market_id: String,
     is_buy: bool,
     reduce_only: bool,
-    price: Option<f64>,
-    quantity: f64,
+    price: Option<Price>,
+    quantity: Qty,
     leverage: u128,
 ) -> Order {
-    let (order_price, order_type) = if price.is_some() {
-        (price.unwrap(), "LIMIT")
-    } else {
-        (0.0, "MARKET")
+    let (order_price, order_type) = match price {
+        Some(price) => (price.to_e18().expect("Order price out of range"), "LIMIT"),
+        None => (0, "MARKET"),
     };
 
     let mut order = Order {
         market: market_name,
         isBuy: is_buy,
-        price: (Unit::Ether(&format!("{}", order_price))
-            .to_wei_str()
-            .unwrap())
-        .parse()
-        .unwrap(),
-        quantity: (Unit::Ether(&format!("{}", quantity)).to_wei_str().unwrap())
-            .parse()
-            .unwrap(),
+        price: order_price,
+        quantity: quantity.to_e18().expect("Order quantity out of range"),
         leverage: (Unit::Ether(&format!("{}", leverage)).to_wei_str().unwrap())
             .parse()
             .unwrap(),
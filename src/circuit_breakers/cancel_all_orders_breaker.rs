@@ -40,6 +40,7 @@ mod test {
     use std::sync::mpsc;
     use std::thread;
     use crate::circuit_breakers::kucoin_breaker::KuCoinBreaker;
+    use crate::env::EnvVars;
     use crate::models::common::CircuitBreakerConfig;
 
     #[test]
@@ -60,7 +61,7 @@ mod test {
                 },
                 num_failures: 0,
                 state: State::Closed,
-                kucoin_breaker: KuCoinBreaker::new("Kucoin Breaker for Test Breaker".to_string()),
+                kucoin_breaker: KuCoinBreaker::new("Kucoin Breaker for Test Breaker".to_string(), &EnvVars::for_tests()),
                 market: "ETH-PERP".to_string(),
             }
         };
@@ -110,7 +111,7 @@ mod test {
                 },
                 num_failures: 0,
                 state: State::Closed,
-                kucoin_breaker: KuCoinBreaker::new("Kucoin Breaker for Test Breaker".to_string()),
+                kucoin_breaker: KuCoinBreaker::new("Kucoin Breaker for Test Breaker".to_string(), &EnvVars::for_tests()),
                 market: "ETH-PERP".to_string(),
             }
         };
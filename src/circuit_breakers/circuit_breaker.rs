@@ -22,4 +22,11 @@ pub trait CircuitBreaker {
     fn open(&mut self) -> bool;
 
     fn is_open(&self) -> bool;
+
+    // A disconnected feed never recovers, so fail until the breaker opens
+    fn trip(&mut self) {
+        while !self.is_open() {
+            self.on_failure();
+        }
+    }
 }
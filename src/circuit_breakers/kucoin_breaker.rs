@@ -1,57 +1,29 @@
+use std::sync::Arc;
 use std::time::Duration;
-use tokio::time::Instant;
-use crate::env;
+use crate::clients::executor::{retry, RetryOn, RetryPolicy};
+use crate::clients::registry::clients;
+use crate::clients::runtime::block_on;
 use crate::env::EnvVars;
-use crate::kucoin::{Credentials, KuCoinClient};
+use crate::kucoin::KuCoinClient;
 use crate::models::common::CircuitBreakerConfig;
 
+// upper bound of the backoff between cancel attempts
+static MAX_RETRY_DELAY: Duration = Duration::from_secs(5);
+
 
 pub struct KuCoinBreaker {
     pub name: String,
-    pub client: KuCoinClient,
-    pub env_vars: EnvVars
+    pub client: Arc<KuCoinClient>,
+    pub retry_period: u64,
 }
 
 impl KuCoinBreaker {
-
-    fn client(
-        kucoin_api_key: &str,
-        kucoin_api_secret: &str,
-        kucoin_api_phrase: &str,
-        kucoin_endpoint: &str,
-        kucoin_on_boarding_url: &str,
-        kucoin_websocket_url: &str,
-        kucoin_leverage: u128) -> KuCoinClient {
-
-        KuCoinClient::new(
-            Credentials::new(
-                kucoin_api_key,
-                kucoin_api_secret,
-                kucoin_api_phrase,
-            ),
-            kucoin_endpoint,
-            kucoin_on_boarding_url,
-            kucoin_websocket_url,
-            kucoin_leverage,
-        )
-
-    }
-
-    pub fn new(name: String) -> KuCoinBreaker {
-        let vars= env::env_variables();
+    pub fn new(name: String, vars: &EnvVars) -> KuCoinBreaker {
         tracing::info!("Initializing Kucoin Client for Breaker: {}", &name);
         KuCoinBreaker {
             name,
-            client: KuCoinBreaker::client(
-                &vars.kucoin_api_key,
-                &vars.kucoin_api_secret,
-                &vars.kucoin_api_phrase,
-                &vars.kucoin_endpoint,
-                &vars.kucoin_on_boarding_url,
-                &vars.kucoin_websocket_url,
-                vars.kucoin_leverage,
-            ),
-            env_vars: vars
+            client: clients(vars).kucoin(),
+            retry_period: vars.market_making_time_throttle_period,
         }
 
     }
@@ -62,32 +34,32 @@ impl KuCoinBreaker {
             return true;
         }
 
-        let mut resp = self.client.cancel_all_orders(Some(market.as_str()));
-
-        let mut retry: u8 = 0;
-        let mut last_retry = Instant::now();
+        // the client retries transient failures itself, the breaker insists on any failure
+        let policy = RetryPolicy {
+            max_attempts: cb_config.num_retries as u32 + 1,
+            base_delay: Duration::from_millis(self.retry_period),
+            max_delay: MAX_RETRY_DELAY,
+            retry_on: RetryOn::Any,
+        };
+        let resp = block_on(retry(&policy, || self.client.cancel_all_orders(Some(market.as_str()))));
 
-        while retry < cb_config.num_retries && resp.error.is_some() {
-            if last_retry.elapsed() >= Duration::from_millis(self.env_vars.market_making_time_throttle_period) {
-                tracing::warn!("KuCoin cancel all orders failed. Retrying {} times, current retry: {}", cb_config.num_retries, retry + 1);
-                resp = self.client.cancel_all_orders(Some(market.as_str()));
-                retry += 1;
-                last_retry = Instant::now();
+        return match resp {
+            Ok(()) => {
+                tracing::info!("Successfully cancelled all orders after Circuit Breaker opened");
+                true
             }
-        }
-        return if retry == cb_config.num_retries {
-            tracing::warn!("Retry number exceeded. Could not cancel orders on KuCoin after Circuit Breaker opened.");
-            false
-        } else {
-            tracing::info!("Successfully cancelled all orders after Circuit Breaker opened");
-            true
-        }
+            Err(e) => {
+                tracing::warn!("Retry number exceeded. Could not cancel orders on KuCoin after Circuit Breaker opened: {}", e);
+                false
+            }
+        };
     }
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::env;
 
     #[test]
     //WILL CANCEL ACTUAL ORDERS ON ETH
@@ -97,16 +69,8 @@ mod test {
 
         let mut breaker = KuCoinBreaker {
             name: "Test Breaker".to_string(),
-            client: KuCoinBreaker::client(
-                &vars.kucoin_api_key,
-                &vars.kucoin_api_secret,
-                &vars.kucoin_api_phrase,
-                &vars.kucoin_endpoint,
-                &vars.kucoin_on_boarding_url,
-                &vars.kucoin_websocket_url,
-                vars.kucoin_leverage,
-            ),
-            env_vars: vars,
+            client: clients(&vars).kucoin(),
+            retry_period: vars.market_making_time_throttle_period,
         };
         let config = CircuitBreakerConfig {
             num_retries: 3,
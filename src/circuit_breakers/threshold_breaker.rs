@@ -2,6 +2,7 @@ use chrono::Duration;
 use chrono::Utc;
 use chrono::DateTime;
 
+use crate::env::EnvVars;
 use crate::models::common::CircuitBreakerConfig;
 
 use super::kucoin_breaker::KuCoinBreaker;
@@ -28,12 +29,12 @@ pub enum ClientType {
 
 
 impl ThresholdCircuitBreaker {
-    pub fn new(name: String, config: CircuitBreakerConfig) -> ThresholdCircuitBreaker {
+    pub fn new(name: String, config: CircuitBreakerConfig, vars: &EnvVars) -> ThresholdCircuitBreaker {
         tracing::info!("Creating Circuit Breaker {} for User Account Balance...", name);
         ThresholdCircuitBreaker {
             name: name.clone(),
             config,
-            kucoin_breaker: KuCoinBreaker::new(format!("Kucoin Breaker for {}", name)),
+            kucoin_breaker: KuCoinBreaker::new(format!("Kucoin Breaker for {}", name), vars),
             kucoin_balance_stack: vec![],
             bluefin_balance_stack: vec![],
 
@@ -47,6 +48,10 @@ impl ThresholdCircuitBreaker {
         }
     }
 
+    pub fn set_config(&mut self, config: CircuitBreakerConfig) {
+        self.config = config;
+    }
+
     pub fn push_bluefin_commission(&mut self, commission: u128) {
         self.cumulative_bluefin_commission += commission;
     }
@@ -124,7 +129,7 @@ mod test {
             failure_threshold: 3,
             loss_threshold_bps: 3.0,
         };
-        let breaker = ThresholdCircuitBreaker::new("Test Breaker".to_string(), config);
+        let breaker = ThresholdCircuitBreaker::new("Test Breaker".to_string(), config, &EnvVars::for_tests());
 
         (config, breaker)
     }
@@ -0,0 +1,5 @@
+pub mod error;
+pub mod executor;
+pub mod rate_limit;
+pub mod registry;
+pub mod runtime;
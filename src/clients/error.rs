@@ -0,0 +1,158 @@
+use std::fmt;
+
+use reqwest::StatusCode;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use thiserror::Error;
+
+use crate::models::common::VenueKind;
+
+// longest part of an unexpected response body kept in the error, 502 pages are html
+static MAX_BODY_LEN: usize = 256;
+
+/**
+ * Exchange error codes the bot reacts to. KuCoin codes are listed in the README,
+ * anything else is kept verbatim
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ErrorCode {
+    // KuCoin 300003, Bluefin 3055
+    InsufficientBalance,
+    // KuCoin 300012, the price is outside of the allowed band
+    MarketBoundExceeded,
+    // KuCoin 100004, the order was filled already or does not exist
+    OrderNotFound,
+    // KuCoin 429000
+    RateLimited,
+    Other(String),
+}
+
+impl ErrorCode {
+    pub fn from_kucoin(code: &str) -> ErrorCode {
+        return match code {
+            "300003" => ErrorCode::InsufficientBalance,
+            "300012" => ErrorCode::MarketBoundExceeded,
+            "100004" => ErrorCode::OrderNotFound,
+            "429000" => ErrorCode::RateLimited,
+            other => ErrorCode::Other(other.to_string()),
+        };
+    }
+
+    pub fn from_bluefin(code: u64) -> ErrorCode {
+        return match code {
+            3055 => ErrorCode::InsufficientBalance,
+            other => ErrorCode::Other(other.to_string()),
+        };
+    }
+}
+
+impl fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return match self {
+            ErrorCode::Other(code) => write!(f, "code {}", code),
+            known => write!(f, "{:?}", known),
+        };
+    }
+}
+
+/**
+ * Failure of a REST call to a venue
+ */
+#[derive(Error, Debug)]
+pub enum VenueError {
+    #[error("{venue:?} request failed: {source}")]
+    Transport {
+        venue: VenueKind,
+        source: reqwest::Error,
+    },
+    #[error("{venue:?} responded with HTTP {status}: {body}")]
+    Http {
+        venue: VenueKind,
+        status: StatusCode,
+        body: String,
+    },
+    #[error("{venue:?} rejected the request with {code}: {message}")]
+    Exchange {
+        venue: VenueKind,
+        code: ErrorCode,
+        message: String,
+    },
+    #[error("could not decode {venue:?} response: {source}")]
+    Decode {
+        venue: VenueKind,
+        source: serde_json::Error,
+    },
+}
+
+impl VenueError {
+    pub fn transport(venue: VenueKind) -> impl Fn(reqwest::Error) -> VenueError {
+        return move |source| VenueError::Transport { venue, source };
+    }
+
+    pub fn http(venue: VenueKind, status: StatusCode, body: &str) -> VenueError {
+        return VenueError::Http {
+            venue,
+            status,
+            body: body.chars().take(MAX_BODY_LEN).collect(),
+        };
+    }
+
+    pub fn decode<T: DeserializeOwned>(venue: VenueKind, value: Value) -> Result<T, VenueError> {
+        return serde_json::from_value(value).map_err(|source| VenueError::Decode { venue, source });
+    }
+
+    pub fn code(&self) -> Option<&ErrorCode> {
+        return match self {
+            VenueError::Exchange { code, .. } => Some(code),
+            _ => None,
+        };
+    }
+
+    /**
+     * The venue turned the request away before processing it
+     */
+    pub fn is_rate_limited(&self) -> bool {
+        return match self {
+            VenueError::Http { status, .. } => *status == StatusCode::TOO_MANY_REQUESTS,
+            VenueError::Exchange { code, .. } => *code == ErrorCode::RateLimited,
+            _ => false,
+        };
+    }
+
+    /**
+     * Network failures, server errors and rate limits go away on their own,
+     * the same call can be sent again on the next cycle
+     */
+    pub fn is_transient(&self) -> bool {
+        return match self {
+            VenueError::Transport { .. } => true,
+            VenueError::Http { status, .. } => status.is_server_error() || self.is_rate_limited(),
+            VenueError::Exchange { .. } => self.is_rate_limited(),
+            VenueError::Decode { .. } => false,
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_map_exchange_codes() {
+        assert_eq!(ErrorCode::from_kucoin("300003"), ErrorCode::InsufficientBalance);
+        assert_eq!(ErrorCode::from_kucoin("100004"), ErrorCode::OrderNotFound);
+        assert_eq!(ErrorCode::from_kucoin("400100"), ErrorCode::Other("400100".to_string()));
+        assert_eq!(ErrorCode::from_bluefin(3055), ErrorCode::InsufficientBalance);
+
+        let rate_limited = VenueError::Exchange {
+            venue: VenueKind::Kucoin,
+            code: ErrorCode::from_kucoin("429000"),
+            message: "Too Many Requests".to_string(),
+        };
+        assert!(rate_limited.is_transient());
+
+        let bad_gateway = VenueError::http(VenueKind::Bluefin, StatusCode::BAD_GATEWAY, &"<html>".repeat(100));
+        assert!(bad_gateway.is_transient());
+        assert!(bad_gateway.code().is_none());
+    }
+}
@@ -0,0 +1,284 @@
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+
+use rand::Rng;
+use reqwest::header::HeaderMap;
+use reqwest::{RequestBuilder, StatusCode};
+use serde_json::Value;
+
+use crate::clients::error::VenueError;
+use crate::clients::rate_limit::{RequestCounter, TokenBucket};
+use crate::models::common::VenueKind;
+
+// milliseconds until KuCoin's rate limit window resets
+static KUCOIN_RESET_HEADER: &str = "gw-ratelimit-reset";
+// pause applied when a venue rate limits without saying for how long
+static DEFAULT_RATE_LIMIT_PAUSE: Duration = Duration::from_secs(1);
+
+// Failures a policy sends the request again for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RetryOn {
+    // see `VenueError::is_transient`
+    Transient,
+    // only requests the venue turned away before processing them
+    RateLimited,
+    Any,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    pub max_attempts: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+    pub retry_on: RetryOn,
+}
+
+impl RetryPolicy {
+    /**
+     * Reads and cancels, sending them twice does no harm
+     */
+    pub fn idempotent() -> RetryPolicy {
+        return RetryPolicy {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(200),
+            max_delay: Duration::from_secs(2),
+            retry_on: RetryOn::Transient,
+        };
+    }
+
+    /**
+     * Order placement. A timed out order might be resting on the book already,
+     * so only orders the venue rate limited are sent again
+     */
+    pub fn orders() -> RetryPolicy {
+        return RetryPolicy {
+            max_attempts: 2,
+            base_delay: Duration::from_millis(250),
+            max_delay: Duration::from_secs(1),
+            retry_on: RetryOn::RateLimited,
+        };
+    }
+
+    pub fn should_retry(&self, error: &VenueError) -> bool {
+        return match self.retry_on {
+            RetryOn::Transient => error.is_transient(),
+            RetryOn::RateLimited => error.is_rate_limited(),
+            RetryOn::Any => true,
+        };
+    }
+
+    /**
+     * Exponential backoff before the given retry, starting at 1. The delay is jittered
+     * between half and the full value so concurrent callers do not retry in lockstep
+     */
+    pub fn delay(&self, retry: u32) -> Duration {
+        let exponential = self
+            .base_delay
+            .saturating_mul(2u32.saturating_pow(retry.saturating_sub(1)))
+            .min(self.max_delay);
+        let half = exponential / 2;
+        return half + half.mul_f64(rand::thread_rng().gen::<f64>());
+    }
+}
+
+/**
+ * Runs `op` until it succeeds, the policy gives up on the error or the attempts run out
+ */
+pub async fn retry<T, F, Fut>(policy: &RetryPolicy, mut op: F) -> Result<T, VenueError>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, VenueError>>,
+{
+    let mut attempt = 1;
+    loop {
+        match op().await {
+            Ok(value) => return Ok(value),
+            Err(e) if attempt < policy.max_attempts && policy.should_retry(&e) => {
+                let delay = policy.delay(attempt);
+                tracing::warn!(
+                    "Attempt {} of {} failed, retrying in {:?}: {}",
+                    attempt,
+                    policy.max_attempts,
+                    delay,
+                    e
+                );
+                tokio::time::sleep(delay).await;
+                attempt += 1;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/**
+ * Sends the REST requests of a venue client. Every attempt takes a token from the
+ * venue's budget, rate limit responses pause the budget for every caller until the
+ * venue's window resets, and failures are retried per the endpoint's policy
+ */
+pub struct RequestExecutor {
+    venue: VenueKind,
+    client: reqwest::Client,
+    requests: Arc<RequestCounter>,
+    budget: Arc<TokenBucket>,
+}
+
+impl RequestExecutor {
+    pub fn new(
+        venue: VenueKind,
+        client: reqwest::Client,
+        requests: Arc<RequestCounter>,
+        budget: Arc<TokenBucket>,
+    ) -> RequestExecutor {
+        return RequestExecutor {
+            venue,
+            client,
+            requests,
+            budget,
+        };
+    }
+
+    // executor with its own request counter and budget, for clients created outside the registry
+    pub fn for_venue(venue: VenueKind, client: reqwest::Client) -> RequestExecutor {
+        return RequestExecutor::new(
+            venue,
+            client,
+            Arc::new(RequestCounter::for_venue(venue)),
+            Arc::new(TokenBucket::for_venue(venue)),
+        );
+    }
+
+    pub fn budget(&self) -> &TokenBucket {
+        return &self.budget;
+    }
+
+    /**
+     * Sends the request built by `build` and decodes the response with `parse`.
+     * The request is built again for every attempt so signatures stay fresh
+     */
+    pub async fn execute<B, P>(&self, policy: &RetryPolicy, build: B, parse: P) -> Result<Value, VenueError>
+    where
+        B: Fn(&reqwest::Client) -> RequestBuilder,
+        P: Fn(StatusCode, &str) -> Result<Value, VenueError>,
+    {
+        return retry(policy, || self.send(&build, &parse)).await;
+    }
+
+    async fn send<B, P>(&self, build: &B, parse: &P) -> Result<Value, VenueError>
+    where
+        B: Fn(&reqwest::Client) -> RequestBuilder,
+        P: Fn(StatusCode, &str) -> Result<Value, VenueError>,
+    {
+        self.budget.take().await;
+        self.requests.record();
+
+        let res = build(&self.client)
+            .send()
+            .await
+            .map_err(VenueError::transport(self.venue))?;
+        let status = res.status();
+        let reset = reset_delay(res.headers());
+        let body = res.text().await.map_err(VenueError::transport(self.venue))?;
+
+        let result = parse(status, &body);
+        if let Err(e) = &result {
+            if e.is_rate_limited() {
+                let pause = reset.unwrap_or(DEFAULT_RATE_LIMIT_PAUSE);
+                tracing::warn!("{:?} rate limit hit, pausing requests for {:?}", self.venue, pause);
+                self.budget.pause(pause);
+            }
+        }
+        return result;
+    }
+}
+
+/**
+ * Time until the venue accepts requests again. Retry-After is in seconds,
+ * KuCoin's reset header in milliseconds
+ */
+fn reset_delay(headers: &HeaderMap) -> Option<Duration> {
+    if let Some(seconds) = header_number(headers, "retry-after") {
+        return Some(Duration::from_secs(seconds));
+    }
+    return header_number(headers, KUCOIN_RESET_HEADER).map(Duration::from_millis);
+}
+
+fn header_number(headers: &HeaderMap, name: &str) -> Option<u64> {
+    return headers.get(name)?.to_str().ok()?.trim().parse().ok();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clients::error::ErrorCode;
+    use reqwest::header::HeaderValue;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    fn rate_limited() -> VenueError {
+        return VenueError::Exchange {
+            venue: VenueKind::Kucoin,
+            code: ErrorCode::RateLimited,
+            message: "Too Many Requests".to_string(),
+        };
+    }
+
+    #[test]
+    fn test_delay_grows_exponentially_with_jitter() {
+        let policy = RetryPolicy {
+            max_attempts: 5,
+            base_delay: Duration::from_millis(100),
+            max_delay: Duration::from_millis(300),
+            retry_on: RetryOn::Transient,
+        };
+
+        for _ in 0..20 {
+            let first = policy.delay(1);
+            assert!(first >= Duration::from_millis(50) && first <= Duration::from_millis(100));
+            let second = policy.delay(2);
+            assert!(second >= Duration::from_millis(100) && second <= Duration::from_millis(200));
+            let capped = policy.delay(4);
+            assert!(capped >= Duration::from_millis(150) && capped <= Duration::from_millis(300));
+        }
+    }
+
+    #[test]
+    fn test_orders_are_only_retried_when_rate_limited() {
+        let bad_gateway = VenueError::http(VenueKind::Kucoin, StatusCode::BAD_GATEWAY, "");
+
+        assert!(RetryPolicy::orders().should_retry(&rate_limited()));
+        assert!(!RetryPolicy::orders().should_retry(&bad_gateway));
+        assert!(RetryPolicy::idempotent().should_retry(&bad_gateway));
+    }
+
+    #[test]
+    fn test_reset_delay_from_headers() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(reset_delay(&headers), None);
+
+        headers.insert(KUCOIN_RESET_HEADER, HeaderValue::from_static("1500"));
+        assert_eq!(reset_delay(&headers), Some(Duration::from_millis(1500)));
+
+        headers.insert("retry-after", HeaderValue::from_static("2"));
+        assert_eq!(reset_delay(&headers), Some(Duration::from_secs(2)));
+    }
+
+    #[tokio::test]
+    async fn test_retry_stops_after_max_attempts() {
+        let policy = RetryPolicy {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(1),
+            max_delay: Duration::from_millis(2),
+            retry_on: RetryOn::Transient,
+        };
+        let attempts = AtomicU32::new(0);
+
+        let result: Result<(), VenueError> = retry(&policy, || async {
+            attempts.fetch_add(1, Ordering::SeqCst);
+            Err(rate_limited())
+        })
+        .await;
+
+        assert!(result.is_err());
+        assert_eq!(attempts.load(Ordering::SeqCst), 3);
+    }
+}
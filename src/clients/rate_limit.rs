@@ -0,0 +1,234 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::models::common::VenueKind;
+
+// share of the request limit after which every request is logged as a warning
+static WARN_USAGE: f64 = 0.8;
+// request budgets of the REST apis, shared by every market
+static KUCOIN_REQUEST_LIMIT: u32 = 30;
+static KUCOIN_REQUEST_WINDOW: Duration = Duration::from_secs(3);
+static BLUEFIN_REQUEST_LIMIT: u32 = 100;
+static BLUEFIN_REQUEST_WINDOW: Duration = Duration::from_secs(10);
+// only depth snapshots are requested, each weighs 20 of the 2400 allowed per minute
+static BINANCE_REQUEST_LIMIT: u32 = 100;
+static BINANCE_REQUEST_WINDOW: Duration = Duration::from_secs(60);
+
+/**
+ * Request limit of a venue's REST api as (requests, window)
+ */
+fn limits(venue: VenueKind) -> (u32, Duration) {
+    return match venue {
+        VenueKind::Kucoin => (KUCOIN_REQUEST_LIMIT, KUCOIN_REQUEST_WINDOW),
+        VenueKind::Bluefin => (BLUEFIN_REQUEST_LIMIT, BLUEFIN_REQUEST_WINDOW),
+        VenueKind::Binance | VenueKind::BinanceSpot => (BINANCE_REQUEST_LIMIT, BINANCE_REQUEST_WINDOW),
+    };
+}
+
+struct Window {
+    started: Instant,
+    requests: u32,
+}
+
+/**
+ * Counts the REST requests sent to a venue in fixed windows so every client of
+ * the venue is accounted against the same limit
+ */
+pub struct RequestCounter {
+    venue: VenueKind,
+    limit: u32,
+    window: Duration,
+    current: Mutex<Window>,
+    total: AtomicU64,
+}
+
+impl RequestCounter {
+    pub fn new(venue: VenueKind, limit: u32, window: Duration) -> RequestCounter {
+        return RequestCounter {
+            venue,
+            limit,
+            window,
+            current: Mutex::new(Window {
+                started: Instant::now(),
+                requests: 0,
+            }),
+            total: AtomicU64::new(0),
+        };
+    }
+
+    pub fn for_venue(venue: VenueKind) -> RequestCounter {
+        let (limit, window) = limits(venue);
+        return RequestCounter::new(venue, limit, window);
+    }
+
+    /**
+     * Records a request, returns the number of requests sent in the current window
+     */
+    pub fn record(&self) -> u32 {
+        self.total.fetch_add(1, Ordering::Relaxed);
+
+        let mut current = self.current.lock().expect("Request counter lock poisoned");
+        if current.started.elapsed() >= self.window {
+            current.started = Instant::now();
+            current.requests = 0;
+        }
+        current.requests += 1;
+
+        if current.requests as f64 >= self.limit as f64 * WARN_USAGE {
+            tracing::warn!(
+                venue = ?self.venue,
+                requests = current.requests,
+                limit = self.limit,
+                "Close to the {:?} rate limit of {} requests per {:?}",
+                self.venue,
+                self.limit,
+                self.window
+            );
+        }
+
+        return current.requests;
+    }
+
+    pub fn total(&self) -> u64 {
+        return self.total.load(Ordering::Relaxed);
+    }
+}
+
+struct Bucket {
+    tokens: f64,
+    refilled: Instant,
+    paused_until: Option<Instant>,
+}
+
+/**
+ * Token bucket over a venue's request limit, refilled continuously at limit / window.
+ * Every request takes a token before it is sent and waits when the bucket is empty,
+ * callers check the remaining budget before starting a burst of calls
+ */
+pub struct TokenBucket {
+    capacity: f64,
+    refill_per_sec: f64,
+    state: Mutex<Bucket>,
+}
+
+impl TokenBucket {
+    pub fn new(capacity: u32, window: Duration) -> TokenBucket {
+        return TokenBucket {
+            capacity: capacity as f64,
+            refill_per_sec: capacity as f64 / window.as_secs_f64(),
+            state: Mutex::new(Bucket {
+                tokens: capacity as f64,
+                refilled: Instant::now(),
+                paused_until: None,
+            }),
+        };
+    }
+
+    pub fn for_venue(venue: VenueKind) -> TokenBucket {
+        let (limit, window) = limits(venue);
+        return TokenBucket::new(limit, window);
+    }
+
+    fn refill(&self, bucket: &mut Bucket, now: Instant) {
+        if let Some(until) = bucket.paused_until {
+            if now < until {
+                return;
+            }
+            bucket.paused_until = None;
+            bucket.refilled = until;
+        }
+        let elapsed = now.saturating_duration_since(bucket.refilled).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * self.refill_per_sec).min(self.capacity);
+        bucket.refilled = now;
+    }
+
+    /**
+     * Takes a token, or returns how long it takes until the next one is available
+     */
+    pub fn try_take(&self) -> Result<(), Duration> {
+        let now = Instant::now();
+        let mut bucket = self.state.lock().expect("Token bucket lock poisoned");
+        self.refill(&mut bucket, now);
+
+        if let Some(until) = bucket.paused_until {
+            return Err(until - now);
+        }
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            return Ok(());
+        }
+        return Err(Duration::from_secs_f64((1.0 - bucket.tokens) / self.refill_per_sec));
+    }
+
+    pub async fn take(&self) {
+        while let Err(wait) = self.try_take() {
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    /**
+     * Whole tokens left, zero while the venue asked us to back off
+     */
+    pub fn available(&self) -> u32 {
+        let mut bucket = self.state.lock().expect("Token bucket lock poisoned");
+        self.refill(&mut bucket, Instant::now());
+        if bucket.paused_until.is_some() {
+            return 0;
+        }
+        return bucket.tokens.min(u32::MAX as f64) as u32;
+    }
+
+    pub fn can_afford(&self, requests: u32) -> bool {
+        return self.available() >= requests;
+    }
+
+    /**
+     * Empties the bucket and holds it for `delay`, used when the venue reports that
+     * the limit was hit. Refilling starts again once the pause is over
+     */
+    pub fn pause(&self, delay: Duration) {
+        let mut bucket = self.state.lock().expect("Token bucket lock poisoned");
+        let until = Instant::now() + delay;
+        bucket.tokens = 0.0;
+        bucket.paused_until = Some(bucket.paused_until.map_or(until, |current| current.max(until)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_requests_are_counted_per_window() {
+        let counter = RequestCounter::new(VenueKind::Kucoin, 10, Duration::from_millis(50));
+
+        assert_eq!(counter.record(), 1);
+        assert_eq!(counter.record(), 2);
+
+        std::thread::sleep(Duration::from_millis(60));
+
+        assert_eq!(counter.record(), 1);
+        assert_eq!(counter.total(), 3);
+    }
+
+    #[test]
+    fn test_token_bucket_refills_and_pauses() {
+        let bucket = TokenBucket::new(2, Duration::from_millis(100));
+
+        assert!(bucket.can_afford(2));
+        assert!(bucket.try_take().is_ok());
+        assert!(bucket.try_take().is_ok());
+        assert!(bucket.try_take().is_err());
+
+        std::thread::sleep(Duration::from_millis(60));
+        assert_eq!(bucket.available(), 1);
+
+        bucket.pause(Duration::from_millis(50));
+        assert_eq!(bucket.available(), 0);
+        assert!(bucket.try_take().unwrap_err() <= Duration::from_millis(50));
+
+        std::thread::sleep(Duration::from_millis(110));
+        assert!(bucket.can_afford(1));
+    }
+}
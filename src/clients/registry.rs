@@ -0,0 +1,171 @@
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+
+use crate::binance::BinanceClient;
+use crate::bluefin::BluefinClient;
+use crate::clients::executor::RequestExecutor;
+use crate::clients::rate_limit::{RequestCounter, TokenBucket};
+use crate::clients::runtime::block_on;
+use crate::env::EnvVars;
+use crate::kucoin::{Credentials, KuCoinClient};
+use crate::models::common::VenueKind;
+use crate::sockets::bluefin_session::BluefinSession;
+use crate::sockets::kucoin_session::KucoinSession;
+
+static REGISTRY: OnceLock<ClientRegistry> = OnceLock::new();
+static HTTP_TIMEOUT: Duration = Duration::from_secs(60);
+
+/**
+ * Process wide REST clients. Every venue, breaker and stats component shares one
+ * client per venue, so there is a single connection pool, a single Bluefin
+ * onboarding, one websocket session and one request counter and budget per
+ * venue. Leverage is passed per order, the clients are built with the global defaults
+ */
+pub struct ClientRegistry {
+    vars: EnvVars,
+    http_client: reqwest::Client,
+    kucoin_requests: Arc<RequestCounter>,
+    bluefin_requests: Arc<RequestCounter>,
+    binance_requests: Arc<RequestCounter>,
+    kucoin_budget: Arc<TokenBucket>,
+    bluefin_budget: Arc<TokenBucket>,
+    binance_budget: Arc<TokenBucket>,
+    kucoin: OnceLock<Arc<KuCoinClient>>,
+    bluefin: OnceLock<Arc<BluefinClient>>,
+    binance: OnceLock<Arc<BinanceClient>>,
+    kucoin_session: OnceLock<Arc<KucoinSession>>,
+    bluefin_session: OnceLock<Arc<BluefinSession>>,
+}
+
+/**
+ * Returns the registry, built from `vars` on first use. The environment does not
+ * change while the process runs so later calls share the first registry
+ */
+pub fn clients(vars: &EnvVars) -> &'static ClientRegistry {
+    return REGISTRY.get_or_init(|| ClientRegistry::new(vars));
+}
+
+impl ClientRegistry {
+    fn new(vars: &EnvVars) -> ClientRegistry {
+        let http_client = reqwest::Client::builder()
+            .timeout(HTTP_TIMEOUT)
+            .build()
+            .expect("Could not build the shared HTTP client");
+
+        return ClientRegistry {
+            vars: vars.clone(),
+            http_client,
+            kucoin_requests: Arc::new(RequestCounter::for_venue(VenueKind::Kucoin)),
+            bluefin_requests: Arc::new(RequestCounter::for_venue(VenueKind::Bluefin)),
+            binance_requests: Arc::new(RequestCounter::for_venue(VenueKind::Binance)),
+            kucoin_budget: Arc::new(TokenBucket::for_venue(VenueKind::Kucoin)),
+            bluefin_budget: Arc::new(TokenBucket::for_venue(VenueKind::Bluefin)),
+            binance_budget: Arc::new(TokenBucket::for_venue(VenueKind::Binance)),
+            kucoin: OnceLock::new(),
+            bluefin: OnceLock::new(),
+            binance: OnceLock::new(),
+            kucoin_session: OnceLock::new(),
+            bluefin_session: OnceLock::new(),
+        };
+    }
+
+    fn executor(&self, venue: VenueKind) -> RequestExecutor {
+        let (requests, budget) = match venue {
+            VenueKind::Kucoin => (&self.kucoin_requests, &self.kucoin_budget),
+            VenueKind::Bluefin => (&self.bluefin_requests, &self.bluefin_budget),
+            VenueKind::Binance | VenueKind::BinanceSpot => (&self.binance_requests, &self.binance_budget),
+        };
+        return RequestExecutor::new(venue, self.http_client.clone(), requests.clone(), budget.clone());
+    }
+
+    pub fn kucoin(&self) -> Arc<KuCoinClient> {
+        return self
+            .kucoin
+            .get_or_init(|| {
+                let vars = &self.vars;
+                Arc::new(KuCoinClient::with_executor(
+                    self.executor(VenueKind::Kucoin),
+                    Credentials::new(
+                        &vars.kucoin_api_key,
+                        &vars.kucoin_api_secret,
+                        &vars.kucoin_api_phrase,
+                    ),
+                    &vars.kucoin_endpoint,
+                    &vars.kucoin_on_boarding_url,
+                    &vars.kucoin_websocket_url,
+                    vars.kucoin_leverage,
+                ))
+            })
+            .clone();
+    }
+
+    pub fn binance(&self) -> Arc<BinanceClient> {
+        return self
+            .binance
+            .get_or_init(|| {
+                Arc::new(BinanceClient::with_executor(
+                    self.executor(VenueKind::Binance),
+                    &self.vars.binance_rest_url,
+                ))
+            })
+            .clone();
+    }
+
+    /**
+     * KuCoin websocket session shared by every market's streams
+     */
+    pub fn kucoin_session(&self) -> Arc<KucoinSession> {
+        return self
+            .kucoin_session
+            .get_or_init(|| Arc::new(KucoinSession::new(self.kucoin())))
+            .clone();
+    }
+
+    /**
+     * The Bluefin client onboards on creation, concurrent callers wait for the
+     * first one so the auth token is only requested once. Onboarding blocks on the
+     * shared runtime, so this must be called from a synchronous thread. A failed
+     * onboarding panics, the supervisor restarts the market with backoff
+     */
+    pub fn bluefin(&self) -> Arc<BluefinClient> {
+        return self
+            .bluefin
+            .get_or_init(|| {
+                let vars = &self.vars;
+                let client = block_on(BluefinClient::with_executor(
+                    self.executor(VenueKind::Bluefin),
+                    &vars.bluefin_wallet_key,
+                    &vars.bluefin_endpoint,
+                    &vars.bluefin_on_boarding_url,
+                    &vars.bluefin_websocket_url,
+                    vars.bluefin_leverage,
+                ))
+                .unwrap_or_else(|e| panic!("Error onboarding on Bluefin: {}", e));
+                Arc::new(client)
+            })
+            .clone();
+    }
+
+    /**
+     * Bluefin userUpdates session, authenticated with the shared client's token. Like
+     * `bluefin` this must be called from a synchronous thread on first use
+     */
+    pub fn bluefin_session(&self) -> Arc<BluefinSession> {
+        return self
+            .bluefin_session
+            .get_or_init(|| {
+                let bluefin = self.bluefin();
+                Arc::new(BluefinSession::new(&self.vars.bluefin_websocket_url, &bluefin.auth_token))
+            })
+            .clone();
+    }
+
+    pub fn requests(&self, venue: VenueKind) -> Option<&RequestCounter> {
+        return match venue {
+            VenueKind::Kucoin => Some(&self.kucoin_requests),
+            VenueKind::Bluefin => Some(&self.bluefin_requests),
+            VenueKind::Binance => Some(&self.binance_requests),
+            VenueKind::BinanceSpot => None,
+        };
+    }
+}
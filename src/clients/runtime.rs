@@ -0,0 +1,28 @@
+use std::future::Future;
+use std::sync::OnceLock;
+
+use tokio::runtime::Runtime;
+
+static RUNTIME: OnceLock<Runtime> = OnceLock::new();
+
+/**
+ * Process wide tokio runtime the REST clients and websockets run on. Market maker,
+ * hedger and stats threads stay synchronous and hand their calls over with `block_on`
+ */
+pub fn runtime() -> &'static Runtime {
+    return RUNTIME.get_or_init(|| {
+        tokio::runtime::Builder::new_multi_thread()
+            .thread_name("venue-io")
+            .enable_all()
+            .build()
+            .expect("Could not build the venue runtime")
+    });
+}
+
+/**
+ * Runs `future` to completion on the shared runtime from a synchronous thread.
+ * Must not be called from inside a runtime task
+ */
+pub fn block_on<F: Future>(future: F) -> F::Output {
+    return runtime().block_on(future);
+}
@@ -0,0 +1,562 @@
+use crate::env::{EnvError, EnvVars};
+use crate::models::common::{CircuitBreakerConfig, Config, Market, MarketParams, VenueFees, VenueKind};
+use crate::venues::venue::{supports_role, VenueRole};
+use dotenv::dotenv;
+use rust_decimal::Decimal;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::fs;
+use std::str::FromStr;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum ConfigError {
+    #[error("invalid environment: {0}")]
+    Env(#[from] EnvError),
+    #[error("unable to read {path}: {source}")]
+    Io {
+        path: String,
+        source: std::io::Error,
+    },
+    #[error("config is not well-formatted: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("config has no markets")]
+    NoMarkets,
+    #[error("market {market} is invalid: {reason}")]
+    InvalidMarket { market: String, reason: String },
+    #[error("fees of {venue:?} are invalid: {reason}")]
+    InvalidFees { venue: VenueKind, reason: String },
+}
+
+/**
+ * Changes between a running and a reloaded configuration, markets are matched by name
+ */
+#[derive(Debug, Default)]
+pub struct ConfigDiff {
+    pub added: Vec<Market>,
+    pub removed: Vec<String>,
+    // venues, symbols or connection settings changed, the market has to be started again
+    pub restarted: Vec<Market>,
+    // only quoting or hedging parameters changed, running threads can pick them up
+    pub updated: Vec<Market>,
+}
+
+impl ConfigDiff {
+    pub fn is_empty(&self) -> bool {
+        return self.added.is_empty()
+            && self.removed.is_empty()
+            && self.restarted.is_empty()
+            && self.updated.is_empty();
+    }
+}
+
+pub static CONFIG_PATH: &str = "src/config/config.json";
+
+/**
+ * Immutable application configuration merging the environment variables and
+ * config.json. Built once on startup and shared between components through an `Arc`
+ */
+pub struct AppConfig {
+    pub vars: EnvVars,
+    pub circuit_breaker_config: CircuitBreakerConfig,
+    pub fees: HashMap<VenueKind, VenueFees>,
+    pub markets: Vec<Market>,
+}
+
+// The env vars hold credentials, keep them out of the logs
+impl fmt::Debug for AppConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return f
+            .debug_struct("AppConfig")
+            .field("circuit_breaker_config", &self.circuit_breaker_config)
+            .field("fees", &self.fees)
+            .field("markets", &self.markets)
+            .finish_non_exhaustive();
+    }
+}
+
+impl AppConfig {
+    pub fn load(config_path: &str) -> Result<AppConfig, ConfigError> {
+        dotenv().ok();
+        let vars = EnvVars::from_env()?;
+
+        return AppConfig::new(vars, AppConfig::read_config(config_path)?);
+    }
+
+    /**
+     * Reads config.json again, the environment is kept as is
+     */
+    pub fn reload(&self, config_path: &str) -> Result<AppConfig, ConfigError> {
+        return AppConfig::new(self.vars.clone(), AppConfig::read_config(config_path)?);
+    }
+
+    fn read_config(config_path: &str) -> Result<Config, ConfigError> {
+        let config_str = fs::read_to_string(config_path).map_err(|source| ConfigError::Io {
+            path: config_path.to_string(),
+            source,
+        })?;
+        return Ok(serde_json::from_str(&config_str)?);
+    }
+
+    pub fn market(&self, name: &str) -> Option<&Market> {
+        return self.markets.iter().find(|market| market.name == name);
+    }
+
+    pub fn diff(&self, new: &AppConfig) -> ConfigDiff {
+        let mut diff = ConfigDiff::default();
+        // the global settings apply to every market
+        let globals_changed = self.circuit_breaker_config != new.circuit_breaker_config || self.fees != new.fees;
+        // every source has its own stream, weights and limits are picked up in place
+        let source_venues = |market: &Market| {
+            return market
+                .fair_value
+                .as_ref()
+                .map(|fair_value| fair_value.sources.iter().map(|source| source.venue).collect::<Vec<_>>());
+        };
+
+        for market in &new.markets {
+            match self.market(&market.name) {
+                None => diff.added.push(market.clone()),
+                Some(old) => {
+                    let old_params = self.market_params(old);
+                    let new_params = new.market_params(market);
+
+                    let needs_restart = old.symbols != market.symbols
+                        || old.venues != market.venues
+                        || source_venues(old) != source_venues(market)
+                        || old.lot_size != market.lot_size
+                        || old_params.market_making_trigger_bps
+                            != new_params.market_making_trigger_bps
+                        || old_params.kucoin_leverage != new_params.kucoin_leverage
+                        || old_params.bluefin_leverage != new_params.bluefin_leverage;
+
+                    if needs_restart {
+                        diff.restarted.push(market.clone());
+                    } else if old != market || globals_changed {
+                        diff.updated.push(market.clone());
+                    }
+                }
+            }
+        }
+
+        for market in &self.markets {
+            if new.market(&market.name).is_none() {
+                diff.removed.push(market.name.clone());
+            }
+        }
+
+        return diff;
+    }
+
+    pub fn new(vars: EnvVars, config: Config) -> Result<AppConfig, ConfigError> {
+        if config.markets.is_empty() {
+            return Err(ConfigError::NoMarkets);
+        }
+
+        for (venue, fees) in &config.fees {
+            if let Some(reason) = AppConfig::invalid_fees(fees) {
+                return Err(ConfigError::InvalidFees { venue: *venue, reason: reason.to_string() });
+            }
+        }
+        for market in &config.markets {
+            AppConfig::validate_market(market)?;
+        }
+
+        return Ok(AppConfig {
+            vars,
+            circuit_breaker_config: config.circuit_breaker_config,
+            fees: config.fees,
+            markets: config.markets,
+        });
+    }
+
+    /**
+     * Global settings, used by components that are not bound to a single market
+     */
+    pub fn default_params(&self) -> MarketParams {
+        return MarketParams {
+            market_making_trigger_bps: self.vars.market_making_trigger_bps,
+            market_making_time_throttle_period: self.vars.market_making_time_throttle_period,
+            periodic_hedging_period: self.vars.periodic_hedging_period,
+            kucoin_leverage: self.vars.kucoin_leverage,
+            bluefin_leverage: self.vars.bluefin_leverage,
+        };
+    }
+
+    /**
+     * Settings of the given market, falling back to the global ones where it has no override
+     */
+    pub fn market_params(&self, market: &Market) -> MarketParams {
+        let defaults = self.default_params();
+        return MarketParams {
+            market_making_trigger_bps: market
+                .market_making_trigger_bps
+                .unwrap_or(defaults.market_making_trigger_bps),
+            market_making_time_throttle_period: market
+                .market_making_time_throttle_period
+                .unwrap_or(defaults.market_making_time_throttle_period),
+            periodic_hedging_period: market
+                .periodic_hedging_period
+                .unwrap_or(defaults.periodic_hedging_period),
+            kucoin_leverage: market.kucoin_leverage.unwrap_or(defaults.kucoin_leverage),
+            bluefin_leverage: market.bluefin_leverage.unwrap_or(defaults.bluefin_leverage),
+        };
+    }
+
+    /**
+     * Fees of `venue` for the given market, its override first, then the global schedule.
+     * A venue listed in neither is assumed to charge nothing
+     */
+    pub fn venue_fees(&self, market: &Market, venue: VenueKind) -> VenueFees {
+        return market
+            .fees
+            .get(&venue)
+            .or_else(|| self.fees.get(&venue))
+            .copied()
+            .unwrap_or_default();
+    }
+
+    // a rebate is a negative fee, but no venue pays a whole percent for a trade
+    fn invalid_fees(fees: &VenueFees) -> Option<&'static str> {
+        let plausible = |bps: f64| bps.is_finite() && bps > -100.0;
+        if !(plausible(fees.maker_bps) && plausible(fees.taker_bps)) {
+            return Some("maker_bps and taker_bps must be finite and above -100");
+        }
+        return None;
+    }
+
+    fn validate_market(market: &Market) -> Result<(), ConfigError> {
+        let invalid = |reason: &str| ConfigError::InvalidMarket {
+            market: market.name.clone(),
+            reason: reason.to_string(),
+        };
+
+        if market.lot_size == 0 {
+            return Err(invalid("lot_size must be positive"));
+        }
+        if market.mm_lot_upper_bound == 0 {
+            return Err(invalid("mm_lot_upper_bound must be positive"));
+        }
+        if market.price_precision < 0 {
+            return Err(invalid("price_precision can not be negative"));
+        }
+        match Decimal::from_str(&market.min_size) {
+            Ok(min_size) if min_size.is_sign_positive() => {}
+            _ => return Err(invalid("min_size must be a positive decimal")),
+        }
+        if market.market_making_trigger_bps.is_some_and(|bps| bps < 0.0) {
+            return Err(invalid("market_making_trigger_bps can not be negative"));
+        }
+        if market.periodic_hedging_period.is_some_and(|period| period <= 0.0) {
+            return Err(invalid("periodic_hedging_period must be positive"));
+        }
+        if market.kucoin_leverage == Some(0) || market.bluefin_leverage == Some(0) {
+            return Err(invalid("leverage must be positive"));
+        }
+        let roles = [
+            (market.venues.maker, VenueRole::Maker),
+            (market.venues.taker, VenueRole::Taker),
+            (market.venues.reference, VenueRole::Reference),
+        ];
+        if let Some((venue, role)) = roles.iter().find(|(venue, role)| !supports_role(*venue, *role)) {
+            return Err(invalid(&format!("{:?} can not be the {:?} venue", venue, role)));
+        }
+        if let Some(fair_value) = &market.fair_value {
+            // sources that play no other role get a reference feed of their own
+            let streamed = [market.venues.maker, market.venues.taker, market.venues.reference];
+            if let Some(source) = fair_value
+                .sources
+                .iter()
+                .find(|source| !streamed.contains(&source.venue) && !supports_role(source.venue, VenueRole::Reference))
+            {
+                return Err(invalid(&format!("{:?} can not be streamed as a fair_value source", source.venue)));
+            }
+            if fair_value.sources.is_empty() {
+                return Err(invalid("fair_value needs at least one source"));
+            }
+            if fair_value.sources.iter().any(|source| !(source.weight.is_finite() && source.weight > 0.0)) {
+                return Err(invalid("fair_value weights must be positive"));
+            }
+            let venues: HashSet<VenueKind> = fair_value.sources.iter().map(|source| source.venue).collect();
+            if venues.len() < fair_value.sources.len() {
+                return Err(invalid("fair_value lists a venue more than once"));
+            }
+            if fair_value.max_deviation_bps.is_some_and(|bps| bps <= 0.0) {
+                return Err(invalid("fair_value max_deviation_bps must be positive"));
+            }
+        }
+        if !(market.skewing_coefficient.is_finite() && market.skewing_coefficient >= 0.0) {
+            return Err(invalid("skewing_coefficient can not be negative"));
+        }
+        if let Some(inventory) = &market.inventory {
+            if !(inventory.max_position.is_finite() && inventory.max_position > 0.0) {
+                return Err(invalid("inventory max_position must be positive"));
+            }
+            if !(inventory.volatility_bps.is_finite() && inventory.volatility_bps >= 0.0) {
+                return Err(invalid("inventory volatility_bps can not be negative"));
+            }
+        }
+        if let Some(volatility) = &market.volatility {
+            if !(volatility.half_life_secs > 0.0 && volatility.horizon_secs > 0.0 && volatility.calm_bps > 0.0) {
+                return Err(invalid("volatility half_life_secs, horizon_secs and calm_bps must be positive"));
+            }
+            if !(volatility.min_spread_multiplier.is_finite() && volatility.min_spread_multiplier >= 0.0) {
+                return Err(invalid("volatility min_spread_multiplier can not be negative"));
+            }
+        }
+        if let Some(edge) = &market.edge {
+            if !edge.min_edge_bps.is_finite() {
+                return Err(invalid("edge min_edge_bps must be finite"));
+            }
+            if !(edge.hedge_slippage_bps.is_finite() && edge.hedge_slippage_bps >= 0.0) {
+                return Err(invalid("edge hedge_slippage_bps can not be negative"));
+            }
+            if edge.price_improvement_bps.is_some_and(|bps| !(bps.is_finite() && bps >= 0.0)) {
+                return Err(invalid("edge price_improvement_bps can not be negative"));
+            }
+        }
+        if let Some(requote) = &market.requote {
+            let tolerance = |value: f64| value.is_finite() && value >= 0.0;
+            if !(tolerance(requote.price_tolerance_bps) && tolerance(requote.size_tolerance_pct)) {
+                return Err(invalid("requote tolerances can not be negative"));
+            }
+        }
+        if let Some(reason) = market.fees.values().find_map(AppConfig::invalid_fees) {
+            return Err(invalid(reason));
+        }
+        if let Some(ladder) = &market.ladder {
+            if ladder.levels == 0 {
+                return Err(invalid("ladder needs at least one level"));
+            }
+            if ladder.spread_offsets_bps.len() != ladder.levels || ladder.size_multipliers.len() != ladder.levels {
+                return Err(invalid("ladder needs one spread offset and one size multiplier per level"));
+            }
+            if ladder.spread_offsets_bps.iter().any(|bps| !(bps.is_finite() && *bps >= 0.0)) {
+                return Err(invalid("ladder spread offsets can not be negative"));
+            }
+            if ladder.size_multipliers.iter().any(|multiplier| !(multiplier.is_finite() && *multiplier > 0.0)) {
+                return Err(invalid("ladder size multipliers must be positive"));
+            }
+            if ladder.max_total_lots == Some(0) {
+                return Err(invalid("ladder max_total_lots must be positive"));
+            }
+        }
+
+        return Ok(());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::common::{EdgeConfig, FairValueConfig, LadderConfig, VenueRoles};
+
+    fn config(markets: Vec<Market>) -> Config {
+        return Config {
+            circuit_breaker_config: CircuitBreakerConfig {
+                num_retries: 3,
+                failure_threshold: 3,
+                loss_threshold_bps: 3.0,
+            },
+            fees: HashMap::new(),
+            markets,
+        };
+    }
+
+    fn named(name: &str) -> Market {
+        return Market {
+            name: name.to_string(),
+            ..Market::for_tests()
+        };
+    }
+
+    #[test]
+    fn test_valid_config() {
+        let app_config = AppConfig::new(EnvVars::for_tests(), config(vec![Market::for_tests()])).unwrap();
+        assert_eq!(app_config.markets.len(), 1);
+        assert_eq!(app_config.circuit_breaker_config.failure_threshold, 3);
+    }
+
+    #[test]
+    fn test_invalid_min_size() {
+        let market = Market {
+            min_size: "abc".to_string(),
+            ..Market::for_tests()
+        };
+        let result = AppConfig::new(EnvVars::for_tests(), config(vec![market]));
+        assert!(matches!(result, Err(ConfigError::InvalidMarket { .. })));
+    }
+
+    #[test]
+    fn test_zero_lot_size() {
+        let market = Market {
+            lot_size: 0,
+            ..Market::for_tests()
+        };
+        let result = AppConfig::new(EnvVars::for_tests(), config(vec![market]));
+        assert!(matches!(result, Err(ConfigError::InvalidMarket { .. })));
+    }
+
+    #[test]
+    fn test_fair_value_sources() {
+        let with_sources = |sources: &str| {
+            let fair_value: FairValueConfig =
+                serde_json::from_str(&format!(r#"{{"sources": {}, "max_deviation_bps": 20.0}}"#, sources)).unwrap();
+            let market = Market {
+                fair_value: Some(fair_value),
+                ..Market::for_tests()
+            };
+            return AppConfig::new(EnvVars::for_tests(), config(vec![market]));
+        };
+
+        let app_config = with_sources(r#"[{"venue": "binance", "weight": 2.0}, {"venue": "binance_spot", "weight": 1.0}]"#)
+            .unwrap();
+        let fair_value = app_config.markets[0].fair_value.as_ref().unwrap();
+        assert_eq!(fair_value.sources[1].venue, VenueKind::BinanceSpot);
+        assert_eq!(fair_value.max_age_ms, None);
+
+        let zero_weight = with_sources(r#"[{"venue": "binance", "weight": 0.0}]"#);
+        assert!(matches!(zero_weight, Err(ConfigError::InvalidMarket { .. })));
+        let twice = with_sources(r#"[{"venue": "binance", "weight": 1.0}, {"venue": "binance", "weight": 1.0}]"#);
+        assert!(matches!(twice, Err(ConfigError::InvalidMarket { .. })));
+    }
+
+    #[test]
+    fn test_venues_must_support_their_role() {
+        let with = |maker: VenueKind, reference: VenueKind, source: Option<&str>| {
+            let fair_value = source.map(|venue| {
+                serde_json::from_str(&format!(r#"{{"sources": [{{"venue": "{}", "weight": 1.0}}]}}"#, venue)).unwrap()
+            });
+            let market = Market {
+                venues: VenueRoles {
+                    maker,
+                    taker: VenueKind::Bluefin,
+                    reference,
+                },
+                fair_value,
+                ..Market::for_tests()
+            };
+            return AppConfig::new(EnvVars::for_tests(), config(vec![market]));
+        };
+
+        let maker_binance = with(VenueKind::Binance, VenueKind::Binance, None);
+        assert!(matches!(maker_binance, Err(ConfigError::InvalidMarket { .. })));
+        let reference_kucoin = with(VenueKind::Kucoin, VenueKind::Kucoin, None);
+        assert!(matches!(reference_kucoin, Err(ConfigError::InvalidMarket { .. })));
+
+        // the maker book is already streamed, it can be a source without a reference feed
+        assert!(with(VenueKind::Kucoin, VenueKind::Binance, Some("kucoin")).is_ok());
+        assert!(with(VenueKind::Kucoin, VenueKind::Bluefin, Some("binance_spot")).is_ok());
+    }
+
+    #[test]
+    fn test_ladder_needs_a_setting_per_level() {
+        let with_ladder = |levels: usize, spread_offsets_bps: Vec<f64>, size_multipliers: Vec<f64>| {
+            let market = Market {
+                ladder: Some(LadderConfig {
+                    levels,
+                    spread_offsets_bps,
+                    size_multipliers,
+                    max_total_lots: Some(20),
+                }),
+                ..Market::for_tests()
+            };
+            return AppConfig::new(EnvVars::for_tests(), config(vec![market]));
+        };
+
+        let app_config = with_ladder(2, vec![0.0, 3.0], vec![1.0, 2.0]).unwrap();
+        assert_eq!(app_config.markets[0].ladder.as_ref().unwrap().levels, 2);
+
+        let short = with_ladder(3, vec![0.0, 3.0], vec![1.0, 2.0, 3.0]);
+        assert!(matches!(short, Err(ConfigError::InvalidMarket { .. })));
+    }
+
+    #[test]
+    fn test_market_fees_override_the_schedule() {
+        let market = Market {
+            edge: Some(serde_json::from_str::<EdgeConfig>(r#"{"min_edge_bps": 1.0, "hedge_slippage_bps": 0.5}"#).unwrap()),
+            fees: HashMap::from([(VenueKind::Kucoin, VenueFees { maker_bps: -0.5, taker_bps: 6.0 })]),
+            ..Market::for_tests()
+        };
+        let mut config = config(vec![market]);
+        config.fees = serde_json::from_str(
+            r#"{"kucoin": {"maker_bps": 2.0, "taker_bps": 6.0}, "bluefin": {"maker_bps": 1.0, "taker_bps": 4.0}}"#,
+        )
+        .unwrap();
+        let app_config = AppConfig::new(EnvVars::for_tests(), config.clone()).unwrap();
+
+        let market = &app_config.markets[0];
+        assert!(!market.edge.unwrap().fetch_fees);
+        assert_eq!(app_config.venue_fees(market, VenueKind::Kucoin).maker_bps, -0.5);
+        assert_eq!(app_config.venue_fees(market, VenueKind::Bluefin).taker_bps, 4.0);
+        assert_eq!(app_config.venue_fees(market, VenueKind::Binance), VenueFees::default());
+
+        config.fees.insert(VenueKind::Bluefin, VenueFees { maker_bps: 0.0, taker_bps: f64::NAN });
+        let result = AppConfig::new(EnvVars::for_tests(), config);
+        assert!(matches!(result, Err(ConfigError::InvalidFees { venue: VenueKind::Bluefin, .. })));
+    }
+
+    #[test]
+    fn test_market_params_fall_back_to_globals() {
+        let vars = EnvVars::for_tests();
+        let trigger_bps = vars.market_making_trigger_bps;
+        let app_config = AppConfig::new(vars, config(vec![Market::for_tests()])).unwrap();
+
+        let params = app_config.market_params(&app_config.markets[0]);
+        assert_eq!(params, app_config.default_params());
+        assert_eq!(params.market_making_trigger_bps, trigger_bps);
+    }
+
+    #[test]
+    fn test_market_params_overrides() {
+        let market = Market {
+            market_making_trigger_bps: Some(7.5),
+            bluefin_leverage: Some(5),
+            ..Market::for_tests()
+        };
+        let app_config = AppConfig::new(EnvVars::for_tests(), config(vec![market])).unwrap();
+
+        let params = app_config.market_params(&app_config.markets[0]);
+        assert_eq!(params.market_making_trigger_bps, 7.5);
+        assert_eq!(params.bluefin_leverage, 5);
+        assert_eq!(params.kucoin_leverage, app_config.vars.kucoin_leverage);
+    }
+
+    #[test]
+    fn test_diff() {
+        let old = AppConfig::new(EnvVars::for_tests(), config(vec![named("eth"), named("btc")])).unwrap();
+
+        let eth = Market {
+            skewing_coefficient: 0.5,
+            ..Market::for_tests()
+        };
+        let new = AppConfig::new(EnvVars::for_tests(), config(vec![eth, named("sol")])).unwrap();
+
+        let diff = old.diff(&new);
+        assert_eq!(diff.added.iter().map(|m| m.name.as_str()).collect::<Vec<_>>(), vec!["sol"]);
+        assert_eq!(diff.removed, vec!["btc".to_string()]);
+        assert_eq!(diff.updated.iter().map(|m| m.name.as_str()).collect::<Vec<_>>(), vec!["eth"]);
+        assert!(diff.restarted.is_empty());
+        assert!(old.diff(&old).is_empty());
+    }
+
+    #[test]
+    fn test_diff_restarts_on_venue_settings() {
+        let old = AppConfig::new(EnvVars::for_tests(), config(vec![Market::for_tests()])).unwrap();
+        let market = Market {
+            lot_size: 10,
+            ..Market::for_tests()
+        };
+        let new = AppConfig::new(EnvVars::for_tests(), config(vec![market])).unwrap();
+
+        let diff = old.diff(&new);
+        assert_eq!(diff.restarted.len(), 1);
+        assert!(diff.updated.is_empty());
+    }
+
+    #[test]
+    fn test_no_markets() {
+        let result = AppConfig::new(EnvVars::for_tests(), config(vec![]));
+        assert!(matches!(result, Err(ConfigError::NoMarkets)));
+    }
+}
@@ -1,9 +1,14 @@
+#[cfg(test)]
 use dotenv::dotenv;
+use crate::models::binance_models::BinanceBookMode;
+use std::str::FromStr;
+use thiserror::Error;
 use tracing_appender::non_blocking::WorkerGuard;
 use tracing_appender::rolling::{RollingFileAppender, Rotation};
 use tracing_subscriber::{fmt, layer::SubscriberExt};
 
 // env variable struct
+#[derive(Clone)]
 pub struct EnvVars {
     pub bluefin_on_boarding_url: String,
     pub bluefin_websocket_url: String,
@@ -17,117 +22,143 @@ pub struct EnvVars {
     pub kucoin_api_secret: String,
     pub kucoin_api_phrase: String,
     pub kucoin_leverage: u128,
-    pub kucoin_depth_topic: String,
+    pub kucoin_book_depth: usize,
     pub kucoin_ticker_v2_socket_topic: String,
     pub binance_websocket_url: String,
+    pub binance_spot_websocket_url: String,
+    pub binance_rest_url: String,
+    pub binance_book_mode: BinanceBookMode,
+    pub binance_book_depth: usize,
     pub dry_run: bool,
     pub market_making_trigger_bps: f64,
     pub market_making_time_throttle_period: u64,
+    pub feed_max_age: u64,
     pub periodic_hedging_enabled: bool,
     pub periodic_hedging_period: f64,
+    pub shutdown_hedge_timeout: u64,
+    pub flatten_on_shutdown: bool,
 
     pub log_level: String,
 }
 
-/**
- * Method to parse environment variables
- */
-#[allow(dead_code)]
-pub fn env_variables() -> EnvVars {
-    dotenv().ok();
-
-    // bluefin vars
-    let bluefin_on_boarding_url =
-        std::env::var("BLUEFIN_ON_BOARDING_URL").expect("BLUEFIN_ON_BOARDING_URL must be set.");
-    let bluefin_endpoint =
-        std::env::var("BLUEFIN_ENDPOINT").expect("BLUEFIN_ENDPOINT must be set.");
-    let bluefin_wallet_key =
-        std::env::var("BLUEFIN_WALLET_KEY").expect("BLUEFIN_WALLET_KEY must be set.");
-    let bluefin_websocket_url =
-        std::env::var("BLUEFIN_WEB_SOCKET_URL").expect("BLUEFIN_WEB_SOCKET_URL must be set.");
-    let bluefin_leverage = std::env::var("BLUEFIN_LEVERAGE")
-        .expect("BLUEFIN_LEVERAGE must be set.")
-        .parse::<u128>()
-        .unwrap();
-    // kucoin vars
-    let kucoin_on_boarding_url =
-        std::env::var("KUCOIN_ON_BOARDING_URL").expect("KUCOIN_ON_BOARDING_URL must be set.");
-    let kucoin_websocket_url =
-        std::env::var("KUCOIN_WEB_SOCKET_URL").expect("KUCOIN_WEB_SOCKET_URL must be set.");
-    let kucoin_endpoint = std::env::var("KUCOIN_ENDPOINT").expect("KUCOIN_ENDPOINT must be set.");
-
-    let kucoin_api_key = std::env::var("KUCOIN_API_KEY").expect("KUCOIN_API_KEY must be set.");
-    let kucoin_api_secret =
-        std::env::var("KUCOIN_API_SECRET").expect("KUCOIN_API_SECRET must be set.");
-
-    let kucoin_api_phrase =
-        std::env::var("KUCOIN_API_PASSPHRASE").expect("KUCOIN_API_PASSPHRASE must be set.");
-
-    let kucoin_leverage = std::env::var("KUCOIN_LEVERAGE")
-        .expect("KUCOIN_LEVERAGE must be set.")
-        .parse::<u128>()
-        .unwrap();
-
-    let kucoin_depth_topic =
-        std::env::var("KUCOIN_DEPTH_SOCKET_TOPIC").expect("KUCOIN_DEPTH_SOCKET_TOPIC must be set.");
-
-    let kucoin_ticker_v2_socket_topic = std::env::var("KUCOIN_TICKER_V2_SOCKET_TOPIC")
-        .expect("KUCOIN_TICKER_V2_SOCKET_TOPIC must be set.");
-
-    let binance_websocket_url =
-        std::env::var("BINANCE_WEB_SOCKET_URL").expect("BINANCE_WEB_SOCKET_URL must be set.");
-
-    let dry_run = std::env::var("DRY_RUN")
-        .expect("DRY_RUN must be set.")
-        .parse::<bool>()
-        .unwrap();
-
-    let market_making_trigger_bps = std::env::var("MARKET_MAKING_TRIGGER_BPS")
-        .expect("MARKET_MAKING_TRIGGER_BPS be set.")
-        .parse::<f64>()
-        .unwrap();
+#[derive(Error, Debug)]
+pub enum EnvError {
+    #[error("{0} must be set")]
+    Missing(&'static str),
+    #[error("{name} has an invalid value: {value:?}")]
+    Invalid { name: &'static str, value: String },
+}
 
-    let market_making_time_throttle_period = std::env::var("MARKET_MAKING_TIME_THROTTLE_PERIOD")
-        .expect("MARKET_MAKING_TIME_THROTTLE_PERIOD be set.")
-        .parse::<u64>()
-        .unwrap();
+fn var(name: &'static str) -> Result<String, EnvError> {
+    return std::env::var(name).map_err(|_| EnvError::Missing(name));
+}
 
-    let periodic_hedging_enabled = std::env::var("PERIODIC_HEDGING_ENABLED")
-        .expect("PERIODIC_HEDGING_ENABLED be set.")
-        .parse::<bool>()
-        .unwrap();
+fn parse_var<T: FromStr>(name: &'static str) -> Result<T, EnvError> {
+    let value = var(name)?;
+    return value
+        .parse::<T>()
+        .map_err(|_| EnvError::Invalid { name, value });
+}
 
-    let periodic_hedging_period = std::env::var("PERIODIC_HEDGING_PERIOD")
-        .expect("PERIODIC_HEDGING_PERIOD be set.")
-        .parse::<f64>()
-        .unwrap();
+fn parse_var_or<T: FromStr>(name: &'static str, default: T) -> Result<T, EnvError> {
+    return match std::env::var(name) {
+        Ok(_) => parse_var(name),
+        Err(_) => Ok(default),
+    };
+}
 
-    // misc
-    let log_level = std::env::var("LOG_LEVEL").expect("LOG_LEVEL must be set.");
+impl EnvVars {
+    /**
+     * Reads the variables from the process environment. Callers are expected to
+     * have loaded the .env file beforehand, see `AppConfig::load`
+     */
+    pub fn from_env() -> Result<EnvVars, EnvError> {
+        return Ok(EnvVars {
+            // bluefin vars
+            bluefin_on_boarding_url: var("BLUEFIN_ON_BOARDING_URL")?,
+            bluefin_endpoint: var("BLUEFIN_ENDPOINT")?,
+            bluefin_wallet_key: var("BLUEFIN_WALLET_KEY")?,
+            bluefin_websocket_url: var("BLUEFIN_WEB_SOCKET_URL")?,
+            bluefin_leverage: parse_var("BLUEFIN_LEVERAGE")?,
+            // kucoin vars
+            kucoin_on_boarding_url: var("KUCOIN_ON_BOARDING_URL")?,
+            kucoin_endpoint: var("KUCOIN_ENDPOINT")?,
+            kucoin_websocket_url: var("KUCOIN_WEB_SOCKET_URL")?,
+            kucoin_api_key: var("KUCOIN_API_KEY")?,
+            kucoin_api_secret: var("KUCOIN_API_SECRET")?,
+            kucoin_api_phrase: var("KUCOIN_API_PASSPHRASE")?,
+            kucoin_leverage: parse_var("KUCOIN_LEVERAGE")?,
+            kucoin_book_depth: parse_var_or("KUCOIN_BOOK_DEPTH", 5)?,
+            kucoin_ticker_v2_socket_topic: var("KUCOIN_TICKER_V2_SOCKET_TOPIC")?,
+            binance_websocket_url: var("BINANCE_WEB_SOCKET_URL")?,
+            binance_spot_websocket_url: parse_var_or(
+                "BINANCE_SPOT_WEB_SOCKET_URL",
+                "wss://stream.binance.com:9443".to_string(),
+            )?,
+            binance_rest_url: parse_var_or("BINANCE_REST_URL", "https://fapi.binance.com".to_string())?,
+            binance_book_mode: parse_var_or("BINANCE_BOOK_MODE", BinanceBookMode::DiffDepth)?,
+            binance_book_depth: parse_var_or("BINANCE_BOOK_DEPTH", 20)?,
+            dry_run: parse_var("DRY_RUN")?,
+            market_making_trigger_bps: parse_var("MARKET_MAKING_TRIGGER_BPS")?,
+            market_making_time_throttle_period: parse_var("MARKET_MAKING_TIME_THROTTLE_PERIOD")?,
+            feed_max_age: parse_var_or("FEED_MAX_AGE_MS", 5000)?,
+            periodic_hedging_enabled: parse_var("PERIODIC_HEDGING_ENABLED")?,
+            periodic_hedging_period: parse_var("PERIODIC_HEDGING_PERIOD")?,
+            // shutdown
+            shutdown_hedge_timeout: parse_var_or("SHUTDOWN_HEDGE_TIMEOUT", 30)?,
+            flatten_on_shutdown: parse_var_or("FLATTEN_ON_SHUTDOWN", false)?,
+            // misc
+            log_level: var("LOG_LEVEL")?,
+        });
+    }
+
+    /**
+     * Placeholder values so components can be built in tests without a .env file
+     */
+    #[cfg(test)]
+    pub fn for_tests() -> EnvVars {
+        return EnvVars {
+            bluefin_on_boarding_url: "http://127.0.0.1/bluefin/onboarding".to_string(),
+            bluefin_endpoint: "http://127.0.0.1/bluefin".to_string(),
+            bluefin_wallet_key: String::new(),
+            bluefin_websocket_url: "ws://127.0.0.1/bluefin".to_string(),
+            bluefin_leverage: 3,
+            kucoin_on_boarding_url: "http://127.0.0.1/kucoin/onboarding".to_string(),
+            kucoin_endpoint: "http://127.0.0.1/kucoin".to_string(),
+            kucoin_websocket_url: "ws://127.0.0.1/kucoin".to_string(),
+            kucoin_api_key: String::new(),
+            kucoin_api_secret: String::new(),
+            kucoin_api_phrase: String::new(),
+            kucoin_leverage: 3,
+            kucoin_book_depth: 5,
+            kucoin_ticker_v2_socket_topic: "/contractMarket/tickerV2".to_string(),
+            binance_websocket_url: "ws://127.0.0.1/binance".to_string(),
+            binance_spot_websocket_url: "ws://127.0.0.1/binance_spot".to_string(),
+            binance_rest_url: "http://127.0.0.1/binance".to_string(),
+            binance_book_mode: BinanceBookMode::DiffDepth,
+            binance_book_depth: 20,
+            dry_run: true,
+            market_making_trigger_bps: 1.0,
+            market_making_time_throttle_period: 100,
+            feed_max_age: 5000,
+            periodic_hedging_enabled: false,
+            periodic_hedging_period: 1.0,
+            shutdown_hedge_timeout: 1,
+            flatten_on_shutdown: false,
+            log_level: "Info".to_string(),
+        };
+    }
+}
 
-    return EnvVars {
-        bluefin_on_boarding_url,
-        bluefin_endpoint,
-        bluefin_wallet_key,
-        bluefin_websocket_url,
-        bluefin_leverage,
-        kucoin_on_boarding_url,
-        kucoin_endpoint,
-        kucoin_websocket_url,
-        kucoin_api_key,
-        kucoin_api_secret,
-        kucoin_api_phrase,
-        kucoin_leverage,
-        kucoin_depth_topic,
-        kucoin_ticker_v2_socket_topic,
-        binance_websocket_url,
-        dry_run,
-        market_making_trigger_bps,
-        market_making_time_throttle_period,
-        periodic_hedging_enabled,
-        periodic_hedging_period,
-        log_level,
-    };
+/**
+ * Loads the .env file and parses environment variables. Only meant for the
+ * integration tests that talk to the real exchanges, the bot itself reads
+ * them once through `AppConfig::load`
+ */
+#[cfg(test)]
+pub fn env_variables() -> EnvVars {
+    dotenv().ok();
+    return EnvVars::from_env().expect("Invalid environment variables");
 }
 
 /**
@@ -1,106 +1,130 @@
-use crate::bluefin::{
-    parse_order_update, parse_user_position, parse_order_settlement_update, AccountData, BluefinClient, OrderUpdate, UserPosition, OrderSettlementUpdate
-};
 use crate::circuit_breakers::cancel_all_orders_breaker::CancelAllOrdersCircuitBreaker;
 use crate::circuit_breakers::circuit_breaker::CircuitBreaker;
 use crate::circuit_breakers::circuit_breaker::CircuitBreakerBase;
 use crate::circuit_breakers::circuit_breaker::State;
 use crate::circuit_breakers::kucoin_breaker::KuCoinBreaker;
-use crate::env;
-use crate::env::EnvVars;
-use crate::kucoin::PositionChangeEvent;
-use crate::kucoin::{Credentials, KuCoinClient};
-use crate::models::common::{CircuitBreakerConfig, Market, OrderBook};
-use crate::models::kucoin_models::KucoinUserPosition;
-use crate::sockets::bluefin_private_socket::stream_bluefin_private_socket;
-use crate::sockets::kucoin_socket::stream_kucoin_socket;
-use rust_decimal::prelude::{FromPrimitive, ToPrimitive};
+use crate::clients::error::{ErrorCode, VenueError};
+use crate::clients::runtime::block_on;
+use crate::config::app_config::AppConfig;
+use crate::models::common::{Market, MarketParams};
+use crate::models::order_book::OrderBook;
+use crate::models::units::{Price, Qty};
+use crate::utils::{forward_into, FeedSender};
+use crate::venues::venue::{create_maker_venue, create_taker_venue, MakerVenue, TakerVenue};
+use rust_decimal::prelude::ToPrimitive;
 use rust_decimal::Decimal;
-use serde_json::Value;
-use std::collections::HashMap;
-use std::ops::{Add, Div, Mul};
 use std::str::FromStr;
 use std::sync::mpsc;
 use std::sync::mpsc::Receiver;
 use std::sync::mpsc::Sender;
-use std::thread;
+use std::sync::Arc;
 use std::time::Duration;
 use std::time::Instant;
 
-static BIGNUMBER_BASE: u128 = 1000000000000000000;
+// Every input the hedger reacts to, multiplexed onto a single channel
+#[derive(Debug)]
+pub enum HedgerEvent {
+    TakerFill(Decimal),
+    TakerPosition(Decimal),
+    TakerOrderBook(OrderBook),
+    MakerPosition(Decimal),
+    Disconnected(HedgerFeed),
+    // reloaded configuration, the market is looked up by name
+    ConfigUpdate(Arc<AppConfig>),
+    // stop hedging, the market got removed
+    Shutdown,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HedgerFeed {
+    TakerFill,
+    TakerPosition,
+    TakerOrderBook,
+    MakerPosition,
+}
 
 pub struct HGR {
     pub market: Market,
-    pub cb_config: CircuitBreakerConfig,
-    bluefin_client: BluefinClient,
-    kucoin_client: KuCoinClient,
-    #[allow(dead_code)]
-    bluefin_account: AccountData,
-    bluefin_position: UserPosition,
-    kucoin_position: KucoinUserPosition,
-    tx_hedger: Sender<f64>,
-    rx_bluefin_ob: Receiver<OrderBook>,
+    pub config: Arc<AppConfig>,
+    pub params: MarketParams,
+    maker: Arc<dyn MakerVenue>,
+    taker: Arc<dyn TakerVenue>,
+    // signed positions in base asset units
+    maker_position: Decimal,
+    taker_position: Decimal,
+    tx_hedger: FeedSender<f64>,
+    tx_event: Sender<HedgerEvent>,
+    rx_event: Receiver<HedgerEvent>,
 }
 
 impl HGR {
+    /**
+     * Hedging starts from both venues' positions, a failed fetch fails the start of the
+     * market. The event channel is made by the caller, the market maker sends the taker
+     * book into it
+     */
     pub fn new(
         market: Market,
-        cb_config: CircuitBreakerConfig,
-        tx_hedger: Sender<f64>,
-        rx_bluefin_ob: Receiver<OrderBook>,
-    ) -> HGR {
-        let vars: EnvVars = env::env_variables();
-
-        let bluefin_client = BluefinClient::new(
-            &vars.bluefin_wallet_key,
-            &vars.bluefin_endpoint,
-            &vars.bluefin_on_boarding_url,
-            &vars.bluefin_websocket_url,
-            vars.bluefin_leverage,
-        );
+        config: Arc<AppConfig>,
+        tx_hedger: FeedSender<f64>,
+        tx_event: Sender<HedgerEvent>,
+        rx_event: Receiver<HedgerEvent>,
+    ) -> Result<HGR, VenueError> {
+        let params = config.market_params(&market);
 
-        let kucoin_client = KuCoinClient::new(
-            Credentials::new(
-                &vars.kucoin_api_key,
-                &vars.kucoin_api_secret,
-                &vars.kucoin_api_phrase,
-            ),
-            &vars.kucoin_endpoint,
-            &vars.kucoin_on_boarding_url,
-            &vars.kucoin_websocket_url,
-            vars.kucoin_leverage,
-        );
-
-        let bluefin_account = bluefin_client.get_user_account();
+        let maker = create_maker_venue(market.venues.maker, &config.vars, &params);
+        let taker = create_taker_venue(market.venues.taker, &config.vars, &params);
 
-        let bluefin_market = market.symbols.bluefin.to_owned();
-        let bluefin_position = bluefin_client.get_user_position(&bluefin_market);
-        let kucoin_position = kucoin_client
-            .get_position(&bluefin_market)
-            .expect("Could not fetch Kucoin Position on Hedger startup");
+        let (taker_position, maker_position) =
+            block_on(async { futures::join!(taker.get_position(&market), maker.get_position(&market)) });
+        let maker_position = maker_position?;
+        let taker_position = taker_position?;
 
         tracing::info!(
-            bluefin_position_qty = bluefin_position.quantity / BIGNUMBER_BASE,
-            bluefin_position_symbol = bluefin_position.symbol,
-            "Bluefin Initial Hedger Position"
+            taker_position_qty = taker_position.to_f64().unwrap(),
+            taker_position_symbol = market.symbols.bluefin,
+            "{:?} Initial Hedger Position",
+            taker.kind()
         );
 
         tracing::info!(
-            kucoin_position_qty = kucoin_position.current_qty / 100,
-            kucoin_position_symbol = kucoin_position.symbol,
-            "Kucoin Initial Hedger Position"
+            maker_position_qty = maker_position.to_f64().unwrap(),
+            maker_position_symbol = market.symbols.kucoin,
+            "{:?} Initial Hedger Position",
+            maker.kind()
         );
 
-        HGR {
+        Ok(HGR {
             market,
-            cb_config,
-            bluefin_client,
-            kucoin_client,
-            bluefin_position,
-            kucoin_position,
-            bluefin_account,
+            config,
+            params,
+            maker,
+            taker,
+            maker_position,
+            taker_position,
             tx_hedger,
-            rx_bluefin_ob,
+            tx_event,
+            rx_event,
+        })
+    }
+}
+
+impl HGR {
+    pub fn event_sender(&self) -> Sender<HedgerEvent> {
+        return self.tx_event.clone();
+    }
+
+    fn apply_config(&mut self, config: Arc<AppConfig>) {
+        match config.market(&self.market.name) {
+            Some(market) => {
+                self.market = market.clone();
+                self.params = config.market_params(market);
+                self.config = config.clone();
+                tracing::info!(market = self.market.name, "Hedger picked up new config {:?}", self.params);
+            }
+            None => {
+                tracing::warn!(market = self.market.name, "Market is missing from the reloaded config, ignoring update");
+            }
         }
     }
 }
@@ -108,231 +132,124 @@ impl HGR {
 pub trait Hedger {
     fn connect(&mut self);
     fn hedge(&mut self, dry_run: bool, ob: Option<&OrderBook>, is_periodic: bool);
-    fn calc_limit_order_price(hedge_qty: Decimal, is_buy: bool, ob: &OrderBook) -> f64;
+    fn calc_limit_order_price(hedge_qty: Decimal, is_buy: bool, ob: &OrderBook) -> Option<Price>;
     fn calc_net_pos_qty(&mut self) -> (String, Decimal, bool);
     fn update_positions(&mut self);
 }
 
 impl Hedger for HGR {
     fn connect(&mut self) {
-        let vars: EnvVars = env::env_variables();
-        let (tx_bluefin_pos_update, rx_bluefin_pos_update) = mpsc::channel();
-        let (tx_bluefin_order_update, _rx_bluefin_order_update) = mpsc::channel();
-        let (tx_kucoin_pos_change, rx_kucoin_pos_change) = mpsc::channel();
-        let (tx_bluefin_order_settlement_update, rx_bluefin_order_settlement_update) = mpsc::channel();
-
-        let bluefin_market = self.market.symbols.bluefin.to_owned();
-        let bluefin_market_for_settlement_update = bluefin_market.clone();
-        let bluefin_auth_token = self.bluefin_client.auth_token.clone();
-        let bluefin_websocket_url = vars.bluefin_websocket_url.clone();
-        let _handle_bluefin_order_settlement_update = thread::spawn(move ||  {
-            stream_bluefin_private_socket(
-                &bluefin_websocket_url,
-                &bluefin_market_for_settlement_update,
-                &bluefin_auth_token,
-                "OrderSettlementUpdate",
-                tx_bluefin_order_settlement_update, // Sender channel of the appropriate type
-                |msg: &str| -> OrderSettlementUpdate {
-                    tracing::info!("Bluefin Order Settlement Update {}", msg);
-                    let v: Value = serde_json::from_str(msg).unwrap();
-                    let order_settlement: OrderSettlementUpdate = parse_order_settlement_update(v["data"].clone());
-
-                    tracing::info!(
-                        quantity_sent_for_settlement = order_settlement.quantity_sent_for_settlement,
-                        is_buy = order_settlement.is_buy,   
-                        "Bluefin Order Settlement Update"
-                    );
-
-                    order_settlement
-                },
-            );
-        });
-
-
-
-        let bluefin_market_for_order_fill = bluefin_market.clone();
-        let bluefin_auth_token = self.bluefin_client.auth_token.clone();
-        let bluefin_websocket_url = vars.bluefin_websocket_url.clone();
-        let _handle_bluefin_order_update = thread::spawn(move || {
-            stream_bluefin_private_socket(
-                &bluefin_websocket_url,
-                &bluefin_market_for_order_fill,
-                &bluefin_auth_token,
-                "OrderUpdate",
-                tx_bluefin_order_update, // Sender channel of the appropriate type
-                |msg: &str| -> OrderUpdate {
-                    tracing::info!("Bluefin Order Update {}", &msg);
-
-                    let v: Value = serde_json::from_str(&msg).unwrap();
-                    let order_update: OrderUpdate = parse_order_update(v["data"]["order"].clone());
-
-                    let open_qty = Decimal::from_u128(order_update.open_qty).unwrap()
-                        / Decimal::from(BIGNUMBER_BASE);
-
-                    if open_qty.is_zero() && order_update.symbol == bluefin_market {
-                        let quantity = Decimal::from_u128(order_update.quantity).unwrap()
-                            / Decimal::from(BIGNUMBER_BASE);
-                        let avg_fill_price = Decimal::from_u128(order_update.avg_fill_price)
-                            .unwrap()
-                            / Decimal::from(BIGNUMBER_BASE);
-                        let volume = (quantity * avg_fill_price).to_f64().unwrap();
-                        tracing::info!(
-                            market = order_update.symbol,
-                            bluefin_volume = volume,
-                            bluefin_order_status = order_update.order_status,
-                            "Bluefin Volume"
-                        );
-                    }
-                    order_update
-                },
-            );
-        });
-
-        let bluefin_market = self.market.symbols.bluefin.to_owned();
-
-        let bluefin_market_for_order_fill = bluefin_market.clone();
-        let bluefin_auth_token = self.bluefin_client.auth_token.clone();
-        let bluefin_websocket_url = vars.bluefin_websocket_url.clone();
-        let _handle_bluefin_pos_update = thread::spawn(move || {
-            stream_bluefin_private_socket(
-                &bluefin_websocket_url,
-                &bluefin_market_for_order_fill,
-                &bluefin_auth_token,
-                "PositionUpdate",
-                tx_bluefin_pos_update, // Sender channel of the appropriate type
-                |msg: &str| -> UserPosition {
-                    tracing::info!("Bluefin Position Update {}", &msg);
-
-                    let v: Value = serde_json::from_str(&msg).unwrap();
-                    let user_position: UserPosition =
-                        parse_user_position(v["data"]["position"].clone());
-                    if user_position.symbol == bluefin_market {
-                        let mut quantity = Decimal::from_u128(user_position.quantity).unwrap()
-                            / Decimal::from(BIGNUMBER_BASE);
-
-                        if !user_position.side {
-                            quantity = quantity * Decimal::from_i128(-1).unwrap();
-                        }
-
-                        let unrealized_pnl = Decimal::from_i128(user_position.unrealized_profit).unwrap()
-                            .div(Decimal::from_u128(BIGNUMBER_BASE).unwrap());
-
-                        tracing::info!(
-                            market = user_position.symbol,
-                            bluefin_real_quantity = quantity.to_f64().unwrap(),
-                            bluefin_unrealized_pnl = unrealized_pnl.to_f64().unwrap(),
-                            "Bluefin Position Update"
-                        );
-                    }
-                    user_position
-                },
-            );
-        });
-
-        let kucoin_market_for_pos_update = self.market.symbols.kucoin.clone();
-        let topic = "/contract/position";
-        let kucoin_private_socket_url = self.kucoin_client.get_kucoin_private_socket_url().clone();
-        let kucoin_lot_size = self.market.lot_size.clone();
-        let _handle_kucoin_pos_change = thread::spawn(move || {
-            stream_kucoin_socket(
-                &kucoin_private_socket_url,
-                &kucoin_market_for_pos_update,
-                &topic,
-                tx_kucoin_pos_change, // Sender channel of the appropriate type
-                |msg: &str| -> KucoinUserPosition {
-                    let kucoin_user_pos: PositionChangeEvent =
-                        serde_json::from_str(&msg).expect("Can't parse");
-
-                    let quantity = Decimal::from_i128(kucoin_user_pos.data.current_qty).unwrap()
-                        / Decimal::from(kucoin_lot_size);
-
-
-                    let avg_entry_price = Decimal::from_f64(kucoin_user_pos.data.avg_entry_price).unwrap();
-
-                    let volume = quantity.mul(avg_entry_price).abs().to_f64().unwrap();
-
-                    tracing::info!(
-                        market = kucoin_user_pos.data.symbol,
-                        kucoin_real_quantity = quantity.to_f64().unwrap(),
-                        kucoin_volume = volume,
-                        kucoin_unrealized_pnl = kucoin_user_pos.data.unrealised_pnl,
-                        kucoin_realized_pnl = kucoin_user_pos.data.realised_pnl,
-                        "Kucoin Position Update"
-                    );
+        let config = self.config.clone();
+        let vars = &config.vars;
+        let tx_taker_pos_update = forward_into(
+            &self.tx_event,
+            HedgerEvent::TakerPosition,
+            HedgerEvent::Disconnected(HedgerFeed::TakerPosition),
+        );
+        let (tx_taker_filled_volume, _rx_taker_filled_volume) = mpsc::channel();
+        let tx_maker_pos_change = forward_into(
+            &self.tx_event,
+            HedgerEvent::MakerPosition,
+            HedgerEvent::Disconnected(HedgerFeed::MakerPosition),
+        );
+        let tx_taker_fill = forward_into(
+            &self.tx_event,
+            HedgerEvent::TakerFill,
+            HedgerEvent::Disconnected(HedgerFeed::TakerFill),
+        );
 
-                    kucoin_user_pos.data
-                },
-                &"position.change",
-                true,
-            );
-        });
+        // streams run as tasks on the shared runtime
+        self.taker.stream_fills(&self.market, tx_taker_fill);
+        self.taker.stream_filled_volume(&self.market, tx_taker_filled_volume.into());
+        self.taker.stream_positions(&self.market, tx_taker_pos_update);
+        self.maker.stream_positions(&self.market, tx_maker_pos_change);
 
         let dry_run = vars.dry_run;
-        let bluefin = "bluefin".to_string();
+        let maker_kind = self.maker.kind();
+        let taker_kind = self.taker.kind();
 
-        let mut ob_map: HashMap<String, OrderBook> = HashMap::new();
+        let mut taker_ob: Option<OrderBook> = None;
 
         let bluefin_market_for_ob_update_breaker = self.market.symbols.bluefin.clone();
-        let mut bluefin_ob_breaker = CancelAllOrdersCircuitBreaker {
-            name: "Bluefin Orderbook breaker".to_string(),
+        let mut taker_ob_breaker = CancelAllOrdersCircuitBreaker {
+            name: format!("{:?} Orderbook breaker", taker_kind),
             circuit_breaker: CircuitBreakerBase {
-                config: self.cb_config.clone(),
+                config: config.circuit_breaker_config,
                 num_failures: 0,
                 state: State::Closed,
                 kucoin_breaker: KuCoinBreaker::new(
-                    "Kucoin Breaker for Bluefin Orderbook breaker".to_string(),
+                    format!("Kucoin Breaker for {:?} Orderbook breaker", taker_kind),
+                    vars,
                 ),
                 market: bluefin_market_for_ob_update_breaker,
             },
         };
 
         let bluefin_market_for_pos_update_breaker = self.market.symbols.bluefin.clone();
-        let mut kucoin_pos_update_disconnect_breaker = CancelAllOrdersCircuitBreaker {
-            name: "Kucoin Position Update Disconnect breaker".to_string(),
+        let mut maker_pos_update_disconnect_breaker = CancelAllOrdersCircuitBreaker {
+            name: format!("{:?} Position Update Disconnect breaker", maker_kind),
             circuit_breaker: CircuitBreakerBase {
-                config: self.cb_config.clone(),
+                config: config.circuit_breaker_config,
                 num_failures: 0,
                 state: State::Closed,
                 kucoin_breaker: KuCoinBreaker::new(
-                    "Kucoin Breaker for Kucoin Position Update Disconnect breaker".to_string(),
+                    format!("Kucoin Breaker for {:?} Position Update Disconnect breaker", maker_kind),
+                    vars,
                 ),
                 market: bluefin_market_for_pos_update_breaker,
             },
         };
 
-        let bluefin_market_for_bluefin_pos_update_breaker = self.market.symbols.bluefin.clone();
-        let mut bluefin_pos_update_disconnect_breaker = CancelAllOrdersCircuitBreaker {
-            name: "Bluefin Position Update Disconnect breaker".to_string(),
+        let bluefin_market_for_taker_pos_update_breaker = self.market.symbols.bluefin.clone();
+        let mut taker_pos_update_disconnect_breaker = CancelAllOrdersCircuitBreaker {
+            name: format!("{:?} Position Update Disconnect breaker", taker_kind),
             circuit_breaker: CircuitBreakerBase {
-                config: self.cb_config.clone(),
+                config: config.circuit_breaker_config,
                 num_failures: 0,
                 state: State::Closed,
                 kucoin_breaker: KuCoinBreaker::new(
-                    "Kucoin Breaker for Bluefin Position Update Disconnect breaker".to_string(),
+                    format!("Kucoin Breaker for {:?} Position Update Disconnect breaker", taker_kind),
+                    vars,
                 ),
-                market: bluefin_market_for_bluefin_pos_update_breaker,
+                market: bluefin_market_for_taker_pos_update_breaker,
             },
         };
 
         let mut last_hedge_time = Instant::now();
 
         let periodic_hedging_enabled = vars.periodic_hedging_enabled;
-        let periodic_hedging_period = vars.periodic_hedging_period;
+        let mut periodic_hedging_period = Duration::from_secs_f64(self.params.periodic_hedging_period);
 
         loop {
-            match rx_bluefin_order_settlement_update.try_recv() {
-                Ok(value) => {
-                    tracing::info!("Bluefin Order Settlement update: {:?}", value);
-                    let curr_side: i128 = if self.bluefin_position.side { 1 } else { -1 };
-                    let new_qty = (self.bluefin_position.quantity as i128 * curr_side) +
-                        ((value.quantity_sent_for_settlement as i128) * (if value.is_buy { 1 } else { -1 }));
+            // block until the next event, waking up in time for the periodic hedge if enabled.
+            // Without a taker book there is nothing to hedge against, so just wait for one.
+            let event = if periodic_hedging_enabled && taker_ob.is_some() {
+                match self
+                    .rx_event
+                    .recv_timeout(periodic_hedging_period.saturating_sub(last_hedge_time.elapsed()))
+                {
+                    Ok(event) => Some(event),
+                    Err(mpsc::RecvTimeoutError::Timeout) => None,
+                    Err(mpsc::RecvTimeoutError::Disconnected) => {
+                        panic!("Hedger event channel has disconnected!")
+                    }
+                }
+            } else {
+                Some(
+                    self.rx_event
+                        .recv()
+                        .expect("Hedger event channel has disconnected!"),
+                )
+            };
 
-                    tracing::info!("Old Bluefin Position {:?}", self.bluefin_position);
+            match event {
+                Some(HedgerEvent::TakerFill(value)) => {
+                    tracing::info!("{:?} fill: {:?}", taker_kind, value);
+                    tracing::info!("Old {:?} Position {:?}", taker_kind, self.taker_position);
 
-                    self.bluefin_position.quantity = new_qty.abs() as u128;
-                    self.bluefin_position.side = if new_qty > 0 { true } else { false };
+                    self.taker_position += value;
 
-                    tracing::info!("New Bluefin Position {:?}", self.bluefin_position);
+                    tracing::info!("New {:?} Position {:?}", taker_kind, self.taker_position);
 
                     let (_bluefin_market, mut order_quantity, is_buy) = self.calc_net_pos_qty();
                     let diff = if is_buy {
@@ -346,181 +263,133 @@ impl Hedger for HGR {
                         .send(diff.to_f64().unwrap())
                         .expect("Could not send current net position from hedger to mm!");
                 }
-                Err(mpsc::TryRecvError::Empty) => {}
-                Err(mpsc::TryRecvError::Disconnected) => {
-                    tracing::info!("Bluefin Order Settlement update worker has disconnected!");
-                    
+                Some(HedgerEvent::TakerPosition(value)) => {
+                    tracing::info!("{:?} position update: {:?}.", taker_kind, value);
+                    taker_pos_update_disconnect_breaker.on_success();
                 }
-            }
-
-            match rx_bluefin_pos_update.try_recv() {
-                Ok(value) => {
-                    tracing::info!("Bluefin position update: {:?}.", value);
-                    bluefin_pos_update_disconnect_breaker.on_success();
-                    
-                    // //update value
-                    // self.bluefin_position = value;
-
-                    // let (_bluefin_market, mut order_quantity, is_buy) = self.calc_net_pos_qty();
-                    // let diff = if is_buy {
-                    //     order_quantity.to_f64().unwrap()
-                    // } else {
-                    //     order_quantity.set_sign_negative(true);
-                    //     order_quantity.to_f64().unwrap()
-                    // };
-
-                    // self.tx_hedger
-                    //     .send(diff.to_f64().unwrap())
-                    //     .expect("Could not send current net position from hedger to mm!");
-                }
-                Err(mpsc::TryRecvError::Empty) => {}
-                Err(mpsc::TryRecvError::Disconnected) => {
-                    tracing::info!("Bluefin position update worker has disconnected!");
-                    if !bluefin_pos_update_disconnect_breaker.is_open() {
-                        bluefin_pos_update_disconnect_breaker.on_failure();
-                    }
+                Some(HedgerEvent::TakerOrderBook(value)) => {
+                    tracing::debug!("hedger taker ob: {:?}", value);
+                    taker_ob_breaker.on_success();
+                    taker_ob = Some(value);
                 }
-            }
+                Some(HedgerEvent::MakerPosition(value)) => {
+                    tracing::info!("{:?} position update: {:?}", maker_kind, value);
+                    maker_pos_update_disconnect_breaker.on_success();
+                    self.maker_position = value;
 
-            match self.rx_bluefin_ob.try_recv() {
-                Ok(value) => {
-                    tracing::debug!("hedger bluefin ob: {:?}", value);
-                    bluefin_ob_breaker.on_success();
-                    ob_map.insert(bluefin.clone(), value);
+                    tracing::info!(periodic_hedge = false, "{:?} Position Hedger", maker_kind);
+                    self.hedge(dry_run, taker_ob.as_ref(), false);
                 }
-                Err(mpsc::TryRecvError::Empty) => {}
-                Err(mpsc::TryRecvError::Disconnected) => {
-                    tracing::info!("Bluefin Hedger OB worker has disconnected!");
-                    if !bluefin_ob_breaker.is_open() {
-                        bluefin_ob_breaker.on_failure();
+                Some(HedgerEvent::ConfigUpdate(config)) => {
+                    self.apply_config(config);
+                    periodic_hedging_period = Duration::from_secs_f64(self.params.periodic_hedging_period);
+                    let cb_config = self.config.circuit_breaker_config;
+                    for breaker in [
+                        &mut taker_ob_breaker,
+                        &mut maker_pos_update_disconnect_breaker,
+                        &mut taker_pos_update_disconnect_breaker,
+                    ] {
+                        breaker.circuit_breaker.config = cb_config;
                     }
                 }
-            }
-
-            match rx_kucoin_pos_change.try_recv() {
-                Ok(value) => {
-                    tracing::info!("Kucoin position update: {:?}", value.1);
-                    kucoin_pos_update_disconnect_breaker.on_success();
-                    self.kucoin_position = value.1;
-
-                    tracing::info!(periodic_hedge = false, "Kucoin Position Hedger");
-                    self.hedge(dry_run, ob_map.get(&bluefin), false);
+                Some(HedgerEvent::Shutdown) => {
+                    let (_bluefin_market, order_quantity, _is_buy) = self.calc_net_pos_qty();
+                    if !order_quantity.is_zero() {
+                        tracing::warn!(market = self.market.name, "Hedger stopped with an unhedged quantity of {}", order_quantity);
+                    }
+                    tracing::info!(market = self.market.name, "Shutting down hedger");
+                    return;
                 }
-                Err(mpsc::TryRecvError::Empty) => {}
-                Err(mpsc::TryRecvError::Disconnected) => {
-                    tracing::info!("Kucoin position update worker has disconnected!");
-                    if !kucoin_pos_update_disconnect_breaker.is_open() {
-                        kucoin_pos_update_disconnect_breaker.on_failure();
+                Some(HedgerEvent::Disconnected(feed)) => {
+                    tracing::info!("{:?} worker has disconnected!", feed);
+                    match feed {
+                        HedgerFeed::TakerFill => continue,
+                        HedgerFeed::TakerPosition => taker_pos_update_disconnect_breaker.trip(),
+                        HedgerFeed::TakerOrderBook => taker_ob_breaker.trip(),
+                        HedgerFeed::MakerPosition => maker_pos_update_disconnect_breaker.trip(),
                     }
+                    // a dead feed does not come back, the supervisor restarts the market
+                    tracing::error!(market = self.market.name, "{:?} feed is gone, stopping hedger", feed);
+                    return;
                 }
+                None => {}
             }
 
             //hedge every second regardless of socket logic
             if periodic_hedging_enabled
-                && (last_hedge_time.elapsed() >= Duration::from_secs_f64(periodic_hedging_period)
-                    && ob_map.contains_key(&bluefin))
+                && (last_hedge_time.elapsed() >= periodic_hedging_period
+                    && taker_ob.is_some())
             {
                 tracing::info!(periodic_hedge = true, "Periodic Hedger");
-                self.hedge(dry_run, ob_map.get(&bluefin), true);
+                self.hedge(dry_run, taker_ob.as_ref(), true);
                 last_hedge_time = Instant::now();
             }
         }
     }
 
     fn update_positions(&mut self) {
-        let bluefin_market_for_position = self.market.symbols.bluefin.to_owned().clone();
-        self.kucoin_position = self
-            .kucoin_client
-            .get_position(&bluefin_market_for_position)
-            .expect("Could not fetch Kucoin Position on Period Hedge");
-        self.bluefin_position = self
-            .bluefin_client
-            .get_user_position(&bluefin_market_for_position);
+        let (maker_position, taker_position) = block_on(async {
+            futures::join!(
+                self.maker.get_position(&self.market),
+                self.taker.get_position(&self.market)
+            )
+        });
+        // a failed fetch keeps the last known position, the streams correct it
+        match (maker_position, taker_position) {
+            (Ok(maker_position), Ok(taker_position)) => {
+                self.maker_position = maker_position;
+                self.taker_position = taker_position;
+            }
+            (Err(e), _) | (_, Err(e)) => {
+                tracing::warn!(market = self.market.name, "Could not refresh positions, keeping the last ones: {}", e);
+            }
+        }
     }
 
     fn calc_net_pos_qty(&mut self) -> (String, Decimal, bool) {
         let bluefin_market = self.market.symbols.bluefin.to_owned();
 
-        // unwrap kucoin position and get quantity
-        let kucoin_quantity = Decimal::from(self.kucoin_position.current_qty);
-
-        let current_kucoin_qty = kucoin_quantity / Decimal::from(self.market.lot_size);
+        let target_quantity = self.maker_position * Decimal::from(-1);
 
-        let mut bluefin_quantity = Decimal::from_u128(self.bluefin_position.quantity).unwrap()
-            / Decimal::from(BIGNUMBER_BASE);
-
-        if !self.bluefin_position.side {
-            bluefin_quantity = bluefin_quantity * Decimal::from(-1);
-        }
-
-        let target_quantity = current_kucoin_qty * Decimal::from(-1);
-
-        let diff = target_quantity - bluefin_quantity;
+        let diff = target_quantity - self.taker_position;
 
         let order_quantity = diff.abs();
 
         let is_buy = diff.is_sign_positive();
 
-        // if order_quantity > Decimal::from(0) {
         tracing::info!(
             market = bluefin_market,
-            current_kucoin_qty = current_kucoin_qty.to_f64().unwrap(),
-            bluefin_quantity = bluefin_quantity.to_f64().unwrap(),
+            current_maker_qty = self.maker_position.to_f64().unwrap(),
+            taker_quantity = self.taker_position.to_f64().unwrap(),
             order_quantity = order_quantity.to_f64().unwrap(),
             is_buy = is_buy,
             "Positions Across"
         );
 
-        tracing::info!(
-            market = bluefin_market,
-            avg_entry_price = self.kucoin_position.avg_entry_price,
-            realised_pnl = self.kucoin_position.realised_pnl,
-            unrealised_pnl = self.kucoin_position.unrealised_pnl,
-            unrealised_pnl_pcnt = self.kucoin_position.unrealised_pnl_pcnt,
-            unrealised_roe_pcnt = self.kucoin_position.unrealised_roe_pcnt,
-            liquidation_price = self.kucoin_position.liquidation_price,
-            "Kucoin position"
-        );
-        // }
-
         return (bluefin_market, order_quantity, is_buy);
     }
 
-    fn calc_limit_order_price(hedge_qty: Decimal, is_buy: bool, ob: &OrderBook) -> f64 {
-        let ob_pairs = if is_buy { &ob.asks } else { &ob.bids };
-
-        let mut cumulative_qty: Decimal = Decimal::new(0, hedge_qty.scale());
-
-        let price = ob_pairs.iter().find_map(|price_and_qty| {
-            cumulative_qty = cumulative_qty.add(Decimal::from_f64(price_and_qty.1).unwrap());
-            if cumulative_qty.ge(&hedge_qty) {
-                Some(price_and_qty.0)
-            } else {
-                None
+    // None when the side the hedge takes from is empty
+    fn calc_limit_order_price(hedge_qty: Decimal, is_buy: bool, ob: &OrderBook) -> Option<Price> {
+        match ob.price_for_size(is_buy, Qty::from(hedge_qty)) {
+            Some(price) => {
+                tracing::info!("Hedging at {}", price);
+                Some(price)
+            }
+            None => {
+                //if we get to the end of the depth and no price match - get last price of max depth
+                let (max_depth_price, _) = ob.levels(!is_buy).last()?;
+                tracing::info!(
+                    "Could not match hedge price in Bluefin OB DOM, hedging at max depth {}",
+                    max_depth_price
+                );
+                Some(max_depth_price)
             }
-        });
-
-        if price.is_none() {
-            //if we get to the end of the depth and no price match - get last price of max depth
-            let max_depth_price = ob_pairs.last().unwrap().0;
-            tracing::info!(
-                "Could not match hedge price in Bluefin OB DOM, hedging at max depth {}",
-                max_depth_price
-            );
-            max_depth_price
-        } else {
-            tracing::info!("Hedging at {}", price.unwrap());
-            price.unwrap()
         }
     }
 
     fn hedge(&mut self, dry_run: bool, ob: Option<&OrderBook>, is_periodic: bool) {
-        
-        // let bluefin_market = self.market.symbols.bluefin.to_owned();
-        // self.bluefin_position = self.bluefin_client.get_user_position(&bluefin_market);
-
-
-        let (bluefin_market, order_quantity, is_buy) = self.calc_net_pos_qty();
+        let (_bluefin_market, order_quantity, is_buy) = self.calc_net_pos_qty();
 
         tracing::info!("Hedge Order Quantity {:?}", order_quantity);
 
@@ -534,47 +403,45 @@ impl Hedger for HGR {
                 let order_quantity_f64 = order_quantity.to_f64().unwrap();
                 tracing::debug!("order quantity as f64: {}", order_quantity_f64);
 
-                let price = HGR::calc_limit_order_price(order_quantity, is_buy, ob.unwrap());
+                let Some(price) = HGR::calc_limit_order_price(order_quantity, is_buy, ob.unwrap()) else {
+                    tracing::warn!(
+                        "No {} in the {:?} book to hedge against, skipping the hedge",
+                        if is_buy { "asks" } else { "bids" },
+                        self.taker.kind()
+                    );
+                    return;
+                };
                 tracing::info!(
-                    hedger_order_price = price,
+                    hedger_order_price = %price,
                     hedger_order_quantity = order_quantity_f64,
                     "Hedger Limit Order"
                 );
 
-                let order = self.bluefin_client.create_limit_ioc_order(
-                    &bluefin_market,
+                let status = block_on(self.taker.place_ioc_order(
+                    &self.market,
                     is_buy,
-                    false,
                     price,
-                    order_quantity_f64,
-                    None,
-                );
-
-                tracing::info!("order {:#?}", order);
-                let signature = self.bluefin_client.sign_order(order.clone());
-                let status = self
-                    .bluefin_client
-                    .post_signed_order(order.clone(), signature);
-                tracing::info!("status {:?}", status);
-
-                if status.error.is_some() {
-                    tracing::error!(
-                        "Error posting Hedge Position on Bluefin. {:?}",
-                        status.error.unwrap()
-                    );
-                } else {
-                    tracing::info!("Placed Hedge limit order on Bluefin");
+                    Qty::from(order_quantity),
+                ));
+
+                match status {
+                    Ok(_) => tracing::info!("Placed Hedge limit order on {:?}", self.taker.kind()),
+                    Err(e) if e.code() == Some(&ErrorCode::InsufficientBalance) => {
+                        tracing::error!(
+                            "Insufficient balance on {:?} to hedge {}: {}",
+                            self.taker.kind(),
+                            order_quantity_f64,
+                            e
+                        );
+                    }
+                    // the position is still unhedged, the next hedge sends it again
+                    Err(e) if e.is_transient() => {
+                        tracing::warn!("Hedge order on {:?} failed, retrying on the next hedge: {}", self.taker.kind(), e);
+                    }
+                    Err(e) => {
+                        tracing::error!("Error posting Hedge Position on {:?}. {}", self.taker.kind(), e);
+                    }
                 }
-
-                // //Optimistic approach to prevent oscillations. For now update local position as if the position if filled immediately.
-                // let bf_pos_sign: i128 = if self.bluefin_position.side { 1 } else { -1 };
-                // let bf_signed_pos: i128 = (self.bluefin_position.quantity as i128) * bf_pos_sign;
-                // let order_pos_sign: i128 = if order.isBuy { 1 } else { -1 };
-                // let order_signed_pos: i128 = (order.quantity as i128) * order_pos_sign;
-                // let new_pos = bf_signed_pos + order_signed_pos;
-
-                // self.bluefin_position.quantity = new_pos.abs() as u128;
-                // self.bluefin_position.side = if new_pos > 0 { true } else { false };
             }
         }
     }
@@ -582,118 +449,92 @@ impl Hedger for HGR {
 
 #[cfg(test)]
 pub mod tests {
-    use crate::models::common::OrderBook;
+    use crate::models::order_book::OrderBook;
+    use crate::models::units::{Price, Qty};
 
     use super::Hedger;
     use super::HGR;
     use bigdecimal::FromPrimitive;
     use rust_decimal::Decimal;
+    use std::str::FromStr;
 
-    #[test]
-    fn test_calc_limit_order_price_buy() {
+    fn book() -> OrderBook {
+        let level = |price: &str, size: &str| (Price::from_str(price).unwrap(), Qty::from_str(size).unwrap());
         let asks = vec![
-            (44988.0, 0.001),
-            (44990.9, 2.222),
-            (44997.600000000006, 4.444),
-            (45001.5, 0.001),
-            (45015.200000000004, 0.001),
+            level("44988.0", "0.001"),
+            level("44990.9", "2.222"),
+            level("44997.6", "4.444"),
+            level("45001.5", "0.001"),
+            level("45015.2", "0.001"),
         ];
         let bids = vec![
-            (44977.9, 0.1),
-            (44976.3, 1.867),
-            (44975.0, 1.334),
-            (44972.100000000006, 2.223),
-            (44969.0, 4.447),
+            level("44977.9", "0.1"),
+            level("44976.3", "1.867"),
+            level("44975.0", "1.334"),
+            level("44972.1", "2.223"),
+            level("44969.0", "4.447"),
         ];
 
-        let ob = OrderBook { asks, bids };
+        return OrderBook::from_levels(asks, bids);
+    }
+
+    #[test]
+    fn test_calc_limit_order_price_buy() {
+        let ob = book();
 
         let hedge_qty = 3.000;
         let limit_order_price =
-            HGR::calc_limit_order_price(Decimal::from_f64(hedge_qty).unwrap(), true, &ob);
-        let expected_limit_order_price = 44997.600000000006;
+            HGR::calc_limit_order_price(Decimal::from_f64(hedge_qty).unwrap(), true, &ob).unwrap();
+        let expected_limit_order_price = Price::from_str("44997.6").unwrap();
 
         assert_eq!(expected_limit_order_price, limit_order_price);
     }
 
     #[test]
     fn test_calc_limit_order_price_sell() {
-        let asks = vec![
-            (44988.0, 0.001),
-            (44990.9, 2.222),
-            (44997.600000000006, 4.444),
-            (45001.5, 0.001),
-            (45015.200000000004, 0.001),
-        ];
-        let bids = vec![
-            (44977.9, 0.1),
-            (44976.3, 1.867),
-            (44975.0, 1.334),
-            (44972.100000000006, 2.223),
-            (44969.0, 4.447),
-        ];
-
-        let ob = OrderBook { asks, bids };
+        let ob = book();
 
         let hedge_qty = 3.000;
         let limit_order_price =
-            HGR::calc_limit_order_price(Decimal::from_f64(hedge_qty).unwrap(), false, &ob);
-        let expected_limit_order_price = 44975.0;
+            HGR::calc_limit_order_price(Decimal::from_f64(hedge_qty).unwrap(), false, &ob).unwrap();
+        let expected_limit_order_price = Price::from_str("44975.0").unwrap();
 
         assert_eq!(expected_limit_order_price, limit_order_price);
     }
 
     #[test]
     fn test_calc_limit_order_price_no_match_buy() {
-        let asks = vec![
-            (44988.0, 0.001),
-            (44990.9, 2.222),
-            (44997.600000000006, 4.444),
-            (45001.5, 0.001),
-            (45015.200000000004, 0.001),
-        ];
-        let bids = vec![
-            (44977.9, 0.1),
-            (44976.3, 1.867),
-            (44975.0, 1.334),
-            (44972.100000000006, 2.223),
-            (44969.0, 4.447),
-        ];
-
-        let ob = OrderBook { asks, bids };
+        let ob = book();
 
         let hedge_qty = 100.00;
         let limit_order_price =
-            HGR::calc_limit_order_price(Decimal::from_f64(hedge_qty).unwrap(), true, &ob);
-        let expected_limit_order_price = 45015.200000000004;
+            HGR::calc_limit_order_price(Decimal::from_f64(hedge_qty).unwrap(), true, &ob).unwrap();
+        let expected_limit_order_price = Price::from_str("45015.2").unwrap();
 
         assert_eq!(expected_limit_order_price, limit_order_price);
     }
 
     #[test]
     fn test_calc_limit_order_price_no_match_sell() {
-        let asks = vec![
-            (44988.0, 0.001),
-            (44990.9, 2.222),
-            (44997.600000000006, 4.444),
-            (45001.5, 0.001),
-            (45015.200000000004, 0.001),
-        ];
-        let bids = vec![
-            (44977.9, 0.1),
-            (44976.3, 1.867),
-            (44975.0, 1.334),
-            (44972.100000000006, 2.223),
-            (44969.0, 4.447),
-        ];
-
-        let ob = OrderBook { asks, bids };
+        let ob = book();
 
         let hedge_qty = 100.00;
         let limit_order_price =
-            HGR::calc_limit_order_price(Decimal::from_f64(hedge_qty).unwrap(), false, &ob);
-        let expected_limit_order_price = 44969.0;
+            HGR::calc_limit_order_price(Decimal::from_f64(hedge_qty).unwrap(), false, &ob).unwrap();
+        let expected_limit_order_price = Price::from_str("44969.0").unwrap();
 
         assert_eq!(expected_limit_order_price, limit_order_price);
     }
+
+    #[test]
+    fn test_calc_limit_order_price_empty_side() {
+        let level = |price: &str, size: &str| (Price::from_str(price).unwrap(), Qty::from_str(size).unwrap());
+        let ob = OrderBook::from_levels(Vec::new(), vec![level("44977.9", "0.1")]);
+
+        assert_eq!(HGR::calc_limit_order_price(Decimal::ONE, true, &ob), None);
+        assert_eq!(
+            HGR::calc_limit_order_price(Decimal::ONE, false, &ob),
+            Some(Price::from_str("44977.9").unwrap())
+        );
+    }
 }
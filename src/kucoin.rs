@@ -3,7 +3,6 @@ pub mod models;
 
 pub use crate::kucoin::client::client::Credentials;
 pub use crate::kucoin::client::client::KuCoinClient;
-pub use crate::kucoin::models::CallResponse;
 pub use crate::kucoin::models::TradeOrderMessage;
 pub use crate::kucoin::models::TransactionHistory;
 pub use crate::kucoin::models::AvailableBalance;
@@ -1,25 +1,29 @@
 pub mod client {
-    use crate::env::EnvVars;
-    use crate::{
-        env,
-        kucoin::models::CallResponse,
-        utils,
-    };
+    use crate::clients::error::{ErrorCode, VenueError};
+    use crate::utils;
     #[allow(deprecated)]
     use base64::encode;
     use hmac::{Hmac, Mac};
     use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+    use reqwest::StatusCode;
     use serde_json::{json, Value};
     use sha2::Sha256;
-    use snailquote::unescape;
     use std::collections::HashMap;
     use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
     #[allow(unused)]
     type HmacSha256 = Hmac<Sha256>;
 
+    static VENUE: VenueKind = VenueKind::Kucoin;
+    static SUCCESS_CODE: &str = "200000";
+
     use crate::kucoin::models::{Error, FillsResponse, Method, RecentFillsResponse, Response, TransactionHistory};
-    use crate::models::kucoin_models::{PositionList, KucoinUserPosition};
+    use crate::models::kucoin_models::{Level2Snapshot, PositionList, KucoinUserPosition, TradeFees};
+    use crate::clients::executor::{RequestExecutor, RetryPolicy};
+    use crate::clients::rate_limit::TokenBucket;
+    use crate::sockets::connection::{Endpoint, Heartbeat};
+    use crate::models::common::VenueKind;
+    use crate::models::units::Price;
 
     #[derive(Debug, Clone)]
     pub struct Credentials {
@@ -47,7 +51,7 @@ pub mod client {
         onboarding_url: String,
         #[allow(unused)]
         websocket_url: String,
-        client: reqwest::blocking::Client,
+        executor: RequestExecutor,
         leverage: u128,
         markets: HashMap<String, String>,
     }
@@ -60,6 +64,30 @@ pub mod client {
             onboarding_url: &str,
             websocket_url: &str,
             leverage: u128,
+        ) -> KuCoinClient {
+            let client = reqwest::Client::builder()
+                .timeout(Duration::from_secs(60))
+                .build()
+                .unwrap();
+
+            return KuCoinClient::with_executor(
+                RequestExecutor::for_venue(VenueKind::Kucoin, client),
+                credentials,
+                api_gateway,
+                onboarding_url,
+                websocket_url,
+                leverage,
+            );
+        }
+
+        // same as `new` but sends its requests through a shared executor, connection pool and request budget
+        pub fn with_executor(
+            executor: RequestExecutor,
+            credentials: Credentials,
+            api_gateway: &str,
+            onboarding_url: &str,
+            websocket_url: &str,
+            leverage: u128,
         ) -> KuCoinClient {
             // TODO read from config and pass as variable when creating kucoin client
             let mut markets: HashMap<String, String> = HashMap::new();
@@ -67,18 +95,13 @@ pub mod client {
             markets.insert("BTC-PERP".to_string(), "XBTUSDTM".to_string());
             markets.insert("SUI-PERP".to_string(), "SUIUSDTM".to_string());
 
-            let client = reqwest::blocking::Client::builder()
-                .timeout(Duration::from_secs(60))
-                .build()
-                .unwrap();
-
             let kucoin_client = KuCoinClient {
                 credentials,
                 api_gateway: api_gateway.to_string(),
                 onboarding_url: onboarding_url.to_string(),
                 websocket_url: websocket_url.to_string(),
                 leverage,
-                client,
+                executor,
                 markets,
             };
 
@@ -87,47 +110,92 @@ pub mod client {
             return kucoin_client;
         }
 
-        pub fn get_private_token(&self) -> String {
-            let endpoint = String::from("/api/v1/bullet-private");
+        /**
+         * Signs and sends a request to the api gateway, signing again on every attempt
+         * so retried requests do not carry a stale timestamp
+         */
+        async fn send(
+            &self,
+            policy: &RetryPolicy,
+            method: Method,
+            endpoint: &str,
+            params: Option<&HashMap<String, String>>,
+            query: Option<&str>,
+        ) -> Result<Value, VenueError> {
+            let url: String = format!("{}{}{}", &self.api_gateway, endpoint, query.unwrap_or(""));
+
+            let build = |http: &reqwest::Client| {
+                let headers: HeaderMap =
+                    self.sign_headers(endpoint.to_string(), params, query.map(String::from), method);
+                let request = match method {
+                    Method::GET => http.get(&url),
+                    Method::POST => http.post(&url),
+                    Method::PUT => http.put(&url),
+                    Method::DELETE => http.delete(&url),
+                }
+                .headers(headers);
+                match (method, params) {
+                    (Method::POST, Some(p)) => request.json(&json!(p)),
+                    _ => request,
+                }
+            };
 
-            let url: String = format!("{}{}", &self.api_gateway, endpoint);
+            return self.executor.execute(policy, build, parse_response).await;
+        }
 
-            let headers: HeaderMap = self.sign_headers(endpoint.clone(), None, None, Method::POST);
+        /**
+         * Exposes the request budget so callers can check whether a burst of calls fits
+         */
+        pub fn budget(&self) -> &TokenBucket {
+            return self.executor.budget();
+        }
 
-            let res = self.client.post(url).headers(headers).send().unwrap();
+        pub async fn get_private_token(&self) -> Result<Response, VenueError> {
+            let endpoint = String::from("/api/v1/bullet-private");
 
-            let body = res.text().unwrap();
+            let value = self
+                .send(&RetryPolicy::idempotent(), Method::POST, &endpoint, None, None)
+                .await?;
 
-            let resp: Response = serde_json::from_str(&body).expect("JSON Decoding failed");
+            return VenueError::decode(VENUE, value);
+        }
 
-            resp.data.token
+        pub async fn get_kucoin_private_socket_url(&self) -> Result<Endpoint, VenueError> {
+            let resp = self.get_private_token().await?;
+            return Ok(self.socket_endpoint(resp));
         }
 
-        pub fn get_kucoin_private_socket_url(&self) -> String {
-            let vars: EnvVars = env::env_variables();
-            let token = self.get_private_token();
-            let kucoin_futures_wss_url = format!("{}?token={}", &vars.kucoin_websocket_url, token);
-            kucoin_futures_wss_url
+        /**
+         * Public websocket url with a fresh token, requested through the shared connection pool
+         */
+        pub async fn get_public_socket_url(&self) -> Result<Endpoint, VenueError> {
+            let value = self
+                .executor
+                .execute(
+                    &RetryPolicy::idempotent(),
+                    |http: &reqwest::Client| http.post(&self.onboarding_url),
+                    parse_response,
+                )
+                .await?;
+
+            let resp: Response = VenueError::decode(VENUE, value)?;
+
+            return Ok(self.socket_endpoint(resp));
         }
 
-        pub fn get_token(onboarding_url: &str) -> String {
-            let client = reqwest::blocking::Client::new();
-
-            let resp: Response = serde_json::from_str(
-                client
-                    .post(onboarding_url)
-                    .send()
-                    .unwrap()
-                    .text()
-                    .unwrap()
-                    .as_str(),
-            )
-            .unwrap();
-
-            return resp.data.token;
+        // tokenized url, pinged at the interval the token was issued with
+        fn socket_endpoint(&self, resp: Response) -> Endpoint {
+            let mut endpoint = Endpoint::new(&format!("{}?token={}", &self.websocket_url, resp.data.token));
+            if let Some(server) = resp.data.instance_servers.first() {
+                endpoint.heartbeat = Heartbeat {
+                    interval: Duration::from_millis(server.ping_interval),
+                    timeout: Duration::from_millis(server.ping_timeout),
+                };
+            }
+            return endpoint;
         }
 
-        pub fn get_recent_fills(&self, market: &str) -> RecentFillsResponse {
+        pub async fn get_recent_fills(&self, market: &str) -> Result<RecentFillsResponse, VenueError> {
             let endpoint = String::from("/api/v1/recentFills");
 
             let market_symbol = self.markets.get(market).unwrap();
@@ -137,27 +205,14 @@ pub mod client {
 
             let query = utils::format_query(&params);
 
-            let url: String = format!("{}{}{}", &self.api_gateway, endpoint, query);
-
-            let headers: HeaderMap =
-                self.sign_headers(endpoint.clone(), None, Some(query), Method::GET);
+            let value = self
+                .send(&RetryPolicy::idempotent(), Method::GET, &endpoint, None, Some(&query))
+                .await?;
 
-            let res: String = self
-                .client
-                .get(url)
-                .headers(headers)
-                .send()
-                .unwrap()
-                .text()
-                .unwrap();
-
-            let resp: RecentFillsResponse =
-                serde_json::from_str(&res).expect("JSON Decoding failed");
-
-            return resp;
+            return VenueError::decode(VENUE, value);
         }
 
-        pub fn get_fills(
+        pub async fn get_fills(
             &self,
             market: &str,
             side: Option<&str>,
@@ -166,7 +221,7 @@ pub mod client {
             end_at: Option<u128>,
             current_page: Option<u64>,
             page_size: Option<u64>,
-        ) -> FillsResponse {
+        ) -> Result<FillsResponse, VenueError> {
             let endpoint = String::from("/api/v1/fills");
 
             let market_symbol = self.markets.get(market).unwrap();
@@ -201,89 +256,61 @@ pub mod client {
 
             let query = utils::format_query(&params);
 
-            let url: String = format!("{}{}{}", &self.api_gateway, endpoint, query);
-
-            let headers: HeaderMap =
-                self.sign_headers(endpoint.clone(), None, Some(query), Method::GET);
-
-            let res: String = self
-                .client
-                .get(url)
-                .headers(headers)
-                .send()
-                .unwrap()
-                .text()
-                .unwrap();
-
-            let resp: FillsResponse = serde_json::from_str(&res).expect("JSON Decoding failed");
+            let value = self
+                .send(&RetryPolicy::idempotent(), Method::GET, &endpoint, None, Some(&query))
+                .await?;
 
-            return resp;
+            return VenueError::decode(VENUE, value);
         }
 
-        pub fn get_fill_size_for_time_window(&self, market: &str, side: &str, since: u128) -> i32 {
+        pub async fn get_fill_size_for_time_window(
+            &self,
+            market: &str,
+            side: &str,
+            since: u128,
+        ) -> Result<i32, VenueError> {
             let now = SystemTime::now()
                 .duration_since(UNIX_EPOCH)
                 .expect("could not get current time since unix epoch")
                 .as_millis();
-            let fills = self.get_fills(
-                market,
-                Some(side),
-                None,
-                Some(since),
-                Some(now),
-                None,
-                Some(1000),
-            );
+            let fills = self
+                .get_fills(
+                    market,
+                    Some(side),
+                    None,
+                    Some(since),
+                    Some(now),
+                    None,
+                    Some(1000),
+                )
+                .await?;
             let total_size = fills
                 .data
                 .items
                 .iter()
                 .fold(0, |acc, trade| acc + trade.size);
-            total_size
+            return Ok(total_size);
         }
 
-        pub fn get_transaction_history(&self) -> TransactionHistory{
+        pub async fn get_transaction_history(&self) -> Result<TransactionHistory, VenueError> {
             let endpoint = String::from("/api/v1/transaction-history");
-            let url: String = format!("{}{}", &self.api_gateway, endpoint);
-
-            let headers: HeaderMap =
-                self.sign_headers(endpoint.clone(), None, None, Method::GET);
-
-            let res: String = self
-                .client
-                .get(url)
-                .headers(headers)
-                .send()
-                .unwrap()
-                .text()
-                .unwrap();
-
-            let transaction_history: TransactionHistory  = serde_json::from_str(&res).expect("Can't parse");
-            transaction_history
+            let value = self
+                .send(&RetryPolicy::idempotent(), Method::GET, &endpoint, None, None)
+                .await?;
 
+            return VenueError::decode(VENUE, value);
         }
 
-        pub fn get_position_list(&self) -> PositionList{
+        pub async fn get_position_list(&self) -> Result<PositionList, VenueError> {
             let endpoint = String::from("/api/v1/positions");
-            let url: String = format!("{}{}", &self.api_gateway, endpoint);
-
-            let headers: HeaderMap =
-                self.sign_headers(endpoint.clone(), None, None, Method::GET);
-
-            let res: String = self
-                .client
-                .get(url)
-                .headers(headers)
-                .send()
-                .unwrap()
-                .text()
-                .unwrap();
+            let value = self
+                .send(&RetryPolicy::idempotent(), Method::GET, &endpoint, None, None)
+                .await?;
 
-            let position_list: PositionList  = serde_json::from_str(&res).expect("Can't parse");
-            position_list
+            return VenueError::decode(VENUE, value);
         }
 
-        pub fn get_position(&self, market: &str) -> Option<KucoinUserPosition> {
+        pub async fn get_position(&self, market: &str) -> Result<KucoinUserPosition, VenueError> {
             let endpoint = String::from("/api/v1/position");
             let market_symbol = self.markets.get(market).unwrap();
 
@@ -291,45 +318,56 @@ pub mod client {
             params.insert(String::from("symbol"), market_symbol.clone());
             let query = utils::format_query(&params);
 
-            let url: String = format!("{}{}{}", &self.api_gateway, endpoint, query);
+            let mut value = self
+                .send(&RetryPolicy::idempotent(), Method::GET, &endpoint, None, Some(&query))
+                .await?;
 
-            let headers: HeaderMap =
-                self.sign_headers(endpoint.clone(), None, Some(query), Method::GET);
+            let user_position: KucoinUserPosition = VenueError::decode(VENUE, value["data"].take())?;
+            tracing::debug!("Got position: {:#?}", user_position);
+            return Ok(user_position);
+        }
 
-            let res: String = self
-                .client
-                .get(url)
-                .headers(headers)
-                .send()
-                .unwrap()
-                .text()
-                .unwrap();
+        /**
+         * Full depth order book of a contract, `symbol` is the KuCoin contract symbol
+         */
+        pub async fn get_level2_snapshot(&self, symbol: &str) -> Result<Level2Snapshot, VenueError> {
+            let endpoint = String::from("/api/v1/level2/snapshot");
+            let query = format!("?symbol={}", symbol);
 
-            let value: Value = serde_json::from_str(&res).expect("JSON Decoding failed");
+            let mut value = self
+                .send(&RetryPolicy::idempotent(), Method::GET, &endpoint, None, Some(&query))
+                .await?;
 
-            if value["code"].to_string().eq("\"200000\"") {
-                let user_position: KucoinUserPosition =
-                    serde_json::from_value(value["data"].clone()).unwrap();
+            return VenueError::decode(VENUE, value["data"].take());
+        }
 
-                tracing::debug!("Got position: {:#?}", user_position);
-                return Some(user_position);
-            } else {
-                let error: Error = serde_json::from_str(&res).expect("JSON Decoding failed");
-                tracing::warn!("Error getting kucoin position: {:#?}", error);
-                return None;
-            }
+        /**
+         * Maker and taker fee rates of the account on a contract, `symbol` is the KuCoin contract symbol
+         */
+        pub async fn get_trade_fees(&self, symbol: &str) -> Result<TradeFees, VenueError> {
+            let endpoint = String::from("/api/v1/trade-fees");
+            let query = format!("?symbol={}", symbol);
+
+            let mut value = self
+                .send(&RetryPolicy::idempotent(), Method::GET, &endpoint, None, Some(&query))
+                .await?;
+
+            return VenueError::decode(VENUE, value["data"].take());
         }
 
-        pub fn place_limit_order(
+        /**
+         * Places a post only limit order and returns its order id
+         */
+        pub async fn place_limit_order(
             &self,
             market: &str,
             is_buy: bool,
-            price: f64,
+            price: Price,
             quantity: u128,
-        ) -> CallResponse {
+            leverage: Option<u128>,
+        ) -> Result<String, VenueError> {
             let endpoint = String::from("/api/v1/orders");
-
-            let url: String = format!("{}{}", &self.api_gateway, endpoint);
+            let leverage = leverage.unwrap_or(self.leverage);
 
             let side = if is_buy { "buy" } else { "sell" };
             let market_symbol = self.markets.get(market).unwrap();
@@ -340,74 +378,62 @@ pub mod client {
             params.insert(String::from("side"), side.to_string());
             params.insert(String::from("price"), price.to_string());
             params.insert(String::from("size"), quantity.to_string());
-            params.insert(String::from("leverage"), self.leverage.to_string());
+            params.insert(String::from("leverage"), leverage.to_string());
             params.insert(String::from("postOnly"), "true".to_string());
 
-            let headers: HeaderMap =
-                self.sign_headers(endpoint.clone(), Some(&params), None, Method::POST);
+            return self.post_order(endpoint, params).await;
+        }
 
-            let res = self
-                .client
-                .post(url)
-                .headers(headers)
-                .json(&json!(params))
-                .send()
-                .unwrap();
+        /**
+         * Market order that can only shrink the current position, used to flatten on shutdown
+         */
+        pub async fn place_reduce_only_market_order(
+            &self,
+            market: &str,
+            is_buy: bool,
+            quantity: u128,
+            leverage: Option<u128>,
+        ) -> Result<String, VenueError> {
+            let endpoint = String::from("/api/v1/orders");
+            let leverage = leverage.unwrap_or(self.leverage);
 
-            let response_body: String = res.text().unwrap();
-            let value: Value = serde_json::from_str(&response_body).expect("JSON Decoding failed");
+            let side = if is_buy { "buy" } else { "sell" };
+            let market_symbol = self.markets.get(market).unwrap();
 
-            if value["code"].to_string().eq("\"200000\"") {
-                tracing::debug!("Futures order placed successfully: {}", response_body);
-                let order_id = unescape(value["data"]["orderId"].as_str().unwrap()).unwrap();
-                return CallResponse {
-                    error: None,
-                    order_id: Some(order_id),
-                };
-            } else {
-                let error: Error =
-                    serde_json::from_str(&response_body).expect("JSON Decoding failed");
+            let mut params: HashMap<String, String> = HashMap::new();
+            params.insert(String::from("clientOid"), utils::get_random_string());
+            params.insert(String::from("symbol"), market_symbol.to_string());
+            params.insert(String::from("side"), side.to_string());
+            params.insert(String::from("type"), "market".to_string());
+            params.insert(String::from("size"), quantity.to_string());
+            params.insert(String::from("leverage"), leverage.to_string());
+            params.insert(String::from("reduceOnly"), "true".to_string());
 
-                tracing::warn!("Error placing futures order: {:#?}", error);
-                return CallResponse {
-                    error: Some(error),
-                    order_id: None,
-                };
-            }
+            return self.post_order(endpoint, params).await;
         }
 
-        pub fn cancel_order_by_id(&self, order_id: &str) -> CallResponse {
-            let endpoint = format!("/api/v1/orders/{}", order_id);
-            let url = format!("{}{}", &self.api_gateway, endpoint);
-
-            let headers: HeaderMap = self.sign_headers(endpoint, None, None, Method::DELETE);
+        async fn post_order(&self, endpoint: String, params: HashMap<String, String>) -> Result<String, VenueError> {
+            let value = self
+                .send(&RetryPolicy::orders(), Method::POST, &endpoint, Some(&params), None)
+                .await?;
 
-            let resp = self.client.delete(url).headers(headers).send().unwrap();
+            tracing::debug!("Futures order placed successfully: {}", value);
+            let order_id: String = VenueError::decode(VENUE, value["data"]["orderId"].clone())?;
+            return Ok(order_id);
+        }
 
-            let response_body: String = resp.text().unwrap();
-            let value: Value = serde_json::from_str(&response_body).expect("JSON Decoding failed");
+        pub async fn cancel_order_by_id(&self, order_id: &str) -> Result<(), VenueError> {
+            let endpoint = format!("/api/v1/orders/{}", order_id);
+            let value = self
+                .send(&RetryPolicy::idempotent(), Method::DELETE, &endpoint, None, None)
+                .await?;
 
-            if value["code"].to_string().eq("\"200000\"") {
-                tracing::debug!("Order successfully cancelled: {}", response_body);
-                return CallResponse {
-                    error: None,
-                    order_id: None,
-                };
-            } else {
-                let error: Error =
-                    serde_json::from_str(response_body.as_str()).expect("JSON Decoding failed");
-                tracing::warn!("Error cancelling order: {:#?}", error);
-                return CallResponse {
-                    error: Some(error),
-                    order_id: None,
-                };
-            }
+            tracing::debug!("Order successfully cancelled: {}", value);
+            return Ok(());
         }
 
-        pub fn cancel_all_orders(&self, market: Option<&str>) -> CallResponse {
+        pub async fn cancel_all_orders(&self, market: Option<&str>) -> Result<(), VenueError> {
             let endpoint: String = String::from("/api/v1/orders");
-            let url: String;
-            let headers: HeaderMap;
             let mut params: HashMap<String, String> = HashMap::new();
 
             if let Some(s) = market {
@@ -415,33 +441,17 @@ pub mod client {
                 params.insert(String::from("symbol"), market_symbol.to_owned());
             };
 
-            if !params.is_empty() {
-                let query = utils::format_query(&params);
-                url = format!("{}{}{}", &self.api_gateway, endpoint, query);
-                headers = self.sign_headers(endpoint, None, Some(query), Method::DELETE);
+            let query = if params.is_empty() {
+                None
             } else {
-                url = format!("{}{}", &self.api_gateway, endpoint);
-                headers = self.sign_headers(endpoint, None, None, Method::DELETE);
-            }
+                Some(utils::format_query(&params))
+            };
 
-            let resp = self.client.delete(url).headers(headers).send().unwrap();
+            self.send(&RetryPolicy::idempotent(), Method::DELETE, &endpoint, None, query.as_deref())
+                .await?;
 
-            if resp.status().is_success() {
-                let response_body = resp.text().unwrap();
-                tracing::debug!("Order successfully cancelled");
-                return CallResponse {
-                    error: None,
-                    order_id: None,
-                };
-            } else {
-                let error: Error =
-                    serde_json::from_str(&resp.text().unwrap()).expect("JSON Decoding failed");
-                tracing::warn!("Error cancelling orders: {:#?}", error);
-                return CallResponse {
-                    error: Some(error),
-                    order_id: None,
-                };
-            }
+            tracing::debug!("Order successfully cancelled");
+            return Ok(());
         }
 
         pub fn sign_headers(
@@ -527,8 +537,34 @@ pub mod client {
         }
     }
 
-    #[test]
-    fn should_create_kucoin_client() {
+    /**
+     * KuCoin answers with a `code` field, anything other than 200000 is turned into
+     * an exchange error
+     */
+    fn parse_response(status: StatusCode, body: &str) -> Result<Value, VenueError> {
+        let value: Value = match serde_json::from_str(body) {
+            Ok(value) => value,
+            Err(_) if !status.is_success() => return Err(VenueError::http(VENUE, status, body)),
+            Err(source) => return Err(VenueError::Decode { venue: VENUE, source }),
+        };
+
+        if value["code"].as_str() == Some(SUCCESS_CODE) {
+            return Ok(value);
+        }
+
+        return match VenueError::decode::<Error>(VENUE, value) {
+            Ok(error) => Err(VenueError::Exchange {
+                venue: VENUE,
+                code: ErrorCode::from_kucoin(&error.code),
+                message: error.msg,
+            }),
+            Err(_) if !status.is_success() => Err(VenueError::http(VENUE, status, body)),
+            Err(e) => Err(e),
+        };
+    }
+
+    #[tokio::test]
+    async fn should_create_kucoin_client() {
         let credentials = Credentials::new("key", "secret", "phrase");
 
         let _ = KuCoinClient::new(
@@ -540,8 +576,8 @@ pub mod client {
         );
     }
 
-    #[test]
-    fn should_get_user_position_on_kucoin() {
+    #[tokio::test]
+    async fn should_get_user_position_on_kucoin() {
         let credentials: Credentials = Credentials::new("1", "2", "3");
 
         let client = KuCoinClient::new(
@@ -552,13 +588,13 @@ pub mod client {
             3,
         );
 
-        client.get_position("ETH-PERP");
+        let _ = client.get_position("ETH-PERP").await;
 
         assert!(true, "Error while placing order");
     }
 
-    #[test]
-    fn should_post_order_on_kucoin() {
+    #[tokio::test]
+    async fn should_post_order_on_kucoin() {
         let credentials = Credentials::new("1", "2", "3");
 
         let client = KuCoinClient::new(
@@ -569,15 +605,18 @@ pub mod client {
             3,
         );
 
-        let resp: CallResponse = client.place_limit_order("SUI-PERP", true, 0.70, 1);
+        let order_id = client
+            .place_limit_order("SUI-PERP", true, "0.70".parse().unwrap(), 1, None)
+            .await
+            .expect("Error while placing order");
 
-        println!("Placed order with id: {}", resp.order_id.unwrap());
+        println!("Placed order with id: {}", order_id);
 
         assert!(true, "Error while placing order");
     }
 
-    #[test]
-    fn should_cancel_the_open_order_by_id() {
+    #[tokio::test]
+    async fn should_cancel_the_open_order_by_id() {
         let credentials = Credentials::new("1", "2", "3");
 
         let client = KuCoinClient::new(
@@ -588,13 +627,13 @@ pub mod client {
             3,
         );
 
-        client.cancel_order_by_id("12132131231");
+        let _ = client.cancel_order_by_id("12132131231").await;
 
         assert!(true, "Error cancelling the order");
     }
 
-    #[test]
-    fn should_cancel_all_orders_for_eth_market() {
+    #[tokio::test]
+    async fn should_cancel_all_orders_for_eth_market() {
         let credentials = Credentials::new("1", "2", "3");
 
         let client = KuCoinClient::new(
@@ -605,16 +644,16 @@ pub mod client {
             3,
         );
 
-        let resp = client.cancel_all_orders(Some("ETH-PERP"));
+        let resp = client.cancel_all_orders(Some("ETH-PERP")).await;
 
         assert!(
-            resp.error.is_none(),
+            resp.is_ok(),
             "Error cancelling all orders for ETH market"
         );
     }
 
-    #[test]
-    fn should_cancel_all_orders_for_all_markets() {
+    #[tokio::test]
+    async fn should_cancel_all_orders_for_all_markets() {
         let credentials = Credentials::new("1", "2", "3");
 
         let client = KuCoinClient::new(
@@ -625,15 +664,15 @@ pub mod client {
             3,
         );
 
-        let resp = client.cancel_all_orders(None);
+        let resp = client.cancel_all_orders(None).await;
 
         assert!(
-            resp.error.is_none(),
+            resp.is_ok(),
             "Error cancelling all orders for all markets"
         );
     }
-    #[test]
-    fn should_get_recent_fills() {
+    #[tokio::test]
+    async fn should_get_recent_fills() {
         let credentials = Credentials::new(
             "654bad2744b9f1000170a857",
             "cc0f02dd-9070-4f65-8d60-8bc0d6bfcd8a",
@@ -648,13 +687,16 @@ pub mod client {
             3,
         );
 
-        let resp = client.get_recent_fills("ETH-PERP");
+        let resp = client
+            .get_recent_fills("ETH-PERP")
+            .await
+            .expect("Error getting recent fills");
 
         assert_eq!(resp.code, "200000", "Error getting recent fills");
     }
 
-    #[test]
-    fn should_get_fills() {
+    #[tokio::test]
+    async fn should_get_fills() {
         let credentials = Credentials::new(
             "654bad2744b9f1000170a857",
             "cc0f02dd-9070-4f65-8d60-8bc0d6bfcd8a",
@@ -677,13 +719,15 @@ pub mod client {
             Some(1700838297000),
             None,
             None,
-        );
+        )
+        .await
+        .expect("Error getting fills");
 
         assert_eq!(resp.code, "200000", "Error getting recent fills");
     }
 
-    #[test]
-    fn should_get_total_fill_size() {
+    #[tokio::test]
+    async fn should_get_total_fill_size() {
         let credentials = Credentials::new(
             "654bad2744b9f1000170a857",
             "cc0f02dd-9070-4f65-8d60-8bc0d6bfcd8a",
@@ -703,8 +747,14 @@ pub mod client {
             .expect("could not get current time since unix epoch")
             .as_millis();
         let since = now - 1000 * 60 * 60 * 24;
-        let total_buy_size = client.get_fill_size_for_time_window("BTC-PERP", "buy", since);
-        let total_sell_size = client.get_fill_size_for_time_window("BTC-PERP", "sell", since);
+        let total_buy_size = client
+            .get_fill_size_for_time_window("BTC-PERP", "buy", since)
+            .await
+            .expect("Error getting buy fills");
+        let total_sell_size = client
+            .get_fill_size_for_time_window("BTC-PERP", "sell", since)
+            .await
+            .expect("Error getting sell fills");
         let _buy_percent =
             (total_buy_size as f64 / ((total_buy_size + total_sell_size) as f64)) * 100.0;
 
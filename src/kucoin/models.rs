@@ -7,9 +7,6 @@ use crate::models::kucoin_models::KucoinUserPosition;
 #[derive(Deserialize, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct InstanceServer {
-    pub endpoint: String,
-    pub encrypt: bool,
-    pub protocol: String,
     pub ping_interval: u64,
     pub ping_timeout: u64,
 }
@@ -33,12 +30,6 @@ pub struct Error {
     pub msg: String,
 }
 
-#[derive(Deserialize, Debug)]
-pub struct CallResponse {
-    pub error: Option<Error>,
-    pub order_id: Option<String>,
-}
-
 #[derive(Deserialize, Debug)]
 pub struct UserPosition {
     pub symbol: String,
@@ -61,7 +52,7 @@ pub struct UserPosition {
     #[serde(rename = "liquidationPrice")]
     pub liquidation_price: f64,
 }
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Clone, Copy)]
 #[serde(rename_all = "camelCase")]
 pub enum Method {
     GET,
@@ -91,9 +82,10 @@ pub struct TradeOrderData {
     #[serde(rename = "type")]
     pub type_field: String,
     pub status: String,
-    #[serde(deserialize_with = "deserialize_optional_f64")]
+    // match, filled, cancelled and old sizes and the liquidity only come with the changes they are about
+    #[serde(default, deserialize_with = "deserialize_optional_f64")]
     pub match_size: Option<f64>,
-    #[serde(deserialize_with = "deserialize_optional_f64")]
+    #[serde(default, deserialize_with = "deserialize_optional_f64")]
     pub match_price: Option<f64>,
     pub order_type: String,
     pub side: String,
@@ -103,15 +95,16 @@ pub struct TradeOrderData {
     pub size: f64,
     #[serde(deserialize_with = "deserialize_string_to_f64")]
     pub remain_size: f64,
-    #[serde(deserialize_with = "deserialize_string_to_f64")]
+    #[serde(default, deserialize_with = "deserialize_string_to_f64")]
     pub filled_size: f64,
-    #[serde(deserialize_with = "deserialize_string_to_f64")]
+    #[serde(default, deserialize_with = "deserialize_string_to_f64")]
     pub canceled_size: f64,
     pub trade_id: Option<String>,
     pub client_oid: String,
     pub order_time: u128,
-    #[serde(deserialize_with = "deserialize_string_to_f64")]
+    #[serde(default, deserialize_with = "deserialize_string_to_f64")]
     pub old_size: f64,
+    #[serde(default)]
     pub liquidity: String,
     pub ts: u128,
 }
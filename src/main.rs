@@ -1,8 +1,12 @@
-use std::thread::JoinHandle;
-use std::{fs, panic, process, thread};
-use std::sync::mpsc::Sender;
+// functions end in an explicit `return` throughout the crate
+#![allow(clippy::needless_return)]
 
+use std::panic::AssertUnwindSafe;
+use std::{panic, process, thread};
+use std::sync::Arc;
 
+
+mod binance;
 mod bluefin;
 mod env;
 mod hedge;
@@ -13,104 +17,59 @@ mod sockets;
 mod statistics;
 mod tests;
 mod utils;
+mod venues;
 
 mod circuit_breakers;
-
-use crate::market_maker::mm::{MarketMaker, MM};
-
-use crate::hedge::hedger::{Hedger, HGR};
-use crate::models::common::Config;
-use crate::statistics::stats::{Statistics, Stats};
-use env::EnvVars;
-use kucoin::AvailableBalance;
-use crate::bluefin::AccountData;
+mod clients;
+mod config;
+mod runner;
+
+use crate::config::app_config::{AppConfig, CONFIG_PATH};
+use crate::runner::shutdown::ShutdownReason;
+use crate::runner::signals;
+use crate::runner::signals::ProcessSignal;
+use crate::runner::supervisor::{Supervisor, SupervisorEvent};
 use crate::statistics::account_stats::{AccountStatistics, AccountStats};
-use crate::bluefin::TradeOrderUpdate;
 
 fn main() {
-    // Set a custom global panic hook
+    // Set a custom global panic hook. Worker panics are caught by the supervisor
+    // which restarts the market, a panic of the supervisor shuts every market down
     panic::set_hook(Box::new(|info| {
         // Log the panic information
         tracing::error!("Panic occurred: {:?}", info);
-
-        // Exit with a non-zero status code to indicate error
-        process::exit(1);
     }));
 
-    // get env variables
-    let vars: EnvVars = env::env_variables();
+    // load env variables and config.json once, every component shares the result
+    let config: Arc<AppConfig> = match AppConfig::load(CONFIG_PATH) {
+        Ok(config) => Arc::new(config),
+        Err(e) => {
+            eprintln!("Invalid configuration: {}", e);
+            process::exit(1);
+        }
+    };
 
     // initialize trace logger and hold on to the guard. Without the guard
     // the logs won't be written to log file
-    let _guard = env::init_logger(vars.log_level);
-
-    let config_str =
-        fs::read_to_string("src/config/config.json").expect("Unable to read config.json");
-    let config: Config = serde_json::from_str(&config_str).expect("JSON was not well-formatted");
-
-    let mut v_tx_account_data: Vec<Sender<AccountData>> = Vec::new();
-    let mut v_tx_account_data_kc: Vec<Sender<AvailableBalance>> = Vec::new();
-    let mut v_tx_account_data_bluefin_user_trade: Vec<Sender<TradeOrderUpdate>> = Vec::new();
-    let mut mm_handles: Vec<JoinHandle<()>> = Vec::new();
-    let mut statistic_handles: Vec<JoinHandle<()>> = Vec::new();
-    let mut hgr_handles: Vec<JoinHandle<()>> = Vec::new();
-
-    for market in config.markets.clone() {
-        let (
-            mut mm, 
-            tx_stats, 
-            tx_account_data, 
-            tx_account_data_kc, 
-            tx_hedger,
-            rx_bluefin_hedger_ob,
-            tx_account_data_bluefin_user_trade,
-        ) = MM::new(market.clone(), config.circuit_breaker_config.clone());
-
-        let mm_handle = thread::spawn(move || {
-            mm.connect();
-        });
-
-        let market_clone_for_stats = market.clone(); // Clone market for the stats thread
-
-        let stats_handle = thread::spawn(move || {
-            Stats::new(market_clone_for_stats.clone(), tx_stats).emit();
-        });
-
-        v_tx_account_data.push(tx_account_data);
-        v_tx_account_data_kc.push(tx_account_data_kc);
-        v_tx_account_data_bluefin_user_trade.push(tx_account_data_bluefin_user_trade);
-
-        let market_clone_for_hgr = market.clone(); // Clone market again for the hedger thread
-
-        let hgr_handle = thread::spawn(move || {
-            HGR::new(
-                market_clone_for_hgr.clone(), 
-                config.circuit_breaker_config.clone(), 
-                tx_hedger, 
-                rx_bluefin_hedger_ob
-            ).connect();
-        });
-
-        mm_handles.push(mm_handle);
-        statistic_handles.push(stats_handle);
-        hgr_handles.push(hgr_handle);
-    }
-
-
-
-    let account_stats_handle = thread::spawn(move || {
-        AccountStats::new(config, v_tx_account_data, v_tx_account_data_kc, v_tx_account_data_bluefin_user_trade).log();
+    let guard = env::init_logger(config.vars.log_level.clone());
+
+    let mut account_stats = AccountStats::new(config.clone());
+    let mut supervisor = Supervisor::new(config, account_stats.event_sender());
+    signals::listen(supervisor.event_sender());
+
+    let tx_supervisor = supervisor.event_sender();
+    let _account_stats_handle = thread::spawn(move || {
+        let _ = panic::catch_unwind(AssertUnwindSafe(|| account_stats.log()));
+        let _ = tx_supervisor.send(SupervisorEvent::Signal(ProcessSignal::Shutdown(
+            ShutdownReason::AccountStatsFailed,
+        )));
     });
 
-    let mut combined_handles = mm_handles;
-    combined_handles.extend(statistic_handles);
-    combined_handles.extend(hgr_handles);
-
-
-    // Wait for all threads to complete in one pass
-    combined_handles.into_iter().for_each(|handle| {
-        handle.join().expect("Thread failed to join main");
-    });
+    // the markets keep quoting after a supervisor panic, they are shut down like on SIGTERM
+    let reason = panic::catch_unwind(AssertUnwindSafe(|| supervisor.run())).unwrap_or(ShutdownReason::Panic);
+    supervisor.shutdown(reason);
 
-    account_stats_handle.join().expect("Thread failed to join main");
+    // process::exit skips destructors, flush the log file first
+    drop(guard);
+    let failed = matches!(reason, ShutdownReason::AccountStatsFailed | ShutdownReason::Panic);
+    process::exit(if failed { 1 } else { 0 });
 }
@@ -1 +1,7 @@
-pub mod mm;
\ No newline at end of file
+pub mod edge;
+pub mod fair_value;
+pub mod feed_monitor;
+pub mod ladder;
+pub mod mm;
+pub mod order_manager;
+pub mod skew;
\ No newline at end of file
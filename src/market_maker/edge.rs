@@ -0,0 +1,127 @@
+use crate::market_maker::ladder::LadderOrder;
+use crate::models::common::{EdgeConfig, VenueFees};
+use crate::models::order_book::OrderBook;
+use crate::models::units::Qty;
+use rust_decimal::Decimal;
+
+/**
+ * Keeps the quotes expected to make money once filled and hedged. A filled ask is hedged
+ * by buying on the taker and a filled bid by selling, at the average price of the taker
+ * levels the hedge sweeps. What the quote makes over that price, less the maker fee, the
+ * taker fee and the expected slippage of the hedge, has to reach `min_edge_bps`
+ */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EdgeFilter {
+    config: EdgeConfig,
+    maker_fees: VenueFees,
+    taker_fees: VenueFees,
+}
+
+impl EdgeFilter {
+    pub fn new(config: EdgeConfig, maker_fees: VenueFees, taker_fees: VenueFees) -> EdgeFilter {
+        return EdgeFilter {
+            config,
+            maker_fees,
+            taker_fees,
+        };
+    }
+
+    // bps a quote keeps once hedged, None when the taker book is too thin to hedge all of it
+    pub fn expected_edge_bps(&self, order: &LadderOrder, lot_size: u128, tkr_book: &OrderBook) -> Option<f64> {
+        let quantity = Qty::from(Decimal::from(order.quantity) / Decimal::from(lot_size));
+        let hedge = tkr_book.vwap(!order.is_bid, quantity)?;
+
+        let quote = order.price.to_f64();
+        let gross = if order.is_bid { hedge.to_f64() - quote } else { quote - hedge.to_f64() };
+        let costs = self.maker_fees.maker_bps + self.taker_fees.taker_bps + self.config.hedge_slippage_bps;
+        return Some(gross / quote * 10000.0 - costs);
+    }
+
+    // drops the orders that do not reach the minimum edge
+    pub fn retain(&self, orders: &mut Vec<LadderOrder>, lot_size: u128, tkr_book: &OrderBook) {
+        orders.retain(|order| match self.expected_edge_bps(order, lot_size, tkr_book) {
+            Some(edge_bps) if edge_bps >= self.config.min_edge_bps => true,
+            edge_bps => {
+                tracing::debug!(
+                    level = order.level,
+                    price = %order.price,
+                    "Not quoting {}, expected edge of {:?}bps is below {}bps",
+                    if order.is_bid { "bid" } else { "ask" },
+                    edge_bps,
+                    self.config.min_edge_bps
+                );
+                false
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::units::Price;
+    use std::str::FromStr;
+
+    fn level(price: &str, size: &str) -> (Price, Qty) {
+        return (Price::from_str(price).unwrap(), Qty::from_str(size).unwrap());
+    }
+
+    fn order(is_bid: bool, price: &str, quantity: u128) -> LadderOrder {
+        return LadderOrder {
+            is_bid,
+            level: 0,
+            price: Price::from_str(price).unwrap(),
+            quantity,
+            order_id: None,
+        };
+    }
+
+    fn filter(min_edge_bps: f64, maker_bps: f64, taker_bps: f64) -> EdgeFilter {
+        let config = EdgeConfig {
+            min_edge_bps,
+            hedge_slippage_bps: 1.0,
+            price_improvement_bps: None,
+            fetch_fees: false,
+        };
+        let fees = |maker_bps: f64, taker_bps: f64| VenueFees { maker_bps, taker_bps };
+        return EdgeFilter::new(config, fees(maker_bps, 0.0), fees(0.0, taker_bps));
+    }
+
+    #[test]
+    fn test_edge_after_fees_and_slippage() {
+        let tkr_book = OrderBook::from_levels(
+            vec![level("2000", "0.1"), level("2001", "1")],
+            vec![level("1999", "0.1"), level("1998", "1")],
+        );
+        // a rebate of 1bp on the maker, 3bps to hedge on the taker
+        let edge = filter(0.0, -1.0, 3.0);
+
+        // an ask 10bps over the taker ask it is hedged on
+        let ask = edge.expected_edge_bps(&order(false, "2002", 10), 100, &tkr_book).unwrap();
+        assert!((ask - (2.0 / 2002.0 * 10000.0 - 3.0)).abs() < 1e-9, "{}", ask);
+
+        // 0.2 sweeps two bid levels, the hedge sells at 1998.5 on average
+        let bid = edge.expected_edge_bps(&order(true, "1996", 20), 100, &tkr_book).unwrap();
+        assert!((bid - (2.5 / 1996.0 * 10000.0 - 3.0)).abs() < 1e-9, "{}", bid);
+
+        // the taker book can not hedge 2 of them
+        assert_eq!(edge.expected_edge_bps(&order(true, "1996", 200), 100, &tkr_book), None);
+    }
+
+    #[test]
+    fn test_keeps_quotes_above_the_minimum_edge() {
+        let tkr_book = OrderBook::from_levels(vec![level("2000", "1")], vec![level("1999", "1")]);
+        let mut orders = vec![
+            order(false, "2001", 10),
+            order(false, "2004", 10),
+            order(true, "1998", 10),
+            order(true, "1990", 10),
+        ];
+
+        // after 1bp of slippage and 4bps of fees the 2004 ask keeps 15bps and the 1990 bid 40,
+        // the other two nothing
+        filter(8.0, 2.0, 2.0).retain(&mut orders, 100, &tkr_book);
+        let kept: Vec<String> = orders.iter().map(|order| order.price.to_string()).collect();
+        assert_eq!(kept, vec!["2004", "1990"]);
+    }
+}
@@ -0,0 +1,225 @@
+use crate::models::common::{FairValueConfig, FairValueSource, Market, VenueKind};
+use crate::models::order_book::OrderBook;
+use crate::models::units::Price;
+use rust_decimal::prelude::FromPrimitive;
+use rust_decimal::Decimal;
+use std::collections::HashMap;
+use thiserror::Error;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum FairValueError {
+    #[error("no source has sent a book yet")]
+    NoBooks,
+    #[error("every source is older than {max_age}ms")]
+    Stale { max_age: u128 },
+    #[error("sources are further than {max_deviation_bps}bps apart: {mids:?}")]
+    Diverged { max_deviation_bps: f64, mids: Vec<(VenueKind, Price)> },
+}
+
+#[derive(Debug, Clone, Copy)]
+struct SourceMid {
+    mid: Price,
+    // ms since epoch
+    received: u128,
+}
+
+/**
+ * Reference price the quotes are built around, a weighted mid of several venues.
+ * A source that stopped sending books is left out and the weights of the others
+ * scaled up to fill in, so a single stale venue does not stop quoting. A source
+ * too far from the median of the others is left out as an outlier
+ */
+#[derive(Debug)]
+pub struct FairValue {
+    sources: Vec<FairValueSource>,
+    max_deviation_bps: Option<f64>,
+    max_age: u128,
+    mids: HashMap<VenueKind, SourceMid>,
+}
+
+impl FairValue {
+    // `default_max_age` in ms applies when the market does not set its own
+    pub fn new(market: &Market, default_max_age: u64) -> FairValue {
+        return FairValue::from_config(FairValue::market_config(market), default_max_age);
+    }
+
+    fn from_config(config: FairValueConfig, default_max_age: u64) -> FairValue {
+        return FairValue {
+            sources: config.sources,
+            max_deviation_bps: config.max_deviation_bps,
+            max_age: config.max_age_ms.unwrap_or(default_max_age) as u128,
+            mids: HashMap::new(),
+        };
+    }
+
+    // the reference venue alone unless the market lists its sources
+    fn market_config(market: &Market) -> FairValueConfig {
+        return market.fair_value.clone().unwrap_or(FairValueConfig {
+            sources: vec![FairValueSource {
+                venue: market.venues.reference,
+                weight: 1.0,
+            }],
+            max_deviation_bps: None,
+            max_age_ms: None,
+        });
+    }
+
+    /**
+     * Picks up reloaded weights and limits, the mids received so far are kept. A reload
+     * that changes the source venues restarts the market instead, see `AppConfig::diff`
+     */
+    pub fn reconfigure(&mut self, market: &Market, default_max_age: u64) {
+        let mids = std::mem::take(&mut self.mids);
+        *self = FairValue::from_config(FairValue::market_config(market), default_max_age);
+        self.mids = mids;
+    }
+
+    pub fn venues(&self) -> Vec<VenueKind> {
+        return self.sources.iter().map(|source| source.venue).collect();
+    }
+
+    // keeps the mid of a book from `venue`, books of venues that are not a source are ignored
+    pub fn update(&mut self, venue: VenueKind, ob: &OrderBook) {
+        if !self.sources.iter().any(|source| source.venue == venue) {
+            return;
+        }
+        let Some(mid) = ob.mid_price() else {
+            return;
+        };
+        self.mids.insert(venue, SourceMid { mid, received: ob.times().received });
+    }
+
+    /**
+     * Weighted mid of the sources that sent a book within the max age and are within
+     * max_deviation_bps of the median of those. When every source is that far from the
+     * median there is no telling which one is right and no fair value
+     */
+    pub fn mid(&self, now: u128) -> Result<Price, FairValueError> {
+        let received: Vec<(FairValueSource, SourceMid)> = self
+            .sources
+            .iter()
+            .filter_map(|source| self.mids.get(&source.venue).map(|mid| (*source, *mid)))
+            .collect();
+        if received.is_empty() {
+            return Err(FairValueError::NoBooks);
+        }
+
+        let fresh: Vec<(FairValueSource, SourceMid)> = received
+            .into_iter()
+            .filter(|(_, mid)| now.saturating_sub(mid.received) <= self.max_age)
+            .collect();
+        if fresh.is_empty() {
+            return Err(FairValueError::Stale { max_age: self.max_age });
+        }
+
+        let inliers: Vec<&(FairValueSource, SourceMid)> = match self.max_deviation_bps {
+            Some(max_deviation_bps) => {
+                let median = median(fresh.iter().map(|(_, mid)| mid.mid).collect());
+                let max_deviation = median.value() * Decimal::from_f64(max_deviation_bps).unwrap_or_default()
+                    / Decimal::from(10000);
+                let inliers: Vec<_> = fresh
+                    .iter()
+                    .filter(|(_, mid)| (mid.mid - median).abs().value() <= max_deviation)
+                    .collect();
+                if inliers.len() < fresh.len() {
+                    tracing::debug!("Fair value left out sources further than {}bps from {}", max_deviation_bps, median);
+                }
+                if inliers.is_empty() {
+                    return Err(FairValueError::Diverged {
+                        max_deviation_bps,
+                        mids: fresh.iter().map(|(source, mid)| (source.venue, mid.mid)).collect(),
+                    });
+                }
+                inliers
+            }
+            None => fresh.iter().collect(),
+        };
+
+        // weights are validated to be positive, see `AppConfig::validate_market`
+        let mut weighted = Decimal::ZERO;
+        let mut total_weight = Decimal::ZERO;
+        for (source, mid) in inliers {
+            let weight = Decimal::from_f64(source.weight).unwrap_or_default();
+            weighted += mid.mid.value() * weight;
+            total_weight += weight;
+        }
+        return Ok(Price::from(weighted / total_weight));
+    }
+}
+
+fn median(mut prices: Vec<Price>) -> Price {
+    prices.sort();
+    let middle = prices.len() / 2;
+    if prices.len().is_multiple_of(2) {
+        return (prices[middle - 1] + prices[middle]) / Decimal::TWO;
+    }
+    return prices[middle];
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::units::Qty;
+    use std::str::FromStr;
+
+    fn price(s: &str) -> Price {
+        return Price::from_str(s).unwrap();
+    }
+
+    fn book(bid: &str, ask: &str) -> OrderBook {
+        let size = Qty::from_str("1").unwrap();
+        return OrderBook::from_levels(vec![(price(ask), size)], vec![(price(bid), size)]).stamped(None);
+    }
+
+    fn three_sources(max_deviation_bps: Option<f64>) -> FairValue {
+        let source = |venue: VenueKind, weight: f64| FairValueSource { venue, weight };
+        let config = FairValueConfig {
+            sources: vec![
+                source(VenueKind::Binance, 2.0),
+                source(VenueKind::BinanceSpot, 1.0),
+                source(VenueKind::Bluefin, 1.0),
+            ],
+            max_deviation_bps,
+            max_age_ms: Some(1_000),
+        };
+        return FairValue::from_config(config, 5_000);
+    }
+
+    #[test]
+    fn test_weights_fresh_sources() {
+        let mut fair_value = three_sources(None);
+        assert_eq!(fair_value.mid(0), Err(FairValueError::NoBooks));
+
+        let binance = book("99", "101");
+        let now = binance.times().received;
+        fair_value.update(VenueKind::Binance, &binance);
+        fair_value.update(VenueKind::BinanceSpot, &book("102", "104"));
+        // not a source
+        fair_value.update(VenueKind::Kucoin, &book("1", "2"));
+        assert_eq!(fair_value.mid(now), Ok(price("101")));
+
+        fair_value.update(VenueKind::Bluefin, &book("104", "106"));
+        assert_eq!(fair_value.mid(now), Ok(price("102")));
+
+        // the books above are all stale by now
+        assert_eq!(fair_value.mid(now + 2_000), Err(FairValueError::Stale { max_age: 1_000 }));
+    }
+
+    #[test]
+    fn test_leaves_out_outliers() {
+        let mut fair_value = three_sources(Some(400.0));
+        let binance = book("99", "101");
+        let now = binance.times().received;
+        fair_value.update(VenueKind::Binance, &binance);
+        fair_value.update(VenueKind::BinanceSpot, &book("110", "112"));
+        fair_value.update(VenueKind::Bluefin, &book("102", "104"));
+        // spot is 8 from the median of 103, further than 4% of it
+        assert_eq!(fair_value.mid(now), Ok(price("101")));
+
+        // two sources that far apart, there is no telling which one is wrong
+        let mut fair_value = three_sources(Some(400.0));
+        fair_value.update(VenueKind::Binance, &binance);
+        fair_value.update(VenueKind::BinanceSpot, &book("110", "112"));
+        assert!(matches!(fair_value.mid(now), Err(FairValueError::Diverged { .. })));
+    }
+}
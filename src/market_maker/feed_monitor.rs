@@ -0,0 +1,133 @@
+use crate::market_maker::mm::MMFeed;
+use crate::models::order_book::FeedTimes;
+use std::collections::{HashMap, VecDeque};
+
+// latency samples kept per feed, older ones are dropped
+static LATENCY_SAMPLES: usize = 1000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LatencySummary {
+    pub p50: u128,
+    pub p99: u128,
+    pub max: u128,
+    pub samples: usize,
+}
+
+#[derive(Debug, Default)]
+struct FeedStats {
+    last_received: u128,
+    // ms from the venue to the bot, oldest first
+    latencies: VecDeque<u128>,
+}
+
+/**
+ * Tracks when every market data feed was last heard from and how long its messages
+ * take to arrive. A socket that is alive but silent never reports a disconnect, its
+ * age is what gives it away
+ */
+#[derive(Debug, Default)]
+pub struct FeedMonitor {
+    feeds: HashMap<MMFeed, FeedStats>,
+}
+
+impl FeedMonitor {
+    pub fn new() -> FeedMonitor {
+        return FeedMonitor::default();
+    }
+
+    /**
+     * Records a book of `feed`. A book received before the last one went back in time and
+     * is dropped, books received in the same ms are distinct updates and all count
+     */
+    pub fn record(&mut self, feed: MMFeed, times: FeedTimes) {
+        let stats = self.feeds.entry(feed).or_default();
+        if times.received < stats.last_received {
+            return;
+        }
+        stats.last_received = times.received;
+
+        if let Some(latency) = times.latency() {
+            if stats.latencies.len() == LATENCY_SAMPLES {
+                stats.latencies.pop_front();
+            }
+            stats.latencies.push_back(latency);
+        }
+    }
+
+    // ms since `feed` was last received, None before its first book
+    pub fn age(&self, feed: MMFeed, now: u128) -> Option<u128> {
+        return self
+            .feeds
+            .get(&feed)
+            .map(|stats| now.saturating_sub(stats.last_received));
+    }
+
+    /**
+     * Feeds of `feeds` not heard from for more than `max_age` ms. A feed that has not sent
+     * anything yet is not stale, nothing is quoted off it until it does
+     */
+    pub fn stale(&self, feeds: &[MMFeed], now: u128, max_age: u128) -> Vec<MMFeed> {
+        return feeds
+            .iter()
+            .copied()
+            .filter(|feed| self.age(*feed, now).is_some_and(|age| age > max_age))
+            .collect();
+    }
+
+    // None until the feed has sent a book with a venue timestamp
+    pub fn latency(&self, feed: MMFeed) -> Option<LatencySummary> {
+        let stats = self.feeds.get(&feed)?;
+        if stats.latencies.is_empty() {
+            return None;
+        }
+
+        let mut sorted: Vec<u128> = stats.latencies.iter().copied().collect();
+        sorted.sort_unstable();
+        let percentile = |p: usize| sorted[(sorted.len() * p / 100).min(sorted.len() - 1)];
+        return Some(LatencySummary {
+            p50: percentile(50),
+            p99: percentile(99),
+            max: sorted[sorted.len() - 1],
+            samples: sorted.len(),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn times(exchange: Option<u128>, received: u128) -> FeedTimes {
+        return FeedTimes { exchange, received };
+    }
+
+    #[test]
+    fn test_flags_silent_feeds() {
+        let mut monitor = FeedMonitor::new();
+        monitor.record(MMFeed::MakerOrderBook, times(None, 1_000));
+        monitor.record(MMFeed::TakerOrderBook, times(None, 4_000));
+
+        let feeds = [MMFeed::MakerOrderBook, MMFeed::ReferenceOrderBook, MMFeed::TakerOrderBook];
+        assert_eq!(monitor.age(MMFeed::MakerOrderBook, 5_000), Some(4_000));
+        assert_eq!(monitor.age(MMFeed::ReferenceOrderBook, 5_000), None);
+        assert_eq!(monitor.stale(&feeds, 5_000, 2_000), vec![MMFeed::MakerOrderBook]);
+
+        monitor.record(MMFeed::MakerOrderBook, times(None, 5_000));
+        assert!(monitor.stale(&feeds, 5_500, 2_000).is_empty());
+    }
+
+    #[test]
+    fn test_latency_percentiles() {
+        let mut monitor = FeedMonitor::new();
+        for latency in 1..=100 {
+            monitor.record(MMFeed::ReferenceOrderBook, times(Some(1_000), 1_000 + latency));
+        }
+        // a book from before the last one is dropped, one from the same ms counts
+        monitor.record(MMFeed::ReferenceOrderBook, times(Some(1_000), 1_050));
+        monitor.record(MMFeed::ReferenceOrderBook, times(Some(1_000), 1_100));
+
+        let summary = monitor.latency(MMFeed::ReferenceOrderBook).expect("Latencies were recorded");
+        assert_eq!(summary, LatencySummary { p50: 51, p99: 100, max: 100, samples: 101 });
+        assert_eq!(monitor.latency(MMFeed::TakerOrderBook), None);
+    }
+}
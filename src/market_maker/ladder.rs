@@ -0,0 +1,149 @@
+use crate::models::common::{LadderConfig, Market};
+use crate::models::units::{Price, Qty};
+use rust_decimal::prelude::FromPrimitive;
+use rust_decimal::Decimal;
+
+// bps quotes improve the price computed for them unless the market's edge config says otherwise
+static DEFAULT_PRICE_IMPROVEMENT_BPS: f64 = 0.5;
+
+// a quote of the ladder, level 0 is the best one of its side
+#[derive(Debug, Clone, PartialEq)]
+pub struct LadderOrder {
+    pub is_bid: bool,
+    pub level: usize,
+    pub price: Price,
+    // venue lots
+    pub quantity: u128,
+    // set once the venue accepted the order
+    pub order_id: Option<String>,
+}
+
+// a single level without offset unless the market configures its ladder
+fn market_ladder(market: &Market) -> LadderConfig {
+    return market.ladder.clone().unwrap_or(LadderConfig {
+        levels: 1,
+        spread_offsets_bps: vec![0.0],
+        size_multipliers: vec![1.0],
+        max_total_lots: None,
+    });
+}
+
+/**
+ * Orders of one side of the ladder from the prices and sizes computed for each level of
+ * the maker book, best level first. Each price is improved by the market's price
+ * improvement, half a bp by default, then pushed away from the mid by the level's
+ * offset and rounded to a tick away from the other side, each size is scaled by the
+ * level's multiplier and capped at `mm_lot_upper_bound`. Levels stop once `max_total_lots`
+ * is used up, a level rounded onto the tick of the one before it is left out
+ */
+pub fn build_side(market: &Market, is_bid: bool, prices: &[Price], sizes: &[Qty]) -> Vec<LadderOrder> {
+    let ladder = market_ladder(market);
+    let improvement_bps = market
+        .edge
+        .and_then(|edge| edge.price_improvement_bps)
+        .unwrap_or(DEFAULT_PRICE_IMPROVEMENT_BPS);
+    let improvement = Decimal::from_f64(improvement_bps).unwrap_or_default() / Decimal::from(10000);
+    let mut remaining = ladder.max_total_lots.unwrap_or(u128::MAX);
+    let mut orders: Vec<LadderOrder> = Vec::new();
+
+    for (level, (&price, &size)) in prices.iter().zip(sizes).take(ladder.levels).enumerate() {
+        let offset = Decimal::from_f64(ladder.spread_offsets_bps[level]).unwrap_or_default() / Decimal::from(10000);
+        let multiplier = Decimal::from_f64(ladder.size_multipliers[level]).unwrap_or_default();
+
+        let price = if is_bid {
+            price * (Decimal::ONE + improvement) * (Decimal::ONE - offset)
+        } else {
+            price * (Decimal::ONE - improvement) * (Decimal::ONE + offset)
+        }
+        .round_to_tick(market.price_precision, is_bid);
+        let quantity = (size * multiplier)
+            .to_lots(market.lot_size)
+            .min(market.mm_lot_upper_bound)
+            .min(remaining);
+        if quantity == 0 {
+            continue;
+        }
+        let behind_last = match orders.last() {
+            Some(last) if is_bid => price < last.price,
+            Some(last) => price > last.price,
+            None => true,
+        };
+        if !behind_last {
+            continue;
+        }
+
+        remaining -= quantity;
+        orders.push(LadderOrder {
+            is_bid,
+            level,
+            price,
+            quantity,
+            order_id: None,
+        });
+    }
+    return orders;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    fn prices(prices: &[&str]) -> Vec<Price> {
+        return prices.iter().map(|price| Price::from_str(price).unwrap()).collect();
+    }
+
+    fn sizes(sizes: &[&str]) -> Vec<Qty> {
+        return sizes.iter().map(|size| Qty::from_str(size).unwrap()).collect();
+    }
+
+    fn quotes(orders: &[LadderOrder]) -> Vec<(usize, String, u128)> {
+        return orders
+            .iter()
+            .map(|order| (order.level, order.price.to_string(), order.quantity))
+            .collect();
+    }
+
+    #[test]
+    fn test_single_level_by_default() {
+        let market = Market::for_tests();
+        let asks = build_side(&market, false, &prices(&["2000", "2001"]), &sizes(&["0.1", "0.2"]));
+        // half a bp inside 2000, rounded up to the tick
+        assert_eq!(quotes(&asks), vec![(0, "1999.90".to_string(), 10)]);
+        // 0.5 is 50 lots, capped at mm_lot_upper_bound
+        let bids = build_side(&market, true, &prices(&["1990"]), &sizes(&["0.5"]));
+        assert_eq!(quotes(&bids), vec![(0, "1990.09".to_string(), 30)]);
+    }
+
+    #[test]
+    fn test_offsets_multipliers_and_total_cap() {
+        let market = Market {
+            ladder: Some(LadderConfig {
+                levels: 3,
+                spread_offsets_bps: vec![0.0, 10.0, 20.0],
+                size_multipliers: vec![1.0, 2.0, 3.0],
+                max_total_lots: Some(45),
+            }),
+            ..Market::for_tests()
+        };
+
+        let bids = build_side(&market, true, &prices(&["2000", "2000", "2000", "1999"]), &sizes(&["0.1", "0.1", "0.1", "0.1"]));
+        assert_eq!(
+            quotes(&bids),
+            vec![(0, "2000.10".to_string(), 10), (1, "1998.09".to_string(), 20), (2, "1996.09".to_string(), 15)]
+        );
+
+        // two levels computed at the same price land on one tick without an offset
+        let market = Market {
+            ladder: Some(LadderConfig {
+                levels: 2,
+                spread_offsets_bps: vec![0.0, 0.0],
+                size_multipliers: vec![1.0, 1.0],
+                max_total_lots: None,
+            }),
+            ..Market::for_tests()
+        };
+        let asks = build_side(&market, false, &prices(&["2000", "2000"]), &sizes(&["0.1", "0.1"]));
+        assert_eq!(quotes(&asks), vec![(0, "1999.90".to_string(), 10)]);
+    }
+}
@@ -1,133 +1,456 @@
-use crate::bluefin::{AccountData, BluefinClient};
 use crate::circuit_breakers::cancel_all_orders_breaker::CancelAllOrdersCircuitBreaker;
 use crate::circuit_breakers::circuit_breaker::{CircuitBreaker, CircuitBreakerBase, State};
 use crate::circuit_breakers::kucoin_breaker::KuCoinBreaker;
 use crate::circuit_breakers::threshold_breaker::{ClientType, ThresholdCircuitBreaker};
-use crate::env;
-use crate::env::EnvVars;
-use crate::kucoin::{AvailableBalance, CallResponse, Credentials, KuCoinClient};
-use crate::models::binance_models::DepthUpdate;
-use crate::models::bluefin_models::OrderbookDepthUpdate;
-use crate::models::common::{
-    abs, add, divide, round_to_precision, subtract, BookOperations, CircuitBreakerConfig, Market,
-    OrderBook,
+use crate::clients::error::{ErrorCode, VenueError};
+use crate::clients::runtime::{block_on, runtime};
+use crate::config::app_config::AppConfig;
+use crate::market_maker::edge::EdgeFilter;
+use crate::market_maker::fair_value::{FairValue, FairValueError};
+use crate::market_maker::feed_monitor::FeedMonitor;
+use crate::market_maker::ladder::{self, LadderOrder};
+use crate::market_maker::order_manager;
+use crate::market_maker::skew::{inventory_skew, Skew};
+use crate::models::common::{abs, add, divide, subtract, Market, MarketParams, VenueFees, VenueKind};
+use crate::models::order_book::OrderBook;
+use crate::models::units::{Price, Qty};
+use crate::statistics::volatility::EwmaVolatility;
+use crate::venues::venue::{
+    create_maker_venue, create_reference_feed, create_taker_venue, MakerVenue, OrderResponse,
+    OrderUpdate, ReferenceFeed, TakerVenue, VenueRole,
 };
-use crate::TradeOrderUpdate;
-use crate::models::kucoin_models::Level2Depth;
-use crate::sockets::binance_ob_socket::BinanceOrderBookStream;
-use crate::sockets::bluefin_ob_socket::BluefinOrderBookStream;
-use crate::sockets::common::OrderBookStream;
-use crate::sockets::kucoin_socket::stream_kucoin_socket;
-use crate::sockets::kucoin_ticker_socket::stream_kucoin_ticker_socket;
-use crate::sockets::kucoin_utils::get_kucoin_url;
+use crate::utils::{forward_into, get_current_time, FeedSender};
+use crate::bluefin::TradeOrderUpdate;
 #[allow(unused_imports)]
 use log::{debug, error, info};
-use rust_decimal::prelude::{FromPrimitive, ToPrimitive};
 use rust_decimal::Decimal;
 use std::collections::HashMap;
 use std::sync::mpsc;
-use std::sync::mpsc::{Receiver, Sender};
-use std::thread;
+use std::sync::mpsc::{Receiver, RecvTimeoutError, Sender};
+use std::sync::Arc;
 use std::time::{Duration, Instant};
+use tokio::task::JoinHandle;
+
+// how often the feeds are checked for staleness while no event arrives
+static FEED_CHECK_PERIOD: Duration = Duration::from_millis(250);
+// how often the feed ages and latencies are logged
+static FEED_REPORT_PERIOD: Duration = Duration::from_secs(60);
+// books create_mm_pair prices off, quotes are pulled when one of them goes stale. The
+// reference books go through the fair value, which leaves out its stale sources itself
+static QUOTING_FEEDS: [MMFeed; 2] = [MMFeed::MakerOrderBook, MMFeed::TakerOrderBook];
+static MONITORED_FEEDS: [MMFeed; 4] = [
+    MMFeed::MakerOrderBook,
+    MMFeed::MakerTicker,
+    MMFeed::ReferenceOrderBook,
+    MMFeed::TakerOrderBook,
+];
+
+// prices and sizes of one side of the quotes, best level first
+type QuoteSide = (Vec<Price>, Vec<Qty>);
+
+// Every input the market maker reacts to, multiplexed onto a single channel
+#[derive(Debug)]
+pub enum MMEvent {
+    MakerOrderBook(OrderBook),
+    MakerTicker(OrderBook),
+    MakerOrderUpdate(OrderUpdate),
+    ReferenceOrderBook(OrderBook),
+    ReferenceOrderBookDiff(OrderBook),
+    TakerOrderBook(OrderBook),
+    TakerOrderBookDiff(OrderBook),
+    // book of a fair value source that plays no other role for the market
+    SourceOrderBook(VenueKind, OrderBook),
+    BuyPercent(f64),
+    TakerTrade(TradeOrderUpdate),
+    MakerBalance(f64),
+    TakerBalance(f64),
+    NetQuantity(f64),
+    Disconnected(MMFeed),
+    // reloaded configuration, the market is looked up by name
+    ConfigUpdate(Arc<AppConfig>),
+    // pull quotes but keep feeding the hedger, the process is shutting down
+    StopQuoting,
+    // pull quotes and stop
+    Shutdown,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MMFeed {
+    MakerOrderBook,
+    MakerTicker,
+    MakerOrderUpdates,
+    ReferenceOrderBook,
+    ReferenceOrderBookDiff,
+    TakerOrderBook,
+    TakerOrderBookDiff,
+    Source(VenueKind),
+    Stats,
+    TakerTrade,
+    MakerBalance,
+    TakerBalance,
+    Hedger,
+}
 
 pub struct MM {
-    pub cb_config: CircuitBreakerConfig,
+    pub config: Arc<AppConfig>,
     pub market: Market,
-    #[allow(dead_code)]
-    bluefin_client: BluefinClient,
-    kucoin_client: KuCoinClient,
-    kucoin_ask_order_response: CallResponse,
-    kucoin_bid_order_response: CallResponse,
+    pub params: MarketParams,
+    maker: Arc<dyn MakerVenue>,
+    taker: Arc<dyn TakerVenue>,
+    reference: Arc<dyn ReferenceFeed>,
+    // quotes on the maker book, every ladder level is its own order. Fills and cancels
+    // reported by the venue are applied as they come in
+    resting_orders: Vec<LadderOrder>,
     last_mm_instant: Instant,
-    rx_stats: Receiver<f64>,
-    rx_account_data: Receiver<AccountData>,
-    rx_account_data_kc: Receiver<AvailableBalance>,
-    rx_hedger_stats: Receiver<f64>,
-    tx_bluefin_hedger_ob: Sender<OrderBook>,
-    rx_bluefin_trade_order_update: Receiver<TradeOrderUpdate>
+    // re-quote running on the shared runtime, resolves to the orders resting once it is done
+    orders_task: Option<JoinHandle<Vec<LadderOrder>>>,
+    // order updates received while a re-quote is in flight, applied again to the orders it returns
+    order_updates: Vec<OrderUpdate>,
+    quoting: bool,
+    feeds: FeedMonitor,
+    fair_value: FairValue,
+    // realised volatility of the fair value, None unless the market configures it
+    volatility: Option<EwmaVolatility>,
+    // edge quotes have to make once hedged, None unless the market configures it
+    edge: Option<EdgeFilter>,
+    // fees the maker and taker venues reported, asked for once per venue
+    fetched_fees: HashMap<VenueKind, VenueFees>,
+    // fair value sources streamed on top of the maker, taker and reference books
+    source_feeds: Vec<MMFeed>,
+    // quotes are pulled until every quoting feed is fresh again
+    feeds_stale: bool,
+    last_feed_report: Instant,
+    tx_event: Sender<MMEvent>,
+    rx_event: Receiver<MMEvent>,
+    tx_taker_hedger_ob: FeedSender<OrderBook>,
 }
 
 impl MM {
+    // the taker book is handed on to the hedger through `tx_taker_hedger_ob`
     pub fn new(
         market: Market,
-        cb_config: CircuitBreakerConfig,
+        config: Arc<AppConfig>,
+        tx_taker_hedger_ob: FeedSender<OrderBook>,
     ) -> (
         MM,
-        Sender<f64>,
-        Sender<AccountData>,
-        Sender<AvailableBalance>,
-        Sender<f64>,
-        Receiver<OrderBook>,
-        Sender<TradeOrderUpdate>
+        FeedSender<f64>,
+        FeedSender<f64>,
+        FeedSender<f64>,
+        FeedSender<f64>,
+        FeedSender<TradeOrderUpdate>
     ) {
-        let vars: EnvVars = env::env_variables();
-
-        let bluefin_client = BluefinClient::new(
-            &vars.bluefin_wallet_key,
-            &vars.bluefin_endpoint,
-            &vars.bluefin_on_boarding_url,
-            &vars.bluefin_websocket_url,
-            vars.bluefin_leverage,
-        );
+        let params = config.market_params(&market);
+        let fair_value = FairValue::new(&market, config.vars.feed_max_age);
+        let volatility = market.volatility.map(EwmaVolatility::new);
+
+        let maker = create_maker_venue(market.venues.maker, &config.vars, &params);
+        let taker = create_taker_venue(market.venues.taker, &config.vars, &params);
+        let reference = create_reference_feed(market.venues.reference, &config.vars, &params);
 
-        let kucoin_client = KuCoinClient::new(
-            Credentials::new(
-                &vars.kucoin_api_key,
-                &vars.kucoin_api_secret,
-                &vars.kucoin_api_phrase,
-            ),
-            &vars.kucoin_endpoint,
-            &vars.kucoin_on_boarding_url,
-            &vars.kucoin_websocket_url,
-            vars.kucoin_leverage,
+        if let Err(e) = block_on(maker.cancel_all_orders(&market)) {
+            tracing::warn!(market = market.name, "Could not cancel leftover orders on startup: {}", e);
+        }
+
+        let (tx_event, rx_event): (Sender<MMEvent>, Receiver<MMEvent>) = mpsc::channel();
+        let tx_stats = forward_into(&tx_event, MMEvent::BuyPercent, MMEvent::Disconnected(MMFeed::Stats));
+        let tx_taker_balance = forward_into(
+            &tx_event,
+            MMEvent::TakerBalance,
+            MMEvent::Disconnected(MMFeed::TakerBalance),
+        );
+        let tx_maker_balance = forward_into(
+            &tx_event,
+            MMEvent::MakerBalance,
+            MMEvent::Disconnected(MMFeed::MakerBalance),
+        );
+        let tx_hedger_stats = forward_into(&tx_event, MMEvent::NetQuantity, MMEvent::Disconnected(MMFeed::Hedger));
+        let tx_bluefin_trade_order_update = forward_into(
+            &tx_event,
+            MMEvent::TakerTrade,
+            MMEvent::Disconnected(MMFeed::TakerTrade),
         );
 
-        let bluefin_market = market.symbols.bluefin.to_owned();
-        kucoin_client.cancel_all_orders(Some(&bluefin_market));
-
-        let (tx_stats, rx_stats): (Sender<f64>, Receiver<f64>) = mpsc::channel();
-        let (tx_account_data, rx_account_data): (Sender<AccountData>, Receiver<AccountData>) =
-            mpsc::channel();
-        let (tx_account_data_kc, rx_account_data_kc): (
-            Sender<AvailableBalance>,
-            Receiver<AvailableBalance>,
-        ) = mpsc::channel();
-        let (tx_hedger_stats, rx_hedger_stats): (Sender<f64>, Receiver<f64>) = mpsc::channel();
-        let (tx_bluefin_hedger_ob, rx_bluefin_hedger_ob): (Sender<OrderBook>, Receiver<OrderBook>) =
-            mpsc::channel();
-        let(tx_bluefin_trade_order_update, rx_bluefin_trade_order_update): (Sender<TradeOrderUpdate>, Receiver<TradeOrderUpdate>) = mpsc::channel();
+        let mut mm = MM {
+            config,
+            market,
+            params,
+            maker,
+            taker,
+            reference,
+            resting_orders: Vec::new(),
+            last_mm_instant: Instant::now(),
+            orders_task: None,
+            order_updates: Vec::new(),
+            quoting: true,
+            feeds: FeedMonitor::new(),
+            fair_value,
+            volatility,
+            edge: None,
+            fetched_fees: HashMap::new(),
+            source_feeds: Vec::new(),
+            feeds_stale: false,
+            last_feed_report: Instant::now(),
+            tx_event,
+            rx_event,
+            tx_taker_hedger_ob,
+        };
+        mm.edge = mm.edge_filter();
 
         (
-            MM {
-                cb_config,
-                market,
-                bluefin_client,
-                kucoin_client,
-                kucoin_ask_order_response: CallResponse {
-                    error: None,
-                    order_id: None,
-                },
-                kucoin_bid_order_response: CallResponse {
-                    error: None,
-                    order_id: None,
-                },
-                last_mm_instant: Instant::now(),
-                rx_stats,
-                rx_account_data,
-                rx_account_data_kc,
-                rx_hedger_stats,
-                tx_bluefin_hedger_ob,
-                rx_bluefin_trade_order_update
-            },
+            mm,
             tx_stats,
-            tx_account_data,
-            tx_account_data_kc,
+            tx_taker_balance,
+            tx_maker_balance,
             tx_hedger_stats,
-            rx_bluefin_hedger_ob,
             tx_bluefin_trade_order_update
         )
     }
 
+    pub fn event_sender(&self) -> Sender<MMEvent> {
+        return self.tx_event.clone();
+    }
+
+    fn apply_config(&mut self, config: Arc<AppConfig>) {
+        match config.market(&self.market.name) {
+            Some(market) => {
+                self.market = market.clone();
+                self.params = config.market_params(market);
+                self.config = config.clone();
+                self.fair_value.reconfigure(&self.market, self.config.vars.feed_max_age);
+                self.volatility = match (self.volatility.take(), self.market.volatility) {
+                    (Some(mut volatility), Some(config)) => {
+                        volatility.reconfigure(config);
+                        Some(volatility)
+                    }
+                    (_, config) => config.map(EwmaVolatility::new),
+                };
+                self.edge = self.edge_filter();
+                tracing::info!(market = self.market.name, "Market maker picked up new config {:?}", self.params);
+            }
+            None => {
+                tracing::warn!(market = self.market.name, "Market is missing from the reloaded config, ignoring update");
+            }
+        }
+    }
+
+    /**
+     * Edge filter of the market with the fees of its maker and taker venues. With
+     * fetch_fees the fees a venue reports are used over the configured ones
+     */
+    fn edge_filter(&mut self) -> Option<EdgeFilter> {
+        let edge = self.market.edge?;
+        if edge.fetch_fees {
+            self.fetch_fees();
+        }
+
+        let fees = |venue: VenueKind| {
+            return self
+                .fetched_fees
+                .get(&venue)
+                .copied()
+                .unwrap_or_else(|| self.config.venue_fees(&self.market, venue));
+        };
+        let maker_fees = fees(self.market.venues.maker);
+        let taker_fees = fees(self.market.venues.taker);
+        tracing::info!(market = self.market.name, "Quoting with maker fees {:?} and taker fees {:?}", maker_fees, taker_fees);
+        return Some(EdgeFilter::new(edge, maker_fees, taker_fees));
+    }
+
+    // asks every venue that has not reported its fees yet, a venue that fails is asked again on the next reload
+    fn fetch_fees(&mut self) {
+        if !self.fetched_fees.contains_key(&self.maker.kind()) {
+            match block_on(self.maker.get_fees(&self.market)) {
+                Ok(Some(fees)) => {
+                    self.fetched_fees.insert(self.maker.kind(), fees);
+                }
+                Ok(None) => {}
+                Err(e) => tracing::warn!(market = self.market.name, "Could not fetch {:?} fees: {}", self.maker.kind(), e),
+            }
+        }
+        if !self.fetched_fees.contains_key(&self.taker.kind()) {
+            match block_on(self.taker.get_fees(&self.market)) {
+                Ok(Some(fees)) => {
+                    self.fetched_fees.insert(self.taker.kind(), fees);
+                }
+                Ok(None) => {}
+                Err(e) => tracing::warn!(market = self.market.name, "Could not fetch {:?} fees: {}", self.taker.kind(), e),
+            }
+        }
+    }
+
+    /**
+     * Picks up the responses of the last re-quote. Returns false while it is still
+     * running, the loop keeps consuming feeds instead of waiting on the REST calls
+     */
+    fn collect_orders_task(&mut self) -> bool {
+        match &self.orders_task {
+            Some(task) if !task.is_finished() => return false,
+            Some(_) => {}
+            None => return true,
+        }
+
+        let task = self.orders_task.take().expect("Orders task checked above");
+        let updates = std::mem::take(&mut self.order_updates);
+        match block_on(task) {
+            Ok(mut orders) => {
+                // the orders placed by the task may have been filled before their ids were known
+                for update in &updates {
+                    order_manager::apply_update(&mut orders, update);
+                }
+                self.resting_orders = orders;
+                tracing::info!("Market Maker resting orders: {:?}", self.resting_orders);
+            }
+            Err(e) => tracing::error!(market = self.market.name, "Maker order task failed: {:?}", e),
+        }
+        return true;
+    }
+
+    fn apply_order_update(&mut self, update: OrderUpdate) {
+        tracing::debug!("maker order update: {:?}", update);
+        order_manager::apply_update(&mut self.resting_orders, &update);
+        if self.orders_task.is_some() {
+            self.order_updates.push(update);
+        }
+    }
+
+    // a re-quote is skipped rather than queued behind the rate limit, quotes would be stale by then
+    fn can_afford_requote(&self, requests: u32) -> bool {
+        if self.maker.can_afford(requests) {
+            return true;
+        }
+        tracing::debug!(market = self.market.name, "{:?} request budget exhausted, skipping re-quote", self.maker.kind());
+        return false;
+    }
+
+    // waits for an in flight re-quote so it can not rest orders after the cancel
+    fn cancel_quotes(&mut self) {
+        if let Some(task) = self.orders_task.take() {
+            let _ = block_on(task);
+        }
+        match block_on(self.maker.cancel_all_orders(&self.market)) {
+            Ok(()) => {
+                self.resting_orders.clear();
+                self.order_updates.clear();
+            }
+            Err(e) => {
+                tracing::error!(market = self.market.name, "Could not cancel quotes on {:?}: {}", self.maker.kind(), e)
+            }
+        }
+    }
+
+    /**
+     * Records the times of a book with the feed monitor and its mid with the fair value.
+     * Diff books are copies of full ones the monitor already counted, they are left out
+     */
+    fn record_book(&mut self, event: &MMEvent) {
+        let (feed, venue, ob) = match event {
+            MMEvent::MakerOrderBook(ob) => (Some(MMFeed::MakerOrderBook), self.maker.kind(), ob),
+            MMEvent::ReferenceOrderBook(ob) => (Some(MMFeed::ReferenceOrderBook), self.reference.kind(), ob),
+            MMEvent::ReferenceOrderBookDiff(ob) => (None, self.reference.kind(), ob),
+            MMEvent::TakerOrderBook(ob) => (Some(MMFeed::TakerOrderBook), self.taker.kind(), ob),
+            MMEvent::TakerOrderBookDiff(ob) => (None, self.taker.kind(), ob),
+            MMEvent::SourceOrderBook(venue, ob) => (Some(MMFeed::Source(*venue)), *venue, ob),
+            // the ticker only carries the best levels of the maker book
+            MMEvent::MakerTicker(ob) => {
+                self.feeds.record(MMFeed::MakerTicker, ob.times());
+                return;
+            }
+            _ => return,
+        };
+        if let Some(feed) = feed {
+            self.feeds.record(feed, ob.times());
+        }
+        self.fair_value.update(venue, ob);
+
+        // every book samples the fair value, unchanged mids count as time without a move
+        let now = get_current_time();
+        if let (Some(volatility), Ok(mid)) = (self.volatility.as_mut(), self.fair_value.mid(now)) {
+            volatility.update(mid, now);
+        }
+    }
+
+    /**
+     * Streams the fair value sources that are not already streamed as the maker, taker
+     * or reference book
+     */
+    fn stream_sources(&mut self) {
+        let streamed = [self.maker.kind(), self.taker.kind(), self.reference.kind()];
+        for venue in self.fair_value.venues() {
+            if streamed.contains(&venue) {
+                continue;
+            }
+            let feed = create_reference_feed(venue, &self.config.vars, &self.params);
+            // moves of the source do not trigger a re-quote, both channels only update the mid
+            let tx = forward_into(
+                &self.tx_event,
+                move |ob| MMEvent::SourceOrderBook(venue, ob),
+                MMEvent::Disconnected(MMFeed::Source(venue)),
+            );
+            let tx_diff = forward_into(
+                &self.tx_event,
+                move |ob| MMEvent::SourceOrderBook(venue, ob),
+                MMEvent::Disconnected(MMFeed::Source(venue)),
+            );
+            feed.stream_order_book(&self.market, tx, tx_diff);
+            self.source_feeds.push(MMFeed::Source(venue));
+        }
+    }
+
+    /**
+     * Pulls the quotes once a feed they are priced off stops sending books and lets
+     * quoting resume when all of them are fresh again. Logs the feed latencies every
+     * FEED_REPORT_PERIOD
+     */
+    fn check_feeds(&mut self) {
+        let now = get_current_time();
+        let max_age = self.config.vars.feed_max_age as u128;
+        let mut reasons: Vec<String> = self
+            .feeds
+            .stale(&QUOTING_FEEDS, now, max_age)
+            .iter()
+            .map(|feed| format!("{:?} is {}ms old", feed, self.feeds.age(*feed, now).unwrap_or_default()))
+            .collect();
+        match self.fair_value.mid(now) {
+            // nothing is quoted before the first books anyway
+            Ok(_) | Err(FairValueError::NoBooks) => {}
+            Err(e) => reasons.push(format!("no fair value, {}", e)),
+        }
+
+        if !reasons.is_empty() && !self.feeds_stale {
+            tracing::warn!(market = self.market.name, "Pulling quotes: {}", reasons.join(", "));
+            self.feeds_stale = true;
+            if self.quoting {
+                self.cancel_quotes();
+            }
+        } else if reasons.is_empty() && self.feeds_stale {
+            tracing::info!(market = self.market.name, "Feeds are fresh again, resuming quotes");
+            self.feeds_stale = false;
+        }
+
+        if self.last_feed_report.elapsed() < FEED_REPORT_PERIOD {
+            return;
+        }
+        self.last_feed_report = Instant::now();
+        tracing::info!(
+            market = self.market.name,
+            fair_value = ?self.fair_value.mid(now),
+            volatility_bps = ?self.volatility.as_ref().and_then(|volatility| volatility.volatility_bps()),
+            "Fair value"
+        );
+        for feed in MONITORED_FEEDS.into_iter().chain(self.source_feeds.clone()) {
+            let latency = self.feeds.latency(feed);
+            tracing::info!(
+                market = self.market.name,
+                feed = ?feed,
+                age_ms = ?self.feeds.age(feed, now),
+                latency_p50_ms = ?latency.map(|l| l.p50),
+                latency_p99_ms = ?latency.map(|l| l.p99),
+                latency_max_ms = ?latency.map(|l| l.max),
+                "Feed latency"
+            );
+        }
+    }
+
     fn cancel_order_breaker(
         &mut self,
         name: String,
@@ -137,10 +460,10 @@ impl MM {
         CancelAllOrdersCircuitBreaker {
             name: name.clone(),
             circuit_breaker: CircuitBreakerBase {
-                config: self.cb_config.clone(),
+                config: self.config.circuit_breaker_config,
                 num_failures: 0,
                 state: State::Closed,
-                kucoin_breaker: KuCoinBreaker::new(format!("Kucoin Breaker for {}", name)),
+                kucoin_breaker: KuCoinBreaker::new(format!("Kucoin Breaker for {}", name), &self.config.vars),
                 market,
             },
         }
@@ -151,293 +474,197 @@ pub trait MarketMaker {
     fn connect(&mut self);
     fn market_make(
         &mut self,
-        ref_book: &OrderBook,
         mm_book: &OrderBook,
         tkr_book: &OrderBook,
         buy_percent: f64,
-        shift: f64,
+        shift: Qty,
         net_quantity: f64,
     );
 
     fn create_mm_pair(
         &self,
-        ref_book: &OrderBook,
+        fair_mid: Price,
         mm_book: &OrderBook,
         tkr_book: &OrderBook,
-        shift: f64,
+        shift: Qty,
         net_quantity: f64,
-    ) -> ((Vec<f64>, Vec<f64>), (Vec<f64>, Vec<f64>));
-    fn extract_top_price_and_size(
-        &self,
-        prices_and_sizes: &(Vec<f64>, Vec<f64>),
-    ) -> Option<(f64, u128)>;
-
-    fn has_valid_kucoin_ask_order_id(&self) -> bool;
+    ) -> ((Vec<Price>, Vec<Qty>), (Vec<Price>, Vec<Qty>));
+    fn has_valid_maker_ask_order_id(&self) -> bool;
 
-    fn has_valid_kucoin_bid_order_id(&self) -> bool;
+    fn has_valid_maker_bid_order_id(&self) -> bool;
 
-    fn place_maker_orders(&mut self, mm: &((Vec<f64>, Vec<f64>), (Vec<f64>, Vec<f64>)));
+    fn place_maker_orders(&mut self, mm: &((Vec<Price>, Vec<Qty>), (Vec<Price>, Vec<Qty>)), tkr_book: &OrderBook);
 
-    fn debug_ob_map(&self, ob_map: &HashMap<String, OrderBook>);
+    fn debug_ob_map(&self, ob_map: &HashMap<VenueRole, OrderBook>);
 }
 
 impl MarketMaker for MM {
     fn connect(&mut self) {
-        let vars: EnvVars = env::env_variables();
-
-        let (tx_kucoin_ob, rx_kucoin_ob) = mpsc::channel();
-        let (tx_kucoin_ticker, rx_kucoin_ticker) = mpsc::channel();
-        let (tx_binance_ob, rx_binance_ob) = mpsc::channel();
-        let (tx_binance_ob_diff, rx_binance_ob_diff) = mpsc::channel();
-        let (tx_bluefin_ob, rx_bluefin_ob) = mpsc::channel();
-        let (tx_bluefin_ob_diff, rx_bluefin_ob_diff) = mpsc::channel();
-
-        let kucoin_market = self.market.symbols.kucoin.to_owned();
-        let kucoin_market_for_ob = kucoin_market.clone();
-
-        let _handle_kucoin_ob = thread::spawn(move || {
-            stream_kucoin_socket(
-                &get_kucoin_url(),
-                &kucoin_market_for_ob.clone(),
-                &vars.kucoin_depth_topic,
-                tx_kucoin_ob, // Sender channel of the appropriate type
-                |msg: &str| -> OrderBook {
-                    let parsed_kucoin_ob: Level2Depth =
-                        serde_json::from_str(&msg).expect("Can't parse");
-                    let ob: OrderBook = parsed_kucoin_ob.into();
-                    ob
-                },
-                "",
-                false,
-            );
-        });
-
-        let kucoin_market_for_ticker = kucoin_market.clone();
-        let _handle_kucoin_ticker = thread::spawn(move || {
-            stream_kucoin_ticker_socket(&kucoin_market_for_ticker.clone(), tx_kucoin_ticker);
-        });
-
-        let binance_market = self.market.symbols.binance.to_owned();
-        let binance_market_for_ob = binance_market.clone();
-
-        let _handle_binance_ob = thread::spawn(move || {
-            let ob_stream = BinanceOrderBookStream::<DepthUpdate>::new();
-            let url = format!(
-                "{}/ws/{}@depth5@100ms",
-                &vars.binance_websocket_url, &binance_market_for_ob
-            );
-            ob_stream.stream_ob_socket(
-                &url,
-                &binance_market_for_ob,
-                tx_binance_ob,
-                tx_binance_ob_diff,
-            );
-        });
+        let tx_maker_ob = forward_into(
+            &self.tx_event,
+            MMEvent::MakerOrderBook,
+            MMEvent::Disconnected(MMFeed::MakerOrderBook),
+        );
+        let tx_maker_ticker = forward_into(
+            &self.tx_event,
+            MMEvent::MakerTicker,
+            MMEvent::Disconnected(MMFeed::MakerTicker),
+        );
+        let tx_maker_order_updates = forward_into(
+            &self.tx_event,
+            MMEvent::MakerOrderUpdate,
+            MMEvent::Disconnected(MMFeed::MakerOrderUpdates),
+        );
+        let tx_ref_ob = forward_into(
+            &self.tx_event,
+            MMEvent::ReferenceOrderBook,
+            MMEvent::Disconnected(MMFeed::ReferenceOrderBook),
+        );
+        let tx_ref_ob_diff = forward_into(
+            &self.tx_event,
+            MMEvent::ReferenceOrderBookDiff,
+            MMEvent::Disconnected(MMFeed::ReferenceOrderBookDiff),
+        );
+        let tx_taker_ob = forward_into(
+            &self.tx_event,
+            MMEvent::TakerOrderBook,
+            MMEvent::Disconnected(MMFeed::TakerOrderBook),
+        );
+        let tx_taker_ob_diff = forward_into(
+            &self.tx_event,
+            MMEvent::TakerOrderBookDiff,
+            MMEvent::Disconnected(MMFeed::TakerOrderBookDiff),
+        );
 
-        let bluefin_market = self.market.symbols.bluefin.to_owned();
-        let bluefin_market_for_ob = bluefin_market.clone();
-
-        let bluefin_websocket_url = vars.bluefin_websocket_url.clone();
-        let _handle_bluefin_ob = thread::spawn(move || {
-            let ob_stream = BluefinOrderBookStream::<OrderbookDepthUpdate>::new();
-            ob_stream.stream_ob_socket(
-                &bluefin_websocket_url,
-                &bluefin_market_for_ob.clone(),
-                tx_bluefin_ob,
-                tx_bluefin_ob_diff,
-            );
-        });
+        // streams run as tasks on the shared runtime
+        self.maker.stream_order_book(&self.market, tx_maker_ob);
+        self.maker.stream_ticker(&self.market, tx_maker_ticker);
+        self.maker.stream_order_updates(&self.market, tx_maker_order_updates);
+        self.reference.stream_order_book(&self.market, tx_ref_ob, tx_ref_ob_diff);
+        self.taker.stream_order_book(&self.market, tx_taker_ob, tx_taker_ob_diff);
+        self.stream_sources();
 
-        let mut ob_map: HashMap<String, OrderBook> = HashMap::new();
+        let mut ob_map: HashMap<VenueRole, OrderBook> = HashMap::new();
         let mut buy_percent: f64 = 50.0;
         let mut net_quantity: f64 = 0.0;
 
         // ---- Circuit Breakers ---- //
-        let vars = env::env_variables();
+        let config = self.config.clone();
+        let vars = &config.vars;
 
-        let mut kucoin_ob_disconnect_breaker = self.cancel_order_breaker(
-            "Kucoin OrderBook disconnect breaker".to_string(),
+        let bluefin_market = self.market.symbols.bluefin.to_owned();
+        let maker_kind = self.maker.kind();
+        let taker_kind = self.taker.kind();
+        let reference_kind = self.reference.kind();
+
+        let mut maker_ob_disconnect_breaker = self.cancel_order_breaker(
+            format!("{:?} OrderBook disconnect breaker", maker_kind),
             bluefin_market.clone(),
         );
-        let mut kucoin_ticker_disconnect_breaker = self.cancel_order_breaker(
-            "Kucoin Ticker disconnect breaker".to_string(),
+        let mut maker_ticker_disconnect_breaker = self.cancel_order_breaker(
+            format!("{:?} Ticker disconnect breaker", maker_kind),
             bluefin_market.clone(),
         );
 
-        let mut binance_ob_disconnect_breaker = self.cancel_order_breaker(
-            "Binance OrderBook disconnect breaker".to_string(),
+        let mut ref_ob_disconnect_breaker = self.cancel_order_breaker(
+            format!("{:?} OrderBook disconnect breaker", reference_kind),
             bluefin_market.clone(),
         );
-        let mut binance_ob_diff_disconnect_breaker = self.cancel_order_breaker(
-            "Binance OrderBook Diff disconnect breaker".to_string(),
+        let mut ref_ob_diff_disconnect_breaker = self.cancel_order_breaker(
+            format!("{:?} OrderBook Diff disconnect breaker", reference_kind),
             bluefin_market.clone(),
         );
 
-        let mut bluefin_ob_disconnect_breaker = self.cancel_order_breaker(
-            "Bluefin OrderBook disconnect breaker".to_string(),
+        let mut taker_ob_disconnect_breaker = self.cancel_order_breaker(
+            format!("{:?} OrderBook disconnect breaker", taker_kind),
             bluefin_market.clone(),
         );
-        let mut bluefin_ob_diff_disconnect_breaker = self.cancel_order_breaker(
-            "Bluefin OrderBook Diff disconnect breaker".to_string(),
+        let mut taker_ob_diff_disconnect_breaker = self.cancel_order_breaker(
+            format!("{:?} OrderBook Diff disconnect breaker", taker_kind),
             bluefin_market.clone(),
         );
 
         //Account Balance threshold breaker
         let mut account_balance_threshold_breaker = ThresholdCircuitBreaker::new(
             "Account Balance Threshold breaker".to_string(),
-            self.cb_config.clone(),
+            self.config.circuit_breaker_config,
+            vars,
         );
 
         loop {
-            match rx_kucoin_ob.try_recv() {
-                Ok((key, value)) => {
-                    tracing::debug!("kucoin ob: {:?}", value);
-                    kucoin_ob_disconnect_breaker.on_success();
-                    ob_map.insert(key.to_string(), value);
-                }
-                Err(mpsc::TryRecvError::Empty) => {
-                    // No message from kucoin yet
+            // blocks until one of the feeds has something for us, or it is time to check
+            // whether they went quiet
+            let event = match self.rx_event.recv_timeout(FEED_CHECK_PERIOD) {
+                Ok(event) => event,
+                Err(RecvTimeoutError::Timeout) => {
+                    self.check_feeds();
+                    continue;
                 }
-                Err(mpsc::TryRecvError::Disconnected) => {
-                    tracing::info!("Kucoin worker has disconnected!");
-                    if !kucoin_ob_disconnect_breaker.is_open() {
-                        kucoin_ob_disconnect_breaker.on_failure();
-                    }
+                Err(RecvTimeoutError::Disconnected) => panic!("Market maker event channel has disconnected!"),
+            };
+            self.record_book(&event);
+            self.check_feeds();
+
+            match event {
+                MMEvent::MakerOrderBook(value) => {
+                    tracing::debug!("maker ob: {:?}", value);
+                    maker_ob_disconnect_breaker.on_success();
+                    ob_map.insert(VenueRole::Maker, value);
                 }
-            }
-
-            match rx_kucoin_ticker.try_recv() {
-                Ok((key, value)) => {
-                    tracing::debug!("kucoin ticker {}: {:?}", key, value);
-                    kucoin_ticker_disconnect_breaker.on_success();
+                MMEvent::MakerTicker(value) => {
+                    tracing::debug!("maker ticker: {:?}", value);
+                    maker_ticker_disconnect_breaker.on_success();
                     if ob_map.len() == 3 {
-                        let ref_ob: &OrderBook = ob_map.get("binance").expect("Key not found");
-                        let mm_ob: &OrderBook = ob_map.get("kucoin").expect("Key not found");
-                        let tkr_ob: &OrderBook = ob_map.get("bluefin").expect("Key not found");
-                        self.market_make(ref_ob, mm_ob, tkr_ob, buy_percent, 0.0, net_quantity);
+                        let mm_ob: &OrderBook = ob_map.get(&VenueRole::Maker).expect("Key not found");
+                        let tkr_ob: &OrderBook = ob_map.get(&VenueRole::Taker).expect("Key not found");
+                        self.market_make(mm_ob, tkr_ob, buy_percent, Qty::ZERO, net_quantity);
                     }
                 }
-                Err(mpsc::TryRecvError::Empty) => {
-                    // No message from kucoin yet
+                MMEvent::MakerOrderUpdate(update) => self.apply_order_update(update),
+                MMEvent::ReferenceOrderBook(value) => {
+                    tracing::debug!("reference ob: {:?}", value);
+                    ref_ob_disconnect_breaker.on_success();
+                    ob_map.insert(VenueRole::Reference, value);
                 }
-                Err(mpsc::TryRecvError::Disconnected) => {
-                    tracing::info!("Kucoin worker has disconnected!");
-                    if !kucoin_ticker_disconnect_breaker.is_open() {
-                        kucoin_ticker_disconnect_breaker.on_failure();
-                    }
-                }
-            }
-
-            match rx_binance_ob.try_recv() {
-                Ok(value) => {
-                    tracing::debug!("binance ob: {:?}", value);
-                    binance_ob_disconnect_breaker.on_success();
-                    ob_map.insert("binance".to_string(), value);
-                }
-                Err(mpsc::TryRecvError::Empty) => {
-                    // No message from binance yet
-                }
-                Err(mpsc::TryRecvError::Disconnected) => {
-                    tracing::info!("Binance worker has disconnected!");
-                    if !binance_ob_disconnect_breaker.is_open() {
-                        binance_ob_disconnect_breaker.on_failure();
-                    }
-                }
-            }
-
-            match rx_binance_ob_diff.try_recv() {
-                Ok(value) => {
-                    tracing::debug!("diff of binance ob: {:?}", value);
-                    binance_ob_diff_disconnect_breaker.on_success();
+                MMEvent::ReferenceOrderBookDiff(value) => {
+                    tracing::debug!("diff of reference ob: {:?}", value);
+                    ref_ob_diff_disconnect_breaker.on_success();
+                    // the fair value already has the mid of this book
                     if ob_map.len() == 3 {
-                        let mm_ob: &OrderBook = ob_map.get("kucoin").expect("Key not found");
-                        let tkr_ob: &OrderBook = ob_map.get("bluefin").expect("Key not found");
-                        self.market_make(&value, mm_ob, tkr_ob, buy_percent, 0.0, net_quantity);
+                        let mm_ob: &OrderBook = ob_map.get(&VenueRole::Maker).expect("Key not found");
+                        let tkr_ob: &OrderBook = ob_map.get(&VenueRole::Taker).expect("Key not found");
+                        self.market_make(mm_ob, tkr_ob, buy_percent, Qty::ZERO, net_quantity);
                     }
-                    ob_map.insert("binance".to_string(), value);
+                    ob_map.insert(VenueRole::Reference, value);
                 }
-                Err(mpsc::TryRecvError::Empty) => {
-                    // No message from binance yet
+                MMEvent::TakerOrderBook(value) => {
+                    tracing::debug!("taker ob: {:?}", value);
+                    taker_ob_disconnect_breaker.on_success();
+                    let _ = self.tx_taker_hedger_ob.send(value.clone());
+                    ob_map.insert(VenueRole::Taker, value);
                 }
-                Err(mpsc::TryRecvError::Disconnected) => {
-                    tracing::info!("Binance worker has disconnected!");
-                    if !binance_ob_diff_disconnect_breaker.is_open() {
-                        binance_ob_diff_disconnect_breaker.on_failure();
-                    }
-                }
-            }
-
-            match rx_bluefin_ob.try_recv() {
-                Ok(value) => {
-                    tracing::debug!("bluefin ob: {:?}", value);
-                    bluefin_ob_disconnect_breaker.on_success();
-                    let _ = self.tx_bluefin_hedger_ob.send(value.clone());
-                    ob_map.insert("bluefin".to_string(), value);
-                }
-                Err(mpsc::TryRecvError::Empty) => {
-                    // No message from binance yet
-                }
-                Err(mpsc::TryRecvError::Disconnected) => {
-                    tracing::info!("Bluefin worker has disconnected!");
-                    if !bluefin_ob_disconnect_breaker.is_open() {
-                        bluefin_ob_disconnect_breaker.on_failure();
-                    }
-                }
-            }
-
-            match rx_bluefin_ob_diff.try_recv() {
-                Ok(value) => {
-                    tracing::debug!("diff of bluefin ob: {:?}", value);
-                    bluefin_ob_diff_disconnect_breaker.on_success();
+                MMEvent::TakerOrderBookDiff(value) => {
+                    tracing::debug!("diff of taker ob: {:?}", value);
+                    taker_ob_diff_disconnect_breaker.on_success();
                     if ob_map.len() == 3 {
-                        let ref_ob: &OrderBook = ob_map.get("binance").expect("Key not found");
-                        let mm_ob: &OrderBook = ob_map.get("kucoin").expect("Key not found");
-                        self.market_make(ref_ob, mm_ob, &value, buy_percent, 0.0, net_quantity);
+                        let mm_ob: &OrderBook = ob_map.get(&VenueRole::Maker).expect("Key not found");
+                        self.market_make(mm_ob, &value, buy_percent, Qty::ZERO, net_quantity);
                     }
-                    ob_map.insert("bluefin".to_string(), value);
-                }
-                Err(mpsc::TryRecvError::Empty) => {
-                    // No message from binance yet
+                    ob_map.insert(VenueRole::Taker, value);
                 }
-                Err(mpsc::TryRecvError::Disconnected) => {
-                    tracing::info!("Bluefin worker has disconnected!");
-                    if !bluefin_ob_diff_disconnect_breaker.is_open() {
-                        bluefin_ob_diff_disconnect_breaker.on_failure();
-                    }
+                MMEvent::SourceOrderBook(venue, value) => {
+                    tracing::debug!("{:?} fair value source ob: {:?}", venue, value);
                 }
-            }
-
-            match self.rx_stats.try_recv() {
-                Ok(percent) => {
+                MMEvent::BuyPercent(percent) => {
                     tracing::debug!("buy percent: {:?}", percent);
                     buy_percent = percent;
                 }
-                Err(mpsc::TryRecvError::Empty) => {
-                    // No message from kucoin yet
-                }
-                Err(mpsc::TryRecvError::Disconnected) => {
-                    tracing::info!("statistic worker has disconnected!");
-                }
-            }
-
-            match self.rx_bluefin_trade_order_update.try_recv() {
-                Ok(value) => {
+                MMEvent::TakerTrade(value) => {
                     tracing::info!("Bluefin Trade Order Update {:?}", value);
                     account_balance_threshold_breaker.push_bluefin_commission(value.commission);
                 }
-                Err(mpsc::TryRecvError::Empty) => {
-                    // No message from kucoin yet
-                }
-                Err(mpsc::TryRecvError::Disconnected) => {
-                    tracing::info!("Kucoin account data worker has disconnected!");
-                }
-            }
-
-            match self.rx_account_data_kc.try_recv() {
-                Ok(value) => {
-                    let balance = value.data.available_balance.parse::<f64>().unwrap()
-                        + value.data.hold_balance.parse::<f64>().unwrap();
-                    tracing::debug!("Kucoin available balance: {:?}", &balance);
+                MMEvent::MakerBalance(balance) => {
+                    tracing::debug!("{:?} available balance: {:?}", maker_kind, &balance);
                     account_balance_threshold_breaker.check_user_balance(
                         balance,
                         ClientType::KUCOIN,
@@ -445,42 +672,66 @@ impl MarketMaker for MM {
                         vars.dry_run,
                     );
                 }
-                Err(mpsc::TryRecvError::Empty) => {
-                    // No message from kucoin yet
-                }
-                Err(mpsc::TryRecvError::Disconnected) => {
-                    tracing::info!("Kucoin account data worker has disconnected!");
-                }
-            }
-
-            match self.rx_account_data.try_recv() {
-                Ok(value) => {
-                    tracing::debug!("Bluefin available balance: {:?}", value.account_value);
+                MMEvent::TakerBalance(balance) => {
+                    tracing::debug!("{:?} available balance: {:?}", taker_kind, balance);
                     account_balance_threshold_breaker.check_user_balance(
-                        value.account_value,
+                        balance,
                         ClientType::BLUEFIN,
                         &bluefin_market,
                         vars.dry_run,
                     );
                 }
-                Err(mpsc::TryRecvError::Empty) => {
-                    // No message from bluefin yet
+                MMEvent::NetQuantity(value) => {
+                    tracing::info!("maker quantity to hedge: {:?}", value);
+                    net_quantity = value;
                 }
-                Err(mpsc::TryRecvError::Disconnected) => {
-                    tracing::info!("Bluefin account data worker has disconnected!");
+                MMEvent::ConfigUpdate(config) => {
+                    self.apply_config(config);
+                    let cb_config = self.config.circuit_breaker_config;
+                    for breaker in [
+                        &mut maker_ob_disconnect_breaker,
+                        &mut maker_ticker_disconnect_breaker,
+                        &mut ref_ob_disconnect_breaker,
+                        &mut ref_ob_diff_disconnect_breaker,
+                        &mut taker_ob_disconnect_breaker,
+                        &mut taker_ob_diff_disconnect_breaker,
+                    ] {
+                        breaker.circuit_breaker.config = cb_config;
+                    }
+                    account_balance_threshold_breaker.set_config(cb_config);
                 }
-            }
-
-            match self.rx_hedger_stats.try_recv() {
-                Ok(value) => {
-                    tracing::info!("kucoin quantity to hedge: {:?}", value);
-                    net_quantity = value;
+                MMEvent::StopQuoting => {
+                    tracing::info!(market = self.market.name, "Stopped quoting");
+                    self.quoting = false;
+                    self.cancel_quotes();
                 }
-                Err(mpsc::TryRecvError::Empty) => {
-                    // No message from kucoin yet
+                MMEvent::Shutdown => {
+                    tracing::info!(market = self.market.name, "Shutting down market maker");
+                    self.cancel_quotes();
+                    return;
                 }
-                Err(mpsc::TryRecvError::Disconnected) => {
-                    tracing::info!("hedger worker has disconnected!");
+                MMEvent::Disconnected(feed) => {
+                    tracing::info!("{:?} worker has disconnected!", feed);
+                    match feed {
+                        MMFeed::MakerOrderBook => maker_ob_disconnect_breaker.trip(),
+                        MMFeed::MakerTicker => maker_ticker_disconnect_breaker.trip(),
+                        MMFeed::ReferenceOrderBook => ref_ob_disconnect_breaker.trip(),
+                        MMFeed::ReferenceOrderBookDiff => ref_ob_diff_disconnect_breaker.trip(),
+                        MMFeed::TakerOrderBook => taker_ob_disconnect_breaker.trip(),
+                        MMFeed::TakerOrderBookDiff => taker_ob_diff_disconnect_breaker.trip(),
+                        // fills would go unnoticed, filled orders kept as resting
+                        MMFeed::MakerOrderUpdates => {}
+                        // the fair value leaves the source out once it goes stale
+                        MMFeed::Source(_)
+                        | MMFeed::Stats
+                        | MMFeed::TakerTrade
+                        | MMFeed::MakerBalance
+                        | MMFeed::TakerBalance
+                        | MMFeed::Hedger => continue,
+                    }
+                    // a dead feed does not come back, the supervisor restarts the market
+                    tracing::error!(market = self.market.name, "{:?} feed is gone, stopping market maker", feed);
+                    return;
                 }
             }
 
@@ -490,20 +741,28 @@ impl MarketMaker for MM {
 
     fn market_make(
         &mut self,
-        ref_book: &OrderBook,
         mm_book: &OrderBook,
         tkr_book: &OrderBook,
         buy_percent: f64,
-        shift: f64,
+        shift: Qty,
         net_quantity: f64,
     ) {
-        let vars: EnvVars = env::env_variables();
+        if !self.quoting || self.feeds_stale {
+            return;
+        }
+        let fair_mid = match self.fair_value.mid(get_current_time()) {
+            Ok(mid) => mid,
+            Err(e) => {
+                tracing::debug!(market = self.market.name, "No fair value to quote around: {}", e);
+                return;
+            }
+        };
 
         tracing::debug!("Calculating best taker prices...");
 
-        let mm = self.create_mm_pair(ref_book, mm_book, tkr_book, shift, net_quantity);
+        let mm = self.create_mm_pair(fair_mid, mm_book, tkr_book, shift, net_quantity);
 
-        tracing::debug!("ref ob: {:?}", &ref_book);
+        tracing::debug!("fair mid: {}", fair_mid);
         tracing::debug!("mm ob: {:?}", &mm_book);
         tracing::debug!("tkr_ob: {:?}", &tkr_book);
         tracing::debug!("market making orders: {:?}", &mm);
@@ -519,55 +778,13 @@ impl MarketMaker for MM {
             mm_bids = (Vec::new(), Vec::new());
         }
 
-        let (ask_prices, ask_sizes) = mm_asks.clone();
-
-        let (tkr_bid_prices, tkr_bid_sizes): (Vec<f64>, Vec<f64>) =
-            tkr_book.bids.clone().into_iter().unzip();
-
-        let filtered_mm_asks: Vec<(f64, f64)> = ask_prices
-            .into_iter()
-            .zip(ask_sizes.into_iter())
-            .zip(tkr_bid_prices.into_iter().zip(tkr_bid_sizes.into_iter()))
-            .map(|((left1, right1), (left2, right2))| (left1, right1, left2, right2))
-            .filter(|&(ask_price, ask_size, tkr_bid_price, tkr_bid_size)| {
-                let mut ask_price_check: bool = ask_price > tkr_bid_price
-                    && ask_price * (10000.0 - 2.0) / 10000.0 >= tkr_bid_price;
-
-                if net_quantity > 0.0 {
-                    ask_price_check = true;
-                } else {
-                    //ignore
-                }
-
-                ask_price_check && ask_size <= tkr_bid_size
-            })
-            .collect::<Vec<_>>()
-            .iter()
-            .map(|&(a, b, _, _)| (a, b)) // Keep only the first two elements of each tuple
-            .collect();
-
-        let (bid_prices, bid_sizes) = mm_bids.clone();
-
-        let (tkr_ask_prices, tkr_ask_sizes): (Vec<f64>, Vec<f64>) =
-            tkr_book.asks.clone().into_iter().unzip();
-
-        let filtered_mm_bids: Vec<(f64, f64)> = bid_prices
-            .into_iter()
-            .zip(bid_sizes.into_iter())
-            .zip(tkr_ask_prices.into_iter().zip(tkr_ask_sizes.into_iter()))
-            .map(|((left1, right1), (left2, right2))| (left1, right1, left2, right2))
-            .filter(|&(bid_price, bid_size, tkr_ask_price, tkr_ask_size)| {
-                bid_price < tkr_ask_price
-                    && bid_price * (10000.0 + 2.0) / 10000.0 <= tkr_ask_price
-                    && bid_size <= tkr_ask_size
-            })
-            .collect::<Vec<_>>()
-            .iter()
-            .map(|&(a, b, _, _)| (a, b)) // Keep only the first two elements of each tuple
-            .collect();
-
-        let (ask_prices, ask_sizes): (Vec<f64>, Vec<f64>) = filtered_mm_asks.into_iter().unzip();
-        let (bid_prices, bid_sizes): (Vec<f64>, Vec<f64>) = filtered_mm_bids.into_iter().unzip();
+        // the edge filter checks the orders place_maker_orders posts, without it every quote
+        // only has to stay 2bps clear of the taker book at the same depth
+        let ((ask_prices, ask_sizes), (bid_prices, bid_sizes)) = if self.edge.is_some() {
+            (mm_asks, mm_bids)
+        } else {
+            taker_cross_check(mm_asks, mm_bids, tkr_book, net_quantity)
+        };
 
         tracing::debug!(
             mm_ask_prices_empty = ask_prices.is_empty(),
@@ -577,197 +794,261 @@ impl MarketMaker for MM {
         tracing::debug!("Got best prices for market orders. Placing Limit Order...");
 
         if self.last_mm_instant.elapsed()
-            >= Duration::from_millis(vars.market_making_time_throttle_period)
+            >= Duration::from_millis(self.params.market_making_time_throttle_period)
+            && self.collect_orders_task()
         {
-            self.place_maker_orders(&((ask_prices, ask_sizes), (bid_prices, bid_sizes)));
+            self.place_maker_orders(&((ask_prices, ask_sizes), (bid_prices, bid_sizes)), tkr_book);
             self.last_mm_instant = Instant::now();
         }
     }
 
     fn create_mm_pair(
         &self,
-        ref_book: &OrderBook,
+        fair_mid: Price,
         mm_book: &OrderBook,
         tkr_book: &OrderBook,
-        shift: f64,
+        shift: Qty,
         net_quantity: f64,
-    ) -> ((Vec<f64>, Vec<f64>), (Vec<f64>, Vec<f64>)) {
-        let ref_mid_price = ref_book.calculate_mid_prices();
-        let mm_mid_price = mm_book.calculate_mid_prices();
+    ) -> ((Vec<Price>, Vec<Qty>), (Vec<Price>, Vec<Qty>)) {
+        let mm_mid_price = mm_book.mid_prices_by_level();
+        // every level of the maker book is priced against the same fair value
+        let ref_mid_price = vec![fair_mid; mm_mid_price.len()];
         let spread = abs(&subtract(&ref_mid_price, &mm_mid_price)); // use absolute value for spread
-        let half_spread = divide(&spread, 2.0);
-        tracing::debug!("half_spread: {:?}", half_spread);
+        let mut half_spread = divide(&spread, Decimal::TWO);
 
-        let mut mm_bid_prices = subtract(&mm_mid_price, &half_spread);
-
-        if net_quantity > 0.0 {
-            tracing::debug!("Net position is long, Skewing bid");
-            mm_bid_prices = subtract(&mm_mid_price, &half_spread);
+        // venues agreeing on the price would quote at the mid, the spread covers the volatility at least
+        let volatility_bps = self.volatility.as_ref().and_then(|volatility| volatility.volatility_bps());
+        if let Some(min_half_spread) = self.volatility.as_ref().and_then(|volatility| volatility.min_half_spread(fair_mid)) {
+            half_spread = half_spread.into_iter().map(|half| half.max(min_half_spread)).collect();
         }
+        tracing::debug!("half_spread: {:?}, volatility: {:?}bps", half_spread, volatility_bps);
 
-        let mut mm_ask_prices = add(&mm_mid_price, &half_spread);
-
-        if net_quantity < 0.0 {
-            tracing::debug!("Net position is short, Skewing ask");
-            mm_ask_prices = add(&mm_mid_price, &half_spread);
+        // both sides move off the mid by the inventory skew
+        let skew = inventory_skew(&self.market, net_quantity, fair_mid, volatility_bps);
+        if skew != Skew::NONE {
+            tracing::debug!("Skewing quotes for a net quantity of {}: {:?}", net_quantity, skew);
         }
-
-        let mm_bid_sizes = tkr_book.bid_shift(shift);
-        let mm_ask_sizes = tkr_book.ask_shift(shift);
-        ((mm_ask_prices, mm_ask_sizes), (mm_bid_prices, mm_bid_sizes))
+        let skewed = |prices: Vec<Price>| prices.into_iter().map(|price| price - skew.offset).collect::<Vec<Price>>();
+        let mm_bid_prices = skewed(subtract(&mm_mid_price, &half_spread));
+        let mm_ask_prices = skewed(add(&mm_mid_price, &half_spread));
+
+        // both sides are sized from the taker bids, smaller while the price is volatile
+        let size_factor = self
+            .volatility
+            .as_ref()
+            .map_or(Decimal::ONE, |volatility| volatility.size_factor());
+        let mm_bid_sizes: Vec<Qty> = tkr_book.bids().map(|(_, size)| (size + shift) * size_factor).collect();
+        let mm_ask_sizes = mm_bid_sizes.clone();
+
+        // at the position limit only the side that unwinds it is quoted
+        let mm_asks = if skew.pull_asks { (Vec::new(), Vec::new()) } else { (mm_ask_prices, mm_ask_sizes) };
+        let mm_bids = if skew.pull_bids { (Vec::new(), Vec::new()) } else { (mm_bid_prices, mm_bid_sizes) };
+        (mm_asks, mm_bids)
     }
 
-    fn extract_top_price_and_size(
-        &self,
-        prices_and_sizes: &(Vec<f64>, Vec<f64>),
-    ) -> Option<(f64, u128)> {
-        let (prices, sizes) = prices_and_sizes;
-        // Check the first element of prices and sizes
-        if let (Some(&price), Some(&size)) = (prices.first(), sizes.first()) {
-            // Ensure the size is positive and non-zero
-            if size.is_sign_positive() && size > 0.0 {
-                let lot_size = (Decimal::from_f64(size).unwrap()
-                    * Decimal::from_u128(self.market.lot_size).unwrap())
-                .floor();
-                let upper_bound = Decimal::from_u128(self.market.mm_lot_upper_bound).unwrap();
-                let order_size = if lot_size > upper_bound {
-                    upper_bound
-                } else {
-                    lot_size
-                };
-                Some((price, Decimal::to_u128(&order_size).unwrap()))
-            } else {
-                None
-            }
-        } else {
-            // Return None if there is no first element
-            None
-        }
+    fn has_valid_maker_ask_order_id(&self) -> bool {
+        self.resting_orders.iter().any(|order| !order.is_bid && order.order_id.is_some())
     }
 
-    fn has_valid_kucoin_ask_order_id(&self) -> bool {
-        self.kucoin_ask_order_response.order_id.is_some()
+    fn has_valid_maker_bid_order_id(&self) -> bool {
+        self.resting_orders.iter().any(|order| order.is_bid && order.order_id.is_some())
     }
 
-    fn has_valid_kucoin_bid_order_id(&self) -> bool {
-        self.kucoin_bid_order_response.order_id.is_some()
-    }
-
-    fn place_maker_orders(&mut self, mm: &((Vec<f64>, Vec<f64>), (Vec<f64>, Vec<f64>))) {
-        let bluefin_market = self.market.symbols.bluefin.to_owned();
-        let res = self.kucoin_client.cancel_all_orders(Some(&bluefin_market));
-        let can_place_order = res.error.is_none();
-        let vars: EnvVars = env::env_variables();
-        let dry_run = vars.dry_run;
-        // Check if the first and second elements are empty
-        let are_asks_empty = mm.0 .0.is_empty() && mm.0 .1.is_empty();
-        let are_bids_empty = mm.1 .0.is_empty() && mm.1 .1.is_empty();
-
-        if can_place_order {
-            let bluefin_market = self.market.symbols.bluefin.to_owned();
-
-            if !are_asks_empty {
-                if let Some(top_ask) = self.extract_top_price_and_size(&mm.0) {
-
-                    let price = round_to_precision(
-                        top_ask.0 - (top_ask.0 / (2.0 * 10000.0)),
-                        self.market.price_precision,
-                    );
-                    let quantity = top_ask.1;
-
-                    tracing::info!(
-                        price = price,
-                        quantity = (quantity as f64 / 100.0),
-                        volume =
-                            price * (quantity as f64) / 100.0,
-                        dry_run = &dry_run,
-                        "Place Maker Ask Order"
-                    );
+    fn place_maker_orders(&mut self, mm: &((Vec<Price>, Vec<Qty>), (Vec<Price>, Vec<Qty>)), tkr_book: &OrderBook) {
+        let dry_run = self.config.vars.dry_run;
+        let ((ask_prices, ask_sizes), (bid_prices, bid_sizes)) = mm;
 
-                    if !dry_run {
-                        let ask_order_response = self.kucoin_client.place_limit_order(
-                            &bluefin_market,
-                            false,
-                            price,
-                            quantity,
-                        );
+        let mut orders = ladder::build_side(&self.market, false, ask_prices, ask_sizes);
+        orders.extend(ladder::build_side(&self.market, true, bid_prices, bid_sizes));
+        // the edge is checked on the prices and sizes that are actually posted
+        if let Some(edge) = &self.edge {
+            edge.retain(&mut orders, self.market.lot_size, tkr_book);
+        }
+        let diff = order_manager::diff_orders(&self.market, &self.resting_orders, orders);
+        if diff.is_empty() {
+            tracing::debug!("Resting orders are within tolerance, nothing to re-quote");
+            return;
+        }
+        if !self.can_afford_requote(diff.requests()) {
+            return;
+        }
+        for order in &diff.place {
+            tracing::info!(
+                level = order.level,
+                price = %order.price,
+                quantity = (order.quantity as f64 / 100.0),
+                volume = order.price.to_f64() * (order.quantity as f64) / 100.0,
+                dry_run = &dry_run,
+                "Place Maker {} Order",
+                if order.is_bid { "Bid" } else { "Ask" }
+            );
+        }
+        tracing::debug!(kept = diff.keep.len(), cancelled = diff.cancel.len(), "Re-quoting maker orders");
 
-                        self.kucoin_ask_order_response = ask_order_response;
+        if dry_run {
+            return;
+        }
 
-                        tracing::info!("Market Maker Ask Order status: {:?}", self.kucoin_ask_order_response);
+        // cancel the moved orders first so a level never rests twice, then place every
+        // new one at once. The market maker loop goes on reading feeds while the calls are in flight
+        let maker = self.maker.clone();
+        let market = self.market.clone();
+        self.orders_task = Some(runtime().spawn(async move {
+            let order_manager::OrderDiff { keep, cancel, place } = diff;
+            let cancelled = cancel.into_iter().map(|order| {
+                let maker = maker.clone();
+                let market = market.clone();
+                async move {
+                    let order_id = order.order_id.clone().unwrap_or_default();
+                    let result = maker.cancel_order(&market, &order_id).await;
+                    (order, result)
+                }
+            });
+
+            let mut resting = keep;
+            for (order, result) in futures::future::join_all(cancelled).await {
+                match result {
+                    Ok(()) => {}
+                    // filled or cancelled already
+                    Err(e) if e.code() == Some(&ErrorCode::OrderNotFound) => {
+                        tracing::debug!("Maker order {:?} is gone already: {}", order.order_id, e);
+                    }
+                    // still resting as far as we know, its level is not re-quoted this time
+                    Err(e) => {
+                        tracing::warn!("Could not cancel maker order {:?}, keeping it: {}", order.order_id, e);
+                        resting.push(order);
                     }
-
-                    tracing::info!("Placed ask limit order on market maker.");
                 }
             }
-            if !are_bids_empty {
-                if let Some(top_bid) = self.extract_top_price_and_size(&mm.1) {
 
-                    let price = round_to_precision(
-                        top_bid.0 + (top_bid.0 / (2.0 * 10000.0)),
-                        self.market.price_precision,
-                    );
-                    let quantity = top_bid.1;
-
-                    tracing::info!(
-                        price = price,
-                        quantity = (quantity as f64 / 100.0),
-                        volume =
-                            price * (quantity as f64) / 100.0,
-                        dry_run = &dry_run,
-                        "Place Maker Bid Order"
-                    );
-                    if !dry_run {
-                        let bid_order_response = self.kucoin_client.place_limit_order(
-                            &bluefin_market,
-                            true,
-                            price,
-                            quantity,
-                        );
-                        self.kucoin_bid_order_response = bid_order_response;
-                        
-                        tracing::info!("Market Maker Ask Order status: {:?}", self.kucoin_bid_order_response);
-                        
+            let placed = place
+                .into_iter()
+                .filter(|order| {
+                    !resting
+                        .iter()
+                        .any(|kept| kept.is_bid == order.is_bid && kept.level == order.level)
+                })
+                .map(|mut order| {
+                    let maker = maker.clone();
+                    let market = market.clone();
+                    async move {
+                        let side = format!("{} level {}", if order.is_bid { "bid" } else { "ask" }, order.level);
+                        let resp = maker.place_limit_order(&market, order.is_bid, order.price, order.quantity).await;
+                        order.order_id = placed_order(&side, resp).order_id;
+                        order
                     }
-
-                    tracing::info!("Placed bid limit order on market maker.");
-                }
-            }
-        } else {
-            tracing::info!("Can't place order on Market Maker. Cancel All previous Orders on Market Maker failed. Error: {:?}", res.error.unwrap().msg);
-        }
+                });
+            resting.extend(futures::future::join_all(placed).await);
+            return resting;
+        }));
     }
 
     #[allow(dead_code)]
-    fn debug_ob_map(&self, ob_map: &HashMap<String, OrderBook>) {
+    fn debug_ob_map(&self, ob_map: &HashMap<VenueRole, OrderBook>) {
         if ob_map.len() == 3 {
-            let binance_ob: &OrderBook = ob_map.get("binance").expect("Key not found");
-            let kucoin_ob: &OrderBook = ob_map.get("kucoin").expect("Key not found");
-            let bluefin_ob: &OrderBook = ob_map.get("bluefin").expect("Key not found");
+            let ref_ob: &OrderBook = ob_map.get(&VenueRole::Reference).expect("Key not found");
+            let mm_ob: &OrderBook = ob_map.get(&VenueRole::Maker).expect("Key not found");
+            let tkr_ob: &OrderBook = ob_map.get(&VenueRole::Taker).expect("Key not found");
 
-            for (i, (ask, size)) in binance_ob.asks.iter().enumerate() {
+            for (i, (ask, size)) in ref_ob.asks().enumerate() {
                 tracing::debug!("{}. ask: {}, size: {}", i, ask, size);
             }
 
-            for (i, (bid, size)) in binance_ob.bids.iter().enumerate() {
+            for (i, (bid, size)) in ref_ob.bids().enumerate() {
                 tracing::debug!("{}. bid: {}, size: {}", i, bid, size);
             }
 
-            for (i, ask) in kucoin_ob.asks.iter().enumerate() {
+            for (i, ask) in mm_ob.asks().enumerate() {
                 tracing::debug!("{}. ask: {:?}", i, ask);
             }
 
-            for (i, bid) in kucoin_ob.bids.iter().enumerate() {
+            for (i, bid) in mm_ob.bids().enumerate() {
                 tracing::debug!("{}. bid: {:?}", i, bid);
             }
 
-            for (i, (ask, size)) in bluefin_ob.asks.iter().enumerate() {
+            for (i, (ask, size)) in tkr_ob.asks().enumerate() {
                 tracing::debug!("{}. ask: {}, size: {}", i, ask, size);
             }
 
-            for (i, (bid, size)) in bluefin_ob.bids.iter().enumerate() {
+            for (i, (bid, size)) in tkr_ob.bids().enumerate() {
                 tracing::debug!("{}. bid: {}, size: {}", i, bid, size);
             }
         }
     }
 }
+
+/**
+ * Quotes of the markets without an edge config: an ask has to be 2bps over the taker bid
+ * at the same depth, unless the hedger is buying, and a bid 2bps under the taker ask.
+ * Neither may be larger than that taker level
+ */
+fn taker_cross_check(mm_asks: QuoteSide, mm_bids: QuoteSide, tkr_book: &OrderBook, net_quantity: f64) -> (QuoteSide, QuoteSide) {
+    let (ask_prices, ask_sizes) = mm_asks;
+
+    // every ask is checked against the taker bid at the same depth
+    let filtered_mm_asks: Vec<(Price, Qty)> = ask_prices
+        .into_iter()
+        .zip(ask_sizes)
+        .zip(tkr_book.bids())
+        .filter(|&((ask_price, ask_size), (tkr_bid_price, tkr_bid_size))| {
+            let mut ask_price_check: bool = ask_price > tkr_bid_price
+                && ask_price * Decimal::new(10000 - 2, 4) >= tkr_bid_price;
+
+            if net_quantity > 0.0 {
+                ask_price_check = true;
+            } else {
+                //ignore
+            }
+
+            ask_price_check && ask_size <= tkr_bid_size
+        })
+        .map(|(ask, _)| ask)
+        .collect();
+
+    let (bid_prices, bid_sizes) = mm_bids;
+
+    let filtered_mm_bids: Vec<(Price, Qty)> = bid_prices
+        .into_iter()
+        .zip(bid_sizes)
+        .zip(tkr_book.asks())
+        .filter(|&((bid_price, bid_size), (tkr_ask_price, tkr_ask_size))| {
+            bid_price < tkr_ask_price
+                && bid_price * Decimal::new(10000 + 2, 4) <= tkr_ask_price
+                && bid_size <= tkr_ask_size
+        })
+        .map(|(bid, _)| bid)
+        .collect();
+
+    let (ask_prices, ask_sizes): (Vec<Price>, Vec<Qty>) = filtered_mm_asks.into_iter().unzip();
+    let (bid_prices, bid_sizes): (Vec<Price>, Vec<Qty>) = filtered_mm_bids.into_iter().unzip();
+
+    return ((ask_prices, ask_sizes), (bid_prices, bid_sizes));
+}
+
+/**
+ * Logs the outcome of a maker order. A rejected order leaves an empty response so the
+ * side is treated as not resting until the next re-quote
+ */
+fn placed_order(side: &str, resp: Result<OrderResponse, VenueError>) -> OrderResponse {
+    return match resp {
+        Ok(resp) => {
+            tracing::info!("Placed {} limit order on market maker.", side);
+            resp
+        }
+        Err(e) => {
+            match e.code() {
+                Some(ErrorCode::InsufficientBalance) => {
+                    tracing::error!("Not enough balance on market maker for the {} order: {}", side, e)
+                }
+                Some(ErrorCode::MarketBoundExceeded) => {
+                    tracing::warn!("Market maker {} price is outside of the allowed band: {}", side, e)
+                }
+                _ if e.is_transient() => {
+                    tracing::warn!("Market maker {} order failed, quoting again on the next round: {}", side, e)
+                }
+                _ => tracing::error!("Market maker {} order failed: {}", side, e),
+            }
+            OrderResponse::default()
+        }
+    };
+}
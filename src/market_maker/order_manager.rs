@@ -0,0 +1,164 @@
+use crate::market_maker::ladder::LadderOrder;
+use crate::models::common::{Market, RequoteConfig};
+use crate::venues::venue::OrderUpdate;
+use rust_decimal::prelude::FromPrimitive;
+use rust_decimal::Decimal;
+
+// what a re-quote does to the resting orders to get to the computed ones
+#[derive(Debug, Default, PartialEq)]
+pub struct OrderDiff {
+    // resting orders close enough to the computed ones, they keep their place in the queue
+    pub keep: Vec<LadderOrder>,
+    pub cancel: Vec<LadderOrder>,
+    pub place: Vec<LadderOrder>,
+}
+
+impl OrderDiff {
+    pub fn is_empty(&self) -> bool {
+        return self.cancel.is_empty() && self.place.is_empty();
+    }
+
+    // REST calls the re-quote makes, one per cancel and one per order placed
+    pub fn requests(&self) -> u32 {
+        return (self.cancel.len() + self.place.len()) as u32;
+    }
+}
+
+/**
+ * Compares the computed quotes to the resting ones, level by level on each side. A resting
+ * order within the market's requote tolerances of the computed one is kept, otherwise it
+ * is cancelled and the computed one placed. The maker venues have no amend, a moved order
+ * is always cancelled and placed again. Orders the venue did not accept are not resting
+ */
+pub fn diff_orders(market: &Market, resting: &[LadderOrder], desired: Vec<LadderOrder>) -> OrderDiff {
+    let tolerance = market.requote.unwrap_or(RequoteConfig {
+        price_tolerance_bps: 0.0,
+        size_tolerance_pct: 0.0,
+    });
+    let mut live: Vec<&LadderOrder> = resting.iter().filter(|order| order.order_id.is_some()).collect();
+    let mut diff = OrderDiff::default();
+
+    for order in desired {
+        let same_level = live
+            .iter()
+            .position(|resting| resting.is_bid == order.is_bid && resting.level == order.level);
+        match same_level.map(|index| live.swap_remove(index)) {
+            Some(resting) if within_tolerance(&tolerance, resting, &order) => diff.keep.push(resting.clone()),
+            Some(resting) => {
+                diff.cancel.push(resting.clone());
+                diff.place.push(order);
+            }
+            None => diff.place.push(order),
+        }
+    }
+    // levels that are no longer quoted
+    diff.cancel.extend(live.into_iter().cloned());
+    return diff;
+}
+
+fn within_tolerance(tolerance: &RequoteConfig, resting: &LadderOrder, desired: &LadderOrder) -> bool {
+    let price_tolerance =
+        desired.price.value() * Decimal::from_f64(tolerance.price_tolerance_bps).unwrap_or_default() / Decimal::from(10000);
+    let size_tolerance = desired.quantity as f64 * tolerance.size_tolerance_pct / 100.0;
+    return (resting.price - desired.price).abs().value() <= price_tolerance
+        && (resting.quantity as f64 - desired.quantity as f64).abs() <= size_tolerance;
+}
+
+// applies a fill or cancel reported by the venue, updates of other orders are ignored
+pub fn apply_update(orders: &mut Vec<LadderOrder>, update: &OrderUpdate) {
+    let is_updated = |order: &LadderOrder| order.order_id.as_deref() == Some(update.order_id.as_str());
+    if update.done || update.remaining == 0 {
+        orders.retain(|order| !is_updated(order));
+        return;
+    }
+    if let Some(order) = orders.iter_mut().find(|order| is_updated(order)) {
+        order.quantity = update.remaining;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::units::Price;
+    use std::str::FromStr;
+
+    fn order(is_bid: bool, level: usize, price: &str, quantity: u128, order_id: Option<&str>) -> LadderOrder {
+        return LadderOrder {
+            is_bid,
+            level,
+            price: Price::from_str(price).unwrap(),
+            quantity,
+            order_id: order_id.map(str::to_string),
+        };
+    }
+
+    fn ids(orders: &[LadderOrder]) -> Vec<Option<&str>> {
+        return orders.iter().map(|order| order.order_id.as_deref()).collect();
+    }
+
+    #[test]
+    fn test_keeps_unchanged_and_replaces_moved_orders() {
+        let resting = vec![
+            order(false, 0, "2001.00", 10, Some("ask0")),
+            order(false, 1, "2003.00", 20, Some("ask1")),
+            order(true, 0, "1999.00", 10, Some("bid0")),
+            // rejected by the venue, not resting
+            order(true, 1, "1997.00", 20, None),
+        ];
+        let desired = vec![
+            order(false, 0, "2001.00", 10, None),
+            order(false, 1, "2003.50", 20, None),
+            order(true, 1, "1997.00", 20, None),
+        ];
+
+        let diff = diff_orders(&Market::for_tests(), &resting, desired);
+        assert_eq!(ids(&diff.keep), vec![Some("ask0")]);
+        assert_eq!(ids(&diff.cancel), vec![Some("ask1"), Some("bid0")]);
+        assert_eq!(
+            diff.place.iter().map(|order| (order.is_bid, order.level)).collect::<Vec<_>>(),
+            vec![(false, 1), (true, 1)]
+        );
+        assert_eq!(diff.requests(), 4);
+        assert!(diff_orders(&Market::for_tests(), &diff.keep, vec![order(false, 0, "2001.00", 10, None)]).is_empty());
+    }
+
+    #[test]
+    fn test_tolerances() {
+        let market = Market {
+            requote: Some(RequoteConfig {
+                price_tolerance_bps: 5.0,
+                size_tolerance_pct: 20.0,
+            }),
+            ..Market::for_tests()
+        };
+        let resting = vec![order(false, 0, "2001.00", 10, Some("ask0"))];
+
+        // 0.8 is 4bps of 2000.20 and 2 lots less than a fifth of 12
+        let diff = diff_orders(&market, &resting, vec![order(false, 0, "2000.20", 12, None)]);
+        assert!(diff.is_empty());
+        assert_eq!(ids(&diff.keep), vec![Some("ask0")]);
+
+        let diff = diff_orders(&market, &resting, vec![order(false, 0, "1999.90", 10, None)]);
+        assert_eq!(ids(&diff.cancel), vec![Some("ask0")]);
+        let diff = diff_orders(&market, &resting, vec![order(false, 0, "2001.00", 13, None)]);
+        assert_eq!(ids(&diff.cancel), vec![Some("ask0")]);
+    }
+
+    #[test]
+    fn test_fills_shrink_and_remove_orders() {
+        let mut orders = vec![order(false, 0, "2001.00", 10, Some("ask0")), order(true, 0, "1999.00", 10, Some("bid0"))];
+        let update = |order_id: &str, remaining: u128, done: bool| OrderUpdate {
+            order_id: order_id.to_string(),
+            remaining,
+            done,
+        };
+
+        apply_update(&mut orders, &update("ask0", 4, false));
+        apply_update(&mut orders, &update("other", 0, true));
+        assert_eq!(orders[0].quantity, 4);
+        assert_eq!(orders.len(), 2);
+
+        apply_update(&mut orders, &update("bid0", 10, true));
+        assert_eq!(ids(&orders), vec![Some("ask0")]);
+    }
+}
@@ -0,0 +1,113 @@
+use crate::models::common::Market;
+use crate::models::units::Price;
+use rust_decimal::prelude::FromPrimitive;
+use rust_decimal::Decimal;
+
+// how far the quotes move off the prices computed for them because of the inventory
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Skew {
+    // subtracted from every bid and ask, positive while long so both sides move down
+    pub offset: Price,
+    // at the position limit the side that would add to the position is not quoted
+    pub pull_bids: bool,
+    pub pull_asks: bool,
+}
+
+impl Skew {
+    pub const NONE: Skew = Skew {
+        offset: Price::ZERO,
+        pull_bids: false,
+        pull_asks: false,
+    };
+}
+
+/**
+ * Inventory skew in the spirit of Avellaneda-Stoikov: the quotes are centred on a
+ * reservation price below the mid while long and above it while short, so the side
+ * that unwinds the position is the one more likely to fill. The offset is
+ * `q * skewing_coefficient * volatility * mid`, with `q` the unhedged position as a
+ * fraction of `max_position` clamped to [-1, 1]. `measured_bps` is the realised
+ * volatility, the configured one stands in until it is known.
+ * `net_quantity` is what the hedger still has to buy on the taker, the unhedged
+ * position is its negative
+ */
+pub fn inventory_skew(market: &Market, net_quantity: f64, mid: Price, measured_bps: Option<f64>) -> Skew {
+    let Some(inventory) = market.inventory else {
+        return Skew::NONE;
+    };
+
+    let position = -net_quantity;
+    let q = (position / inventory.max_position).clamp(-1.0, 1.0);
+    let volatility_bps = measured_bps.unwrap_or(inventory.volatility_bps);
+    let factor = q * market.skewing_coefficient * volatility_bps / 10000.0;
+    return Skew {
+        offset: mid * Decimal::from_f64(factor).unwrap_or_default(),
+        pull_bids: position >= inventory.max_position,
+        pull_asks: position <= -inventory.max_position,
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::common::InventoryConfig;
+    use std::str::FromStr;
+
+    fn skewed(skewing_coefficient: f64) -> Market {
+        return Market {
+            skewing_coefficient,
+            inventory: Some(InventoryConfig {
+                max_position: 2.0,
+                volatility_bps: 10.0,
+            }),
+            ..Market::for_tests()
+        };
+    }
+
+    fn price(s: &str) -> Price {
+        return Price::from_str(s).unwrap();
+    }
+
+    #[test]
+    fn test_no_skew_when_flat_or_unconfigured() {
+        let mid = price("2000");
+        assert_eq!(inventory_skew(&skewed(1.0), 0.0, mid, None), Skew::NONE);
+        assert_eq!(inventory_skew(&Market::for_tests(), -1.5, mid, None), Skew::NONE);
+        assert_eq!(inventory_skew(&skewed(0.0), -1.5, mid, None).offset, Price::ZERO);
+    }
+
+    #[test]
+    fn test_long_moves_quotes_down() {
+        // the hedger still has to sell 1, the maker is long 1 of the 2 allowed
+        let skew = inventory_skew(&skewed(1.0), -1.0, price("2000"), None);
+        // half of 10bps of 2000
+        assert_eq!(skew.offset, price("1"));
+        assert!(!skew.pull_bids && !skew.pull_asks);
+
+        // the coefficient scales the offset linearly
+        let skew = inventory_skew(&skewed(0.5), -1.0, price("2000"), None);
+        assert_eq!(skew.offset, price("0.5"));
+
+        // a measured volatility replaces the configured one
+        let skew = inventory_skew(&skewed(1.0), -1.0, price("2000"), Some(20.0));
+        assert_eq!(skew.offset, price("2"));
+    }
+
+    #[test]
+    fn test_short_moves_quotes_up() {
+        let skew = inventory_skew(&skewed(1.0), 0.5, price("2000"), None);
+        assert_eq!(skew.offset, price("-0.5"));
+        assert!(!skew.pull_bids && !skew.pull_asks);
+    }
+
+    #[test]
+    fn test_position_limit_caps_skew_and_pulls_a_side() {
+        let long = inventory_skew(&skewed(1.0), -3.0, price("2000"), None);
+        assert_eq!(long.offset, price("2"));
+        assert!(long.pull_bids && !long.pull_asks);
+
+        let short = inventory_skew(&skewed(1.0), 2.0, price("2000"), None);
+        assert_eq!(short.offset, price("-2"));
+        assert!(!short.pull_bids && short.pull_asks);
+    }
+}
@@ -2,3 +2,5 @@ pub mod binance_models;
 pub mod bluefin_models;
 pub mod common;
 pub mod kucoin_models;
+pub mod order_book;
+pub mod units;
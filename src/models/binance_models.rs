@@ -1,6 +1,33 @@
 use serde::{Serialize, Deserialize};
-use crate::models::common::OrderBook;
-use crate::models::common::deserialize_as_string_tuples;
+use std::str::FromStr;
+use crate::models::order_book::OrderBook;
+use crate::models::common::deserialize_decimal_levels;
+use crate::models::units::{Price, Qty};
+
+// How the reference book is read from Binance
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinanceBookMode {
+    // `@depth5@100ms` partial book snapshots
+    Depth5,
+    // local book kept from a REST snapshot and the `@depth@100ms` diffs
+    DiffDepth,
+    // `@bookTicker` best bid and ask, pushed on every change
+    BookTicker,
+}
+
+impl FromStr for BinanceBookMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        return match s {
+            "depth5" => Ok(BinanceBookMode::Depth5),
+            "diff" => Ok(BinanceBookMode::DiffDepth),
+            "bookTicker" => Ok(BinanceBookMode::BookTicker),
+            other => Err(format!("unknown binance book mode {}", other)),
+        };
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct DepthUpdate {
     #[serde(rename = "e")]
@@ -16,28 +43,69 @@ pub struct DepthUpdate {
     pub symbol: String,
 
     #[serde(rename = "U")]
-    pub u_id: i64, // First update ID in event
+    pub u_id: u64, // First update ID in event
 
     #[serde(rename = "u")]
-    pub u2_id: i64,  // Final update ID in event
+    pub u2_id: u64,  // Final update ID in event
 
     #[serde(rename = "pu")]
-    pub pu_id: i64,  // Final update Id in last stream(ie `u` in last stream)
+    pub pu_id: u64,  // Final update Id in last stream(ie `u` in last stream)
 
     #[serde(rename = "b")]
-    #[serde(deserialize_with = "deserialize_as_string_tuples")]
-    pub bid_orders: Vec<(f64, f64)>,
+    #[serde(deserialize_with = "deserialize_decimal_levels")]
+    pub bid_orders: Vec<(Price, Qty)>,
 
     #[serde(rename = "a")]
-    #[serde(deserialize_with = "deserialize_as_string_tuples")]
-    pub ask_orders: Vec<(f64, f64)>,
+    #[serde(deserialize_with = "deserialize_decimal_levels")]
+    pub ask_orders: Vec<(Price, Qty)>,
 }
 
 impl From<DepthUpdate> for OrderBook {
     fn from(depth_update: DepthUpdate) -> Self {
-        OrderBook {
-            asks: depth_update.ask_orders,
-            bids: depth_update.bid_orders,
-        }
+        OrderBook::from_levels(depth_update.ask_orders, depth_update.bid_orders)
+            .stamped(Some(depth_update.event_time as u128))
     }
-}
\ No newline at end of file
+}
+
+// Response of /fapi/v1/depth
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DepthSnapshot {
+    pub last_update_id: u64,
+    #[serde(deserialize_with = "deserialize_decimal_levels")]
+    pub bids: Vec<(Price, Qty)>,
+    #[serde(deserialize_with = "deserialize_decimal_levels")]
+    pub asks: Vec<(Price, Qty)>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BookTicker {
+    #[serde(rename = "s")]
+    pub symbol: String,
+
+    #[serde(rename = "b")]
+    pub best_bid_price: Price,
+
+    #[serde(rename = "B")]
+    pub best_bid_qty: Qty,
+
+    #[serde(rename = "a")]
+    pub best_ask_price: Price,
+
+    #[serde(rename = "A")]
+    pub best_ask_qty: Qty,
+
+    // only sent by the futures streams
+    #[serde(rename = "E", default)]
+    pub event_time: Option<i64>,
+}
+
+impl From<BookTicker> for OrderBook {
+    fn from(ticker: BookTicker) -> Self {
+        OrderBook::from_levels(
+            vec![(ticker.best_ask_price, ticker.best_ask_qty)],
+            vec![(ticker.best_bid_price, ticker.best_bid_qty)],
+        )
+        .stamped(ticker.event_time.map(|time| time as u128))
+    }
+}
@@ -1,6 +1,7 @@
 use serde::{Deserialize, Serialize};
-use crate::models::common::OrderBook;
+use crate::models::order_book::OrderBook;
 use crate::models::common::deserialize_as_bignumber_string_tuples;
+use crate::models::units::{Price, Qty};
 
 #[derive(Serialize, Deserialize, Debug, PartialEq)]
 #[serde(rename_all = "camelCase")]
@@ -14,18 +15,16 @@ pub struct OrderbookDepthUpdate {
 pub struct OrderbookData {
     pub symbol: String,
     #[serde(deserialize_with = "deserialize_as_bignumber_string_tuples")]
-    pub bids: Vec<(f64, f64)>,
+    pub bids: Vec<(Price, Qty)>,
     #[serde(deserialize_with = "deserialize_as_bignumber_string_tuples")]
-    pub asks: Vec<(f64, f64)>,
+    pub asks: Vec<(Price, Qty)>,
     pub depth: u32,
     pub orderbook_update_id: u64,
 }
 
 impl From<OrderbookDepthUpdate> for OrderBook {
     fn from(d_update: OrderbookDepthUpdate) -> Self {
-        OrderBook {
-            asks: d_update.data.asks,
-            bids: d_update.data.bids,
-        }
+        // depth updates carry no time
+        OrderBook::from_levels(d_update.data.asks, d_update.data.bids).stamped(None)
     }
 }
\ No newline at end of file
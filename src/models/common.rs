@@ -1,21 +1,133 @@
-use std::fmt;
 use serde::{de, Deserialize, Deserializer};
-use serde::de::{SeqAccess, Visitor};
-use bigdecimal::{BigDecimal, ToPrimitive};
+use rust_decimal::prelude::ToPrimitive;
+use std::collections::HashMap;
 use std::str::FromStr;
 use rust_decimal::Decimal;
-use thiserror::Error;
+use crate::models::units::{Price, Qty};
 
 // Define a struct for the symbol mappings for each market
-#[derive(Deserialize, Debug, Clone)]
+#[derive(Deserialize, Debug, Clone, PartialEq)]
 pub struct Symbol {
     pub binance: String,
     pub kucoin: String,
     pub bluefin: String,
 }
 
+// Venues the bot knows how to talk to
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum VenueKind {
+    Binance,
+    // only streams the best bid and ask, it makes no REST calls
+    #[serde(rename = "binance_spot")]
+    BinanceSpot,
+    Kucoin,
+    Bluefin,
+}
+
+// Which venue plays which role for a market
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct VenueRoles {
+    pub maker: VenueKind,
+    pub taker: VenueKind,
+    pub reference: VenueKind,
+}
+
+impl Default for VenueRoles {
+    fn default() -> Self {
+        VenueRoles {
+            maker: VenueKind::Kucoin,
+            taker: VenueKind::Bluefin,
+            reference: VenueKind::Binance,
+        }
+    }
+}
+
+// Venue mid blended into the fair value, weights are relative to the other sources
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct FairValueSource {
+    pub venue: VenueKind,
+    pub weight: f64,
+}
+
+// How the reference price of a market is built, see `FairValue`
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct FairValueConfig {
+    pub sources: Vec<FairValueSource>,
+    // sources further than this from the median of the fresh sources are left out
+    pub max_deviation_bps: Option<f64>,
+    // sources without a book for longer are left out, defaults to FEED_MAX_AGE_MS
+    pub max_age_ms: Option<u64>,
+}
+
+// Quote levels rested on each side of the maker book, see `ladder::build_side`
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct LadderConfig {
+    pub levels: usize,
+    // bps each level is pushed away from the mid, one entry per level
+    pub spread_offsets_bps: Vec<f64>,
+    // factor on the size computed for each level, one entry per level
+    pub size_multipliers: Vec<f64>,
+    // lots over all levels of one side, each level is also capped at mm_lot_upper_bound
+    pub max_total_lots: Option<u128>,
+}
+
+// Inventory the quotes are skewed against, see `skew::inventory_skew`
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct InventoryConfig {
+    // unhedged position in base units at which the skew is full and the side adding to it is pulled
+    pub max_position: f64,
+    // volatility of the mid in bps, a full position moves the quotes by this times
+    // skewing_coefficient. The measured volatility replaces it once there is one
+    pub volatility_bps: f64,
+}
+
+// Realised volatility of the fair value, see `EwmaVolatility`
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct VolatilityConfig {
+    // seconds after which a squared return weighs half as much
+    pub half_life_secs: f64,
+    // seconds of price moves the volatility is measured over
+    pub horizon_secs: f64,
+    // the half-spread is kept at least this many times the volatility
+    pub min_spread_multiplier: f64,
+    // volatility in bps above which quote sizes shrink by calm_bps / volatility
+    pub calm_bps: f64,
+}
+
+// Fees of a venue in bps of the notional, negative for a rebate
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+pub struct VenueFees {
+    pub maker_bps: f64,
+    pub taker_bps: f64,
+}
+
+// Edge a quote has to make once hedged to be posted, see `edge::EdgeFilter`
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct EdgeConfig {
+    // bps left after the maker fee, the hedge taker fee and the hedge slippage
+    pub min_edge_bps: f64,
+    // bps the hedge is expected to fill worse than the taker book it is priced off
+    pub hedge_slippage_bps: f64,
+    // bps quotes improve the price computed for them, half a bp when missing
+    pub price_improvement_bps: Option<f64>,
+    // ask the maker and taker venues for the account's fees, the configured ones
+    // apply to a venue that can not tell
+    #[serde(default)]
+    pub fetch_fees: bool,
+}
+
+// How far a resting quote may drift from the one computed for it, see `order_manager::diff_orders`
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct RequoteConfig {
+    // bps the price may differ by before the order is replaced
+    pub price_tolerance_bps: f64,
+    // percent of the computed size the resting size may differ by
+    pub size_tolerance_pct: f64,
+}
+
 // Define a struct for each market entry
-#[derive(Deserialize, Debug, Clone)]
+#[derive(Deserialize, Debug, Clone, PartialEq)]
 pub struct Market {
     pub name: String,
     pub mm_lot_upper_bound: u128,
@@ -24,10 +136,84 @@ pub struct Market {
     pub price_precision: i32,
     pub skewing_coefficient:f64,
     pub symbols: Symbol,
+    #[serde(default)]
+    pub venues: VenueRoles,
+    // the reference venue's mid alone when missing
+    #[serde(default)]
+    pub fair_value: Option<FairValueConfig>,
+    // a single level per side when missing
+    #[serde(default)]
+    pub ladder: Option<LadderConfig>,
+    // quotes are not skewed when missing
+    #[serde(default)]
+    pub inventory: Option<InventoryConfig>,
+    // spreads and sizes ignore volatility when missing
+    #[serde(default)]
+    pub volatility: Option<VolatilityConfig>,
+    // quotes only have to stay 2bps clear of the taker book when missing
+    #[serde(default)]
+    pub edge: Option<EdgeConfig>,
+    // only quotes that did not change at all keep resting when missing
+    #[serde(default)]
+    pub requote: Option<RequoteConfig>,
+    // per venue overrides of the global fee schedule
+    #[serde(default)]
+    pub fees: HashMap<VenueKind, VenueFees>,
+    // optional overrides of the global settings from the environment
+    pub market_making_trigger_bps: Option<f64>,
+    pub market_making_time_throttle_period: Option<u64>,
+    pub periodic_hedging_period: Option<f64>,
+    pub kucoin_leverage: Option<u128>,
+    pub bluefin_leverage: Option<u128>,
+}
+
+impl Market {
+    /**
+     * An eth market with only the required settings, tests override the fields they exercise
+     */
+    #[cfg(test)]
+    pub fn for_tests() -> Market {
+        return Market {
+            name: "eth".to_string(),
+            mm_lot_upper_bound: 30,
+            lot_size: 100,
+            min_size: "0.01".to_string(),
+            price_precision: 2,
+            skewing_coefficient: 0.1,
+            symbols: Symbol {
+                binance: "ethusdt".to_string(),
+                kucoin: "ETHUSDTM".to_string(),
+                bluefin: "ETH-PERP".to_string(),
+            },
+            venues: VenueRoles::default(),
+            fair_value: None,
+            ladder: None,
+            inventory: None,
+            volatility: None,
+            edge: None,
+            requote: None,
+            fees: HashMap::new(),
+            market_making_trigger_bps: None,
+            market_making_time_throttle_period: None,
+            periodic_hedging_period: None,
+            kucoin_leverage: None,
+            bluefin_leverage: None,
+        };
+    }
+}
+
+// Settings of a market once its overrides are applied on top of the global ones
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MarketParams {
+    pub market_making_trigger_bps: f64,
+    pub market_making_time_throttle_period: u64,
+    pub periodic_hedging_period: f64,
+    pub kucoin_leverage: u128,
+    pub bluefin_leverage: u128,
 }
 
 //Config for Circuit Breakers
-#[derive(Deserialize, Debug, Clone, Copy)]
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
 pub struct CircuitBreakerConfig {
     pub num_retries: u8,
     pub failure_threshold: u8,
@@ -38,80 +224,51 @@ pub struct CircuitBreakerConfig {
 #[derive(Deserialize, Debug, Clone)]
 pub struct Config {
     pub circuit_breaker_config: CircuitBreakerConfig,
+    // fees of every venue, a venue missing from the schedule is assumed to charge nothing
+    #[serde(default)]
+    pub fees: HashMap<VenueKind, VenueFees>,
     pub markets: Vec<Market>,
 }
 
-#[derive(Debug, Deserialize, Clone)]
-pub struct OrderBook {
-    pub asks: Vec<(f64, f64)>,
-    pub bids: Vec<(f64, f64)>,
-}
-
-
-pub trait BookOperations {
-    fn calculate_mid_prices(&self) -> Vec<f64>;
-    fn bid_shift(&self, shift:f64) -> Vec<f64>;
-    fn ask_shift(&self, shift:f64) -> Vec<f64>;
-}
-
-impl BookOperations for OrderBook {
-    fn calculate_mid_prices(&self) -> Vec<f64> {
-        self.asks.iter()
-            .zip(self.bids.iter())
-            .map(|((ask_price, _), (bid_price, _))| (ask_price + bid_price) / 2.0)
-            .collect()
-    }
-    fn bid_shift(&self, shift:f64) -> Vec<f64> {
-        self.bids.iter()
-            .map(|(_, bid_size)| (bid_size + shift))
-            .collect()
-    }
-    fn ask_shift(&self, shift:f64) -> Vec<f64> {
-        self.bids.iter()
-            .map(|(_, bid_size)| (bid_size + shift))
-            .collect()
-    }
-
-}
-
 pub trait SpreadCalculator {
     fn calculate_spreads(&self, mid_prices1: &[f64], mid_prices2: &[f64]) -> Vec<f64>;
 }
 
-pub fn add(term: &[f64], summand: &[f64]) -> Vec<f64> {
+pub fn add(term: &[Price], summand: &[Price]) -> Vec<Price> {
     term.iter()
         .zip(summand.iter())
         .map(|(&term_item, &summand_item)| term_item + summand_item)
         .collect()
 }
-pub fn subtract(term: &[f64], minuend: &[f64]) -> Vec<f64> {
+pub fn subtract(term: &[Price], minuend: &[Price]) -> Vec<Price> {
         term.iter()
         .zip(minuend.iter())
         .map(|(&term_item, &minuend_item)| term_item - minuend_item)
         .collect()
 }
 
-pub fn divide(dividend: &[f64], divisor: f64) -> Vec<f64> {
+pub fn divide(dividend: &[Price], divisor: Decimal) -> Vec<Price> {
         dividend.iter()
         .map(|&dividend_item| dividend_item / divisor)
         .collect()
 }
 
-pub fn multiply(multiplicand: &[f64], multiplier: f64) -> Vec<f64> {
+#[allow(dead_code)]
+pub fn multiply(multiplicand: &[Price], multiplier: Decimal) -> Vec<Price> {
     multiplicand.iter()
         .map(|&multiplicand_item| multiplicand_item * multiplier)
         .collect()
 }
 
-pub fn abs(values: &[f64]) -> Vec<f64> {
+pub fn abs(values: &[Price]) -> Vec<Price> {
     values.iter()
         .map(|&value| value.abs())
         .collect()
 }
 
 #[allow(dead_code)]
-pub fn is_positive(values: &[f64]) -> bool {
-    values[0] > 0.0
+pub fn is_positive(values: &[Price]) -> bool {
+    values[0] > Price::ZERO
 }
 
 pub fn deserialize_optional_f64<'de, D>(deserializer: D) -> Result<Option<f64>, D::Error>
@@ -134,67 +291,41 @@ pub fn deserialize_string_to_f64<'de, D>(deserializer: D) -> Result<f64, D::Erro
     s.parse::<f64>().map_err(de::Error::custom)
 }
 
-pub fn deserialize_as_string_tuples<'de, D>(deserializer: D) -> Result<Vec<(f64, f64)>, D::Error>
-    where
-        D: Deserializer<'de>,
+/**
+ * Price levels given as strings or numbers. Both are read from their text so they stay
+ * exact and the price can key a book
+ */
+pub fn deserialize_decimal_levels<'de, D>(deserializer: D) -> Result<Vec<(Price, Qty)>, D::Error>
+where
+    D: Deserializer<'de>,
 {
-    let string_tuples: Vec<(String, String)> = Vec::deserialize(deserializer)?;
-
-    let mut number_tuples: Vec<(f64, f64)> = Vec::with_capacity(string_tuples.len());
-    for (s1, s2) in string_tuples {
-        let n1 = s1.parse::<f64>().map_err(serde::de::Error::custom)?;
-        let n2 = s2.parse::<f64>().map_err(serde::de::Error::custom)?;
-        number_tuples.push((n1, n2));
-    }
-
-    Ok(number_tuples)
+    let levels: Vec<(serde_json::Value, serde_json::Value)> = Vec::deserialize(deserializer)?;
+    return levels
+        .into_iter()
+        .map(|(price, size)| {
+            let price = Price::from_str(&json_number_text(&price)).map_err(de::Error::custom)?;
+            let size = Qty::from_str(&json_number_text(&size)).map_err(de::Error::custom)?;
+            Ok((price, size))
+        })
+        .collect();
 }
 
-pub fn deserialize_as_mix_tuples<'de, D>(deserializer: D) -> Result<Vec<(f64, f64)>, D::Error>
-    where
-        D: Deserializer<'de>,
-{
-    struct StringTupleVisitor;
-
-    impl<'de> Visitor<'de> for StringTupleVisitor {
-        type Value = Vec<(f64, f64)>;
-        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-            formatter.write_str("a list of price-amount tuples where the amount can be a string or a number")
-        }
-        fn visit_seq<S>(self, mut seq: S) -> Result<Vec<(f64, f64)>, S::Error>
-            where
-                S: SeqAccess<'de>,
-        {
-            let mut tuples = Vec::new();
-
-            while let Some((price, amount)) = seq.next_element::<(String, serde_json::Value)>()? {
-                let price_parsed = price.parse::<f64>().map_err(de::Error::custom)?;
-                let amount_parsed = match amount {
-                    serde_json::Value::String(s) => s.parse::<f64>().map_err(de::Error::custom)?,
-                    serde_json::Value::Number(n) => n.as_f64().ok_or_else(|| de::Error::custom("Invalid number"))?,
-                    _ => return Err(de::Error::custom("Invalid type for amount")),
-                };
-                tuples.push((price_parsed, amount_parsed));
-            }
-
-            Ok(tuples)
-        }
-
-    }
-
-    deserializer.deserialize_seq(StringTupleVisitor)
+fn json_number_text(value: &serde_json::Value) -> String {
+    return match value {
+        serde_json::Value::String(s) => s.clone(),
+        other => other.to_string(),
+    };
 }
 
-
-pub fn deserialize_as_bignumber_string_tuples<'de, D>(deserializer: D) -> Result<Vec<(f64, f64)>, D::Error>
+pub fn deserialize_as_bignumber_string_tuples<'de, D>(deserializer: D) -> Result<Vec<(Price, Qty)>, D::Error>
     where D: Deserializer<'de>,
 {
     let string_tuples: Vec<(String, String)> = Vec::deserialize(deserializer)?;
 
-    let mut number_tuples: Vec<(f64, f64)> = Vec::with_capacity(string_tuples.len());
+    let mut number_tuples: Vec<(Price, Qty)> = Vec::with_capacity(string_tuples.len());
     for (s1, s2) in string_tuples {
-        let n1 = convert_bignumber_to_f64(&s1).map_err(de::Error::custom)?;
-        let n2 = convert_bignumber_to_f64(&s2).map_err(de::Error::custom)?;
+        let n1 = Price::from_e18(&s1).map_err(de::Error::custom)?;
+        let n2 = Qty::from_e18(&s2).map_err(de::Error::custom)?;
         number_tuples.push((n1, n2));
     }
 
@@ -211,45 +342,3 @@ pub fn deserialize_to_f64_via_decimal<'de, D>(deserializer: D) -> Result<f64, D:
     let decimal_value = parsed / divisor;
     Ok(decimal_value.to_f64().unwrap())
 }
-
-
-pub fn round_to_precision(value: f64, precision: i32) -> f64 {
-    let scale = 10f64.powi(precision);
-    (value * scale).round() / scale
-}
-// Define a custom error type
-#[derive(Error, Debug)]
-enum ConversionError {
-    #[error("failed to parse big decimal")]
-    BigDecimalParseError,
-
-    #[error("failed to convert to f64")]
-    F64ConversionError,
-}
-fn convert_bignumber_to_f64(bignumber: &str) -> Result<f64, ConversionError> {
-    let bd = BigDecimal::from_str(bignumber)
-        .map_err(|_| ConversionError::BigDecimalParseError)?;
-
-    let scaled = bd / BigDecimal::from_str("1000000000000000000")
-        .map_err(|_| ConversionError::BigDecimalParseError)?;
-
-    scaled.to_f64().ok_or(ConversionError::F64ConversionError)
-}
-#[cfg(test)]
-mod tests {
-    use crate::models::common::{BookOperations, OrderBook};
-
-    #[test]
-    fn test_mid_prices() {
-        let order_book = OrderBook {
-            asks: vec![(102.0, 10.0), (103.0, 20.0), (104.0, 30.0)],
-            bids: vec![(98.0, 10.0), (97.0, 20.0), (96.0, 30.0)],
-        };
-
-        let mid_prices = order_book.calculate_mid_prices();
-        let expected_mid_prices = vec![100.0, 100.0, 100.0];
-
-        assert_eq!(mid_prices, expected_mid_prices, "The mid prices should be correctly calculated.");
-    }
-
-}
@@ -1,26 +1,9 @@
 use serde::{Deserialize, Serialize};
-use crate::models::common::OrderBook;
-use crate::models::common::deserialize_as_mix_tuples;
-#[derive(Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct InstanceServer {
-    pub endpoint: String,
-    pub encrypt: bool,
-    pub protocol: String,
-    pub ping_interval: u64,
-    pub ping_timeout: u64,
-}
-#[derive(Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct Data {
-    pub token: String,
-    pub instance_servers: Vec<InstanceServer>,
-}
-#[derive(Deserialize)]
-pub struct Response {
-    pub code: String,
-    pub data: Data,
-}
+use rust_decimal::Decimal;
+use std::str::FromStr;
+use crate::models::common::{deserialize_decimal_levels, deserialize_string_to_f64};
+use crate::models::order_book::OrderBook;
+use crate::models::units::{Price, Qty};
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Comm {
     pub id: String,
@@ -29,33 +12,61 @@ pub struct Comm {
     pub data: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct Level2Depth {
-    pub topic: String,
-    #[serde(rename = "type")]
-    pub type_: String,
-    pub subject: String,
-    pub sn: u64,
-    pub data: Level2Data,
+// Full depth snapshot of /api/v1/level2/snapshot, levels are sorted from the best price
+#[derive(Debug, Deserialize)]
+pub struct Level2Snapshot {
+    pub sequence: u64,
+    #[serde(deserialize_with = "deserialize_decimal_levels")]
+    pub asks: Vec<(Price, Qty)>,
+    #[serde(deserialize_with = "deserialize_decimal_levels")]
+    pub bids: Vec<(Price, Qty)>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct Level2Data {
-    #[serde(deserialize_with = "deserialize_as_mix_tuples")]
-    pub bids: Vec<(f64, f64)>,
+// Fees the account pays on a contract, from /api/v1/trade-fees. Rates are fractions of the notional
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TradeFees {
+    #[serde(deserialize_with = "deserialize_string_to_f64")]
+    pub maker_fee_rate: f64,
+    #[serde(deserialize_with = "deserialize_string_to_f64")]
+    pub taker_fee_rate: f64,
+}
+
+// Message of the /contractMarket/level2 topic
+#[derive(Debug, Deserialize)]
+pub struct Level2Update {
+    pub data: Level2UpdateData,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Level2UpdateData {
     pub sequence: u64,
-    pub timestamp: u64,
-    pub ts: u64,
-    #[serde(deserialize_with = "deserialize_as_mix_tuples")]
-    pub asks: Vec<(f64, f64)>,
+    // "price,side,size", a size of 0 removes the level
+    pub change: String,
+    // ms since epoch
+    #[serde(default)]
+    pub timestamp: Option<u64>,
+}
+
+// One price level change of a level2 update
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Level2Change {
+    pub price: Price,
+    pub is_bid: bool,
+    pub size: Qty,
 }
 
-impl From<Level2Depth> for OrderBook {
-    fn from(l2_depth: Level2Depth) -> Self {
-        OrderBook {
-            asks: l2_depth.data.asks,
-            bids: l2_depth.data.bids,
-        }
+impl Level2UpdateData {
+    pub fn parse_change(&self) -> Option<Level2Change> {
+        let mut parts = self.change.split(',');
+        let price = Price::from_str(parts.next()?).ok()?;
+        let is_bid = match parts.next()? {
+            "buy" => true,
+            "sell" => false,
+            _ => return None,
+        };
+        let size = Qty::from_str(parts.next()?).ok()?;
+        return Some(Level2Change { price, is_bid, size });
     }
 }
 
@@ -84,6 +95,19 @@ pub struct TickerV2 {
     pub data: TickerData,
 }
 
+impl From<TickerV2> for OrderBook {
+    fn from(ticker: TickerV2) -> Self {
+        let best_ask_price = Price::from_str(&ticker.data.best_ask_price).unwrap_or_default();
+        let best_bid_price = Price::from_str(&ticker.data.best_bid_price).unwrap_or_default();
+        OrderBook::from_levels(
+            vec![(best_ask_price, Qty::from(Decimal::from(ticker.data.best_ask_size)))],
+            vec![(best_bid_price, Qty::from(Decimal::from(ticker.data.best_bid_size)))],
+        )
+        // tickers are stamped in nanoseconds
+        .stamped(Some(ticker.data.ts as u128 / 1_000_000))
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct Position {
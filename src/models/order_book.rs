@@ -0,0 +1,229 @@
+use crate::models::units::{Price, Qty};
+use crate::utils::get_current_time;
+use rust_decimal::Decimal;
+use std::collections::BTreeMap;
+
+// when the venue sent a book and when it was read off the socket, ms since epoch
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FeedTimes {
+    // None for venues that do not stamp their messages
+    pub exchange: Option<u128>,
+    pub received: u128,
+}
+
+impl FeedTimes {
+    // ms the message spent between the venue and the bot, clocks drifting apart count as 0
+    pub fn latency(&self) -> Option<u128> {
+        return self.exchange.map(|exchange| self.received.saturating_sub(exchange));
+    }
+}
+
+/**
+ * Price levels of both sides of a book, kept sorted by price so a level is inserted or
+ * removed in O(log n) and the best levels are read without sorting. Levels are read
+ * best first on both sides
+ */
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct OrderBook {
+    asks: BTreeMap<Price, Qty>,
+    bids: BTreeMap<Price, Qty>,
+    times: FeedTimes,
+}
+
+impl OrderBook {
+    pub fn new() -> OrderBook {
+        return OrderBook::default();
+    }
+
+    // book of the levels a venue sent, in any order
+    pub fn from_levels(asks: Vec<(Price, Qty)>, bids: Vec<(Price, Qty)>) -> OrderBook {
+        let mut book = OrderBook::new();
+        for (price, size) in asks {
+            book.set_level(false, price, size);
+        }
+        for (price, size) in bids {
+            book.set_level(true, price, size);
+        }
+        return book;
+    }
+
+    /**
+     * Marks the book as read now, `exchange` is the time the venue put on the message
+     */
+    pub fn stamped(mut self, exchange: Option<u128>) -> OrderBook {
+        self.times = FeedTimes {
+            exchange,
+            received: get_current_time(),
+        };
+        return self;
+    }
+
+    pub fn times(&self) -> FeedTimes {
+        return self.times;
+    }
+
+    /**
+     * Sets the size resting at `price`, a size of zero removes the level
+     */
+    pub fn set_level(&mut self, is_bid: bool, price: Price, size: Qty) {
+        if size.is_zero() {
+            self.remove_level(is_bid, price);
+            return;
+        }
+        self.side_mut(is_bid).insert(price, size);
+    }
+
+    pub fn remove_level(&mut self, is_bid: bool, price: Price) -> Option<Qty> {
+        return self.side_mut(is_bid).remove(&price);
+    }
+
+    fn side_mut(&mut self, is_bid: bool) -> &mut BTreeMap<Price, Qty> {
+        return if is_bid { &mut self.bids } else { &mut self.asks };
+    }
+
+    // levels of one side, best price first
+    pub fn levels(&self, is_bid: bool) -> Box<dyn Iterator<Item = (Price, Qty)> + '_> {
+        let level = |(price, size): (&Price, &Qty)| (*price, *size);
+        if is_bid {
+            return Box::new(self.bids.iter().rev().map(level));
+        }
+        return Box::new(self.asks.iter().map(level));
+    }
+
+    pub fn asks(&self) -> Box<dyn Iterator<Item = (Price, Qty)> + '_> {
+        return self.levels(false);
+    }
+
+    pub fn bids(&self) -> Box<dyn Iterator<Item = (Price, Qty)> + '_> {
+        return self.levels(true);
+    }
+
+    pub fn best_ask(&self) -> Option<(Price, Qty)> {
+        return self.asks.first_key_value().map(|(price, size)| (*price, *size));
+    }
+
+    pub fn best_bid(&self) -> Option<(Price, Qty)> {
+        return self.bids.last_key_value().map(|(price, size)| (*price, *size));
+    }
+
+    // copy of the best `depth` levels of each side
+    pub fn truncated(&self, depth: usize) -> OrderBook {
+        return OrderBook {
+            asks: self.asks().take(depth).collect(),
+            bids: self.bids().take(depth).collect(),
+            times: self.times,
+        };
+    }
+
+    pub fn mid_price(&self) -> Option<Price> {
+        let (ask, _) = self.best_ask()?;
+        let (bid, _) = self.best_bid()?;
+        return Some((ask + bid) / Decimal::TWO);
+    }
+
+    // mid price of the n-th best ask and bid, for every depth both sides have
+    pub fn mid_prices_by_level(&self) -> Vec<Price> {
+        return self
+            .asks()
+            .zip(self.bids())
+            .map(|((ask, _), (bid, _))| (ask + bid) / Decimal::TWO)
+            .collect();
+    }
+
+    /**
+     * Price of the last level an order of `size` reaches when it takes liquidity, None when
+     * the book is not deep enough to fill it
+     */
+    pub fn price_for_size(&self, is_buy: bool, size: Qty) -> Option<Price> {
+        let mut cumulative = Qty::ZERO;
+        for (price, level_size) in self.levels(!is_buy) {
+            cumulative = cumulative + level_size;
+            if cumulative >= size {
+                return Some(price);
+            }
+        }
+        return None;
+    }
+
+    /**
+     * Average price an order of `size` fills at when it takes liquidity, None when the book
+     * is not deep enough to fill it
+     */
+    pub fn vwap(&self, is_buy: bool, size: Qty) -> Option<Price> {
+        if size <= Qty::ZERO {
+            return None;
+        }
+        let mut remaining = size;
+        let mut notional = Decimal::ZERO;
+        for (price, level_size) in self.levels(!is_buy) {
+            let filled = remaining.min(level_size);
+            notional += price.value() * filled.value();
+            remaining = remaining - filled;
+            if remaining.is_zero() {
+                return Some(Price::from(notional / size.value()));
+            }
+        }
+        return None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    fn price(s: &str) -> Price {
+        return Price::from_str(s).unwrap();
+    }
+
+    fn qty(s: &str) -> Qty {
+        return Qty::from_str(s).unwrap();
+    }
+
+    fn book() -> OrderBook {
+        return OrderBook::from_levels(
+            vec![(price("101"), qty("1")), (price("100.5"), qty("1")), (price("102"), qty("4"))],
+            vec![(price("99.5"), qty("2")), (price("100"), qty("3"))],
+        );
+    }
+
+    #[test]
+    fn test_mid_prices() {
+        let level = |price: i64, size: i64| (Price::from(Decimal::from(price)), Qty::from(Decimal::from(size)));
+        let order_book = OrderBook::from_levels(
+            vec![level(102, 10), level(103, 20), level(104, 30)],
+            vec![level(98, 10), level(97, 20), level(96, 30)],
+        );
+
+        let mid_prices = order_book.mid_prices_by_level();
+        let expected_mid_prices = vec![Price::from(Decimal::from(100)); 3];
+
+        assert_eq!(mid_prices, expected_mid_prices, "The mid prices should be correctly calculated.");
+    }
+
+    #[test]
+    fn test_keeps_levels_sorted() {
+        let mut book = book();
+        assert_eq!(book.best_ask(), Some((price("100.5"), qty("1"))));
+        assert_eq!(book.best_bid(), Some((price("100"), qty("3"))));
+
+        book.set_level(true, price("100.25"), qty("1"));
+        book.set_level(false, price("100.5"), Qty::ZERO);
+        assert_eq!(book.best_ask(), Some((price("101"), qty("1"))));
+        assert_eq!(
+            book.bids().collect::<Vec<_>>(),
+            vec![(price("100.25"), qty("1")), (price("100"), qty("3")), (price("99.5"), qty("2"))]
+        );
+        assert_eq!(book.truncated(1).asks().collect::<Vec<_>>(), vec![(price("101"), qty("1"))]);
+    }
+
+    #[test]
+    fn test_prices_for_size() {
+        let book = book();
+        assert_eq!(book.mid_price(), Some(price("100.25")));
+        assert_eq!(book.price_for_size(true, qty("1.5")), Some(price("101")));
+        assert_eq!(book.vwap(true, qty("2")), Some(price("100.75")));
+        assert_eq!(book.vwap(false, qty("4")), Some(price("99.875")));
+        assert_eq!(book.vwap(true, qty("7")), None);
+    }
+}
@@ -0,0 +1,181 @@
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::{Decimal, RoundingStrategy};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::ops::{Add, Div, Mul, Sub};
+use std::str::FromStr;
+
+// Bluefin sends and expects prices and quantities as integers scaled by 1e18
+static E18_SCALE: u32 = 18;
+
+// implements what prices and quantities share on top of their decimal
+macro_rules! fixed_point {
+    ($name:ident) => {
+        // not every helper is used by both types
+        #[allow(dead_code)]
+        impl $name {
+            pub const ZERO: $name = $name(Decimal::ZERO);
+
+            pub fn value(self) -> Decimal {
+                return self.0;
+            }
+
+            // for logs and the statistics only, never to compute an order
+            pub fn to_f64(self) -> f64 {
+                return self.0.to_f64().unwrap_or_default();
+            }
+
+            pub fn abs(self) -> $name {
+                return $name(self.0.abs());
+            }
+
+            pub fn is_zero(self) -> bool {
+                return self.0.is_zero();
+            }
+
+            // reads an integer scaled by 1e18 without going through a float
+            pub fn from_e18(raw: &str) -> Result<$name, rust_decimal::Error> {
+                let mut value = Decimal::from_str(raw)?;
+                value.set_scale(value.scale() + E18_SCALE)?;
+                return Ok($name(value.normalize()));
+            }
+
+            // integer scaled by 1e18, digits below 1e-18 are cut. None when negative or too large
+            pub fn to_e18(self) -> Option<u128> {
+                return self
+                    .0
+                    .checked_mul(Decimal::from(10u64.pow(E18_SCALE)))?
+                    .trunc()
+                    .to_u128();
+            }
+        }
+
+        impl From<Decimal> for $name {
+            fn from(value: Decimal) -> Self {
+                return $name(value);
+            }
+        }
+
+        impl FromStr for $name {
+            type Err = rust_decimal::Error;
+
+            // venues send plain decimals, a few send exponents for very small values
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                let value = Decimal::from_str(s).or_else(|_| Decimal::from_scientific(s))?;
+                return Ok($name(value.normalize()));
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                return fmt::Display::fmt(&self.0, f);
+            }
+        }
+
+        impl Add for $name {
+            type Output = $name;
+
+            fn add(self, other: $name) -> $name {
+                return $name(self.0 + other.0);
+            }
+        }
+
+        impl Sub for $name {
+            type Output = $name;
+
+            fn sub(self, other: $name) -> $name {
+                return $name(self.0 - other.0);
+            }
+        }
+
+        impl Mul<Decimal> for $name {
+            type Output = $name;
+
+            fn mul(self, factor: Decimal) -> $name {
+                return $name(self.0 * factor);
+            }
+        }
+
+        impl Div<Decimal> for $name {
+            type Output = $name;
+
+            fn div(self, divisor: Decimal) -> $name {
+                return $name(self.0 / divisor);
+            }
+        }
+    };
+}
+
+/**
+ * Price of one contract. Prices stay fixed point from the venue messages to the orders
+ * so they key books exactly and land on the tick they are rounded to
+ */
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Price(Decimal);
+
+/**
+ * Size of a level or an order in base units
+ */
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Qty(Decimal);
+
+fixed_point!(Price);
+fixed_point!(Qty);
+
+impl Price {
+    /**
+     * Rounds to a tick of `precision` decimals, away from the other side of the book:
+     * bids down and asks up, so a quote is never more aggressive than the price it came from
+     */
+    pub fn round_to_tick(self, precision: i32, is_bid: bool) -> Price {
+        let strategy = if is_bid { RoundingStrategy::ToNegativeInfinity } else { RoundingStrategy::ToPositiveInfinity };
+        return Price(self.0.round_dp_with_strategy(precision.max(0) as u32, strategy));
+    }
+}
+
+impl Qty {
+    /**
+     * Whole lots of `lots_per_unit` in this quantity, a partial lot is dropped
+     */
+    pub fn to_lots(self, lots_per_unit: u128) -> u128 {
+        return (self.0 * Decimal::from(lots_per_unit)).floor().to_u128().unwrap_or(0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn price(s: &str) -> Price {
+        return Price::from_str(s).expect("Could not parse price");
+    }
+
+    #[test]
+    fn test_rounds_to_tick_without_float_error() {
+        // 1.15 is 1.149999... as a float, flooring it lands a tick below
+        assert_eq!(price("1.15").round_to_tick(2, true), price("1.15"));
+        assert_eq!(price("1.15").round_to_tick(2, false), price("1.15"));
+        assert_eq!((price("0.1") + price("0.2")).round_to_tick(1, false), price("0.3"));
+
+        assert_eq!(price("1.151").round_to_tick(2, true), price("1.15"));
+        assert_eq!(price("1.151").round_to_tick(2, false), price("1.16"));
+    }
+
+    #[test]
+    fn test_e18_round_trip() {
+        let raw = "3512340000000000000000";
+        assert_eq!(Price::from_e18(raw), Ok(price("3512.34")));
+        assert_eq!(price("3512.34").to_e18(), Some(3512340000000000000000));
+        assert_eq!(Qty::from_e18("100000000000000000"), Qty::from_str("0.1"));
+        assert_eq!(price("-1").to_e18(), None);
+    }
+
+    #[test]
+    fn test_to_lots_floors() {
+        let qty = Qty::from_str("0.29").unwrap();
+        assert_eq!(qty.to_lots(100), 29);
+        assert_eq!(Qty::from_str("0.299").unwrap().to_lots(100), 29);
+    }
+}
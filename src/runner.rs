@@ -0,0 +1,4 @@
+pub mod market_runner;
+pub mod shutdown;
+pub mod signals;
+pub mod supervisor;
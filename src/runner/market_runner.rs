@@ -0,0 +1,186 @@
+use std::sync::mpsc;
+use std::sync::mpsc::Sender;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+
+use crate::clients::error::VenueError;
+use crate::config::app_config::{AppConfig, ConfigDiff};
+use crate::hedge::hedger::{Hedger, HedgerEvent, HedgerFeed, HGR};
+use crate::market_maker::mm::{MMEvent, MarketMaker, MM};
+use crate::models::common::Market;
+use crate::runner::supervisor::{spawn_worker, SupervisorEvent};
+use crate::statistics::account_stats::{AccountStatsEvent, AccountStatsSubscriber};
+use crate::statistics::stats::{Statistics, Stats};
+use crate::utils::forward_into;
+
+static NEXT_INSTANCE: AtomicU64 = AtomicU64::new(0);
+
+/**
+ * The running threads of a single market and the channels to control them
+ */
+pub struct MarketHandle {
+    pub market: Market,
+    // unique per spawn, tells the supervisor which instance a worker belonged to
+    pub instance: u64,
+    tx_mm: Sender<MMEvent>,
+    tx_hgr: Sender<HedgerEvent>,
+    mm_handle: JoinHandle<()>,
+    // taken once the hedger is stopped
+    hgr_handle: Option<JoinHandle<()>>,
+    stats_handle: JoinHandle<()>,
+    tx_stats_shutdown: Sender<()>,
+}
+
+impl MarketHandle {
+    pub fn update(&self, config: Arc<AppConfig>) {
+        if self.tx_mm.send(MMEvent::ConfigUpdate(config.clone())).is_err() {
+            tracing::warn!(market = self.market.name, "Market maker is gone, config update dropped");
+        }
+        if self.tx_hgr.send(HedgerEvent::ConfigUpdate(config)).is_err() {
+            tracing::warn!(market = self.market.name, "Hedger is gone, config update dropped");
+        }
+    }
+
+    /**
+     * Cancels the market's quotes, the market maker keeps forwarding the taker
+     * order book so the hedger can still work off the net position
+     */
+    pub fn stop_quoting(&self) {
+        if self.tx_mm.send(MMEvent::StopQuoting).is_err() {
+            tracing::warn!(market = self.market.name, "Market maker is gone, can not stop quoting");
+        }
+    }
+
+    pub fn stop_hedger(mut self) -> MarketHandle {
+        if let Some(hgr_handle) = self.hgr_handle.take() {
+            let _ = self.tx_hgr.send(HedgerEvent::Shutdown);
+            if hgr_handle.join().is_err() {
+                tracing::error!(market = self.market.name, "Hedger panicked while shutting down");
+            }
+        }
+        return self;
+    }
+
+    /**
+     * Stops the hedger before the market maker, otherwise the hedger sees its
+     * taker order book feed disconnect and trips its breaker. The stats thread is
+     * woken from its wait between periods, it only finishes a fill query in flight
+     */
+    pub fn shutdown(self) {
+        let handle = self.stop_hedger();
+
+        let _ = handle.tx_mm.send(MMEvent::Shutdown);
+        if handle.mm_handle.join().is_err() {
+            tracing::error!(market = handle.market.name, "Market maker panicked while shutting down");
+        }
+        let _ = handle.tx_stats_shutdown.send(());
+        if handle.stats_handle.join().is_err() {
+            tracing::error!(market = handle.market.name, "Stats panicked while shutting down");
+        }
+
+        tracing::info!(market = handle.market.name, "Market stopped");
+    }
+
+
+}
+
+/**
+ * Spawns the market maker, hedger and stats threads of a market and
+ * registers it with the account stats. Fails when the hedger can not fetch the positions
+ */
+pub fn spawn_market(
+    market: Market,
+    config: Arc<AppConfig>,
+    tx_account_stats: &Sender<AccountStatsEvent>,
+    tx_supervisor: &Sender<SupervisorEvent>,
+) -> Result<MarketHandle, VenueError> {
+    let instance = NEXT_INSTANCE.fetch_add(1, Ordering::Relaxed);
+
+    let (tx_hgr_event, rx_hgr_event) = mpsc::channel();
+    let tx_taker_hedger_ob = forward_into(
+        &tx_hgr_event,
+        HedgerEvent::TakerOrderBook,
+        HedgerEvent::Disconnected(HedgerFeed::TakerOrderBook),
+    );
+    let (
+        mut mm,
+        tx_stats,
+        tx_taker_balance,
+        tx_maker_balance,
+        tx_hedger,
+        tx_account_data_bluefin_user_trade,
+    ) = MM::new(market.clone(), config.clone(), tx_taker_hedger_ob);
+    let mut hgr = HGR::new(market.clone(), config.clone(), tx_hedger, tx_hgr_event, rx_hgr_event)?;
+
+    let subscriber = AccountStatsSubscriber {
+        maker: market.venues.maker,
+        taker: market.venues.taker,
+        tx_maker_balance,
+        tx_taker_balance,
+        tx_taker_trade: tx_account_data_bluefin_user_trade,
+    };
+    tx_account_stats
+        .send(AccountStatsEvent::Register(subscriber))
+        .expect("Account stats event channel has disconnected!");
+
+    let tx_mm = mm.event_sender();
+    let tx_hgr = hgr.event_sender();
+
+    let mm_handle = spawn_worker(&market.name, "mm", instance, tx_supervisor, move || {
+        mm.connect();
+    });
+
+    let market_clone_for_stats = market.clone();
+    let (tx_stats_shutdown, rx_stats_shutdown) = mpsc::channel();
+    let stats_handle = spawn_worker(&market.name, "stats", instance, tx_supervisor, move || {
+        Stats::new(market_clone_for_stats, config, tx_stats, rx_stats_shutdown).emit();
+    });
+
+    let hgr_handle = spawn_worker(&market.name, "hedger", instance, tx_supervisor, move || {
+        hgr.connect();
+    });
+
+    return Ok(MarketHandle {
+        market,
+        instance,
+        tx_mm,
+        tx_hgr,
+        mm_handle,
+        hgr_handle: Some(hgr_handle),
+        stats_handle,
+        tx_stats_shutdown,
+    });
+}
+
+/**
+ * Applies a config diff to the running markets. Returns the added and restarted
+ * markets, the supervisor starts them so a failing start is retried like a crash
+ */
+pub fn apply_diff(markets: &mut Vec<MarketHandle>, diff: ConfigDiff, config: Arc<AppConfig>) -> Vec<Market> {
+    let stopped: Vec<String> = diff
+        .removed
+        .iter()
+        .cloned()
+        .chain(diff.restarted.iter().map(|market| market.name.clone()))
+        .collect();
+
+    let (to_stop, running): (Vec<MarketHandle>, Vec<MarketHandle>) = markets
+        .drain(..)
+        .partition(|handle| stopped.contains(&handle.market.name));
+    *markets = running;
+
+    for handle in to_stop {
+        tracing::info!(market = handle.market.name, "Stopping market");
+        handle.shutdown();
+    }
+
+    for market in diff.updated.iter() {
+        if let Some(handle) = markets.iter().find(|handle| handle.market.name == market.name) {
+            tracing::info!(market = market.name, "Updating market config");
+            handle.update(config.clone());
+        }
+    }
+
+    return diff.restarted.into_iter().chain(diff.added).collect();
+}
@@ -0,0 +1,175 @@
+use std::str::FromStr;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+
+use crate::clients::runtime::block_on;
+use crate::config::app_config::AppConfig;
+use crate::models::common::Market;
+use crate::models::units::Qty;
+use crate::runner::market_runner::MarketHandle;
+use crate::venues::venue::{create_maker_venue, create_taker_venue, MakerVenue, TakerVenue};
+
+static POSITION_POLL_PERIOD: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShutdownReason {
+    Interrupt,
+    Terminate,
+    // balances feed the breakers of every market, there is no trading without them
+    AccountStatsFailed,
+    // the supervisor panicked on the main thread, nothing restarts the markets anymore
+    Panic,
+}
+
+struct MarketVenues {
+    market: Market,
+    maker: Arc<dyn MakerVenue>,
+    taker: Arc<dyn TakerVenue>,
+}
+
+/**
+ * Stops every market in an order that leaves no resting quotes behind:
+ * quoting is stopped and all maker orders are cancelled, the hedgers get until
+ * `SHUTDOWN_HEDGE_TIMEOUT` to bring the net position under `min_size`, then both
+ * legs are optionally flattened with reduce-only orders
+ */
+pub fn shutdown(markets: Vec<MarketHandle>, config: &AppConfig, reason: ShutdownReason) {
+    tracing::info!("Shutting down on {:?}", reason);
+
+    for handle in markets.iter() {
+        handle.stop_quoting();
+    }
+
+    // markets whose market maker crashed still need their quotes pulled
+    let venues: Vec<MarketVenues> = config
+        .markets
+        .iter()
+        .map(|market| {
+            let params = config.market_params(market);
+            return MarketVenues {
+                market: market.clone(),
+                maker: create_maker_venue(market.venues.maker, &config.vars, &params),
+                taker: create_taker_venue(market.venues.taker, &config.vars, &params),
+            };
+        })
+        .collect();
+
+    let cancels = block_on(futures::future::join_all(
+        venues.iter().map(|venue| venue.maker.cancel_all_orders(&venue.market)),
+    ));
+    for (venue, resp) in venues.iter().zip(cancels) {
+        if let Err(e) = resp {
+            tracing::error!(market = venue.market.name, "Could not cancel orders on shutdown: {}", e);
+        }
+    }
+
+    let deadline = Instant::now() + Duration::from_secs(config.vars.shutdown_hedge_timeout);
+    for venue in venues.iter() {
+        wait_until_hedged(venue, deadline);
+    }
+
+    let markets: Vec<MarketHandle> = markets.into_iter().map(MarketHandle::stop_hedger).collect();
+
+    if config.vars.flatten_on_shutdown {
+        for venue in venues.iter() {
+            flatten(venue, config.vars.dry_run);
+        }
+    }
+
+    for handle in markets {
+        handle.shutdown();
+    }
+
+    tracing::info!("Shutdown complete");
+}
+
+fn wait_until_hedged(venue: &MarketVenues, deadline: Instant) {
+    let market = &venue.market;
+    let min_size = Decimal::from_str(&market.min_size).expect("min_size is validated on load");
+
+    loop {
+        let (maker_position, taker_position) =
+            block_on(async { futures::join!(venue.maker.get_position(market), venue.taker.get_position(market)) });
+        let (maker_position, taker_position) = match (maker_position, taker_position) {
+            (Ok(maker_position), Ok(taker_position)) => (maker_position, taker_position),
+            (Err(e), _) | (_, Err(e)) => {
+                tracing::warn!(market = market.name, "Could not fetch positions, not waiting for the hedger: {}", e);
+                return;
+            }
+        };
+        let net = maker_position + taker_position;
+
+        if net.abs() < min_size {
+            tracing::info!(market = market.name, net_position = net.to_f64(), "Market is hedged");
+            return;
+        }
+
+        if Instant::now() >= deadline {
+            tracing::error!(market = market.name, net_position = net.to_f64(), "Timed out waiting for the hedger");
+            return;
+        }
+
+        tracing::info!(market = market.name, net_position = net.to_f64(), "Waiting for the hedger");
+        thread::sleep(POSITION_POLL_PERIOD);
+    }
+}
+
+fn flatten(venue: &MarketVenues, dry_run: bool) {
+    let market = &venue.market;
+
+    match block_on(venue.maker.get_position(market)).map(flatten_order) {
+        Ok(Some((is_buy, quantity))) => {
+            // maker positions are expressed in base units, orders in lots
+            let lots = (quantity * Decimal::from(market.lot_size)).to_u128().unwrap_or(0);
+            tracing::info!(market = market.name, is_buy, lots, dry_run, "Flattening maker position");
+            if !dry_run && lots > 0 {
+                let resp = block_on(venue.maker.place_reduce_only_market_order(market, is_buy, lots));
+                if let Err(e) = resp {
+                    tracing::error!(market = market.name, "Could not flatten maker position: {}", e);
+                }
+            }
+        }
+        Ok(None) => tracing::info!(market = market.name, "Maker position is flat"),
+        Err(e) => tracing::error!(market = market.name, "Could not fetch maker position, not flattening: {}", e),
+    }
+
+    match block_on(venue.taker.get_position(market)).map(flatten_order) {
+        Ok(Some((is_buy, quantity))) => {
+            tracing::info!(market = market.name, is_buy, quantity = %quantity, dry_run, "Flattening taker position");
+            if !dry_run {
+                let resp = block_on(venue.taker.place_reduce_only_market_order(market, is_buy, Qty::from(quantity)));
+                if let Err(e) = resp {
+                    tracing::error!(market = market.name, "Could not flatten taker position: {}", e);
+                }
+            }
+        }
+        Ok(None) => tracing::info!(market = market.name, "Taker position is flat"),
+        Err(e) => tracing::error!(market = market.name, "Could not fetch taker position, not flattening: {}", e),
+    }
+}
+
+/**
+ * Side and size of the order closing a signed position, None when already flat
+ */
+fn flatten_order(position: Decimal) -> Option<(bool, Decimal)> {
+    if position.is_zero() {
+        return None;
+    }
+    return Some((position.is_sign_negative(), position.abs()));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_flatten_order() {
+        assert_eq!(flatten_order(Decimal::ZERO), None);
+        assert_eq!(flatten_order(Decimal::from_str("0.5").unwrap()), Some((false, Decimal::from_str("0.5").unwrap())));
+        assert_eq!(flatten_order(Decimal::from_str("-2").unwrap()), Some((true, Decimal::from(2))));
+    }
+}
@@ -0,0 +1,49 @@
+use std::process;
+use std::sync::mpsc::Sender;
+
+use tokio::signal::unix::{signal, SignalKind};
+
+use crate::clients::runtime::runtime;
+use crate::runner::shutdown::ShutdownReason;
+use crate::runner::supervisor::SupervisorEvent;
+
+// Requests the main thread acts on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProcessSignal {
+    Reload,
+    Shutdown(ShutdownReason),
+}
+
+/**
+ * Listens for SIGHUP, SIGINT and SIGTERM on the shared venue runtime. A second
+ * SIGINT or SIGTERM while shutting down exits right away
+ */
+pub fn listen(tx_supervisor: Sender<SupervisorEvent>) {
+    runtime().spawn(async move {
+        let mut hangup = signal(SignalKind::hangup()).expect("Could not listen for SIGHUP");
+        let mut interrupt = signal(SignalKind::interrupt()).expect("Could not listen for SIGINT");
+        let mut terminate = signal(SignalKind::terminate()).expect("Could not listen for SIGTERM");
+        let mut shutting_down = false;
+
+        loop {
+            let received = tokio::select! {
+                _ = hangup.recv() => ProcessSignal::Reload,
+                _ = interrupt.recv() => ProcessSignal::Shutdown(ShutdownReason::Interrupt),
+                _ = terminate.recv() => ProcessSignal::Shutdown(ShutdownReason::Terminate),
+            };
+
+            if let ProcessSignal::Shutdown(reason) = received {
+                if shutting_down {
+                    tracing::warn!("Received {:?} while shutting down, exiting now", reason);
+                    process::exit(1);
+                }
+                shutting_down = true;
+            }
+
+            tracing::info!("Received {:?}", received);
+            if tx_supervisor.send(SupervisorEvent::Signal(received)).is_err() {
+                return;
+            }
+        }
+    });
+}
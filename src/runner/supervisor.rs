@@ -0,0 +1,360 @@
+use std::collections::HashMap;
+use std::panic;
+use std::panic::AssertUnwindSafe;
+use std::sync::mpsc;
+use std::sync::mpsc::{Receiver, Sender};
+use std::sync::Arc;
+use std::thread;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use crate::clients::runtime::block_on;
+use crate::config::app_config::{AppConfig, CONFIG_PATH};
+use crate::runner::market_runner::{apply_diff, spawn_market, MarketHandle};
+use crate::runner::shutdown;
+use crate::runner::shutdown::ShutdownReason;
+use crate::runner::signals::ProcessSignal;
+use crate::statistics::account_stats::AccountStatsEvent;
+use crate::venues::venue::create_maker_venue;
+
+static INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+static MAX_BACKOFF: Duration = Duration::from_secs(60);
+// a market that ran this long before failing starts over from the initial backoff
+static STABLE_PERIOD: Duration = Duration::from_secs(300);
+
+// Every input the supervisor reacts to, multiplexed onto a single channel
+#[derive(Debug)]
+pub enum SupervisorEvent {
+    Signal(ProcessSignal),
+    WorkerExited(WorkerExit),
+    RestartDue(String),
+}
+
+#[derive(Debug)]
+pub struct WorkerExit {
+    pub market: String,
+    pub worker: &'static str,
+    // id of the market instance, exits of a stopped instance are ignored
+    pub instance: u64,
+    pub panicked: bool,
+}
+
+/**
+ * Spawns a named worker thread of a market. The supervisor is told once the
+ * worker returns or panics
+ */
+pub fn spawn_worker<F>(
+    market: &str,
+    worker: &'static str,
+    instance: u64,
+    tx_supervisor: &Sender<SupervisorEvent>,
+    f: F,
+) -> JoinHandle<()>
+where
+    F: FnOnce() + Send + 'static,
+{
+    let market = market.to_string();
+    let tx_supervisor = tx_supervisor.clone();
+
+    return thread::Builder::new()
+        .name(format!("{}-{}", market, worker))
+        .spawn(move || {
+            let panicked = panic::catch_unwind(AssertUnwindSafe(f)).is_err();
+            let _ = tx_supervisor.send(SupervisorEvent::WorkerExited(WorkerExit {
+                market,
+                worker,
+                instance,
+                panicked,
+            }));
+        })
+        .expect("Could not spawn worker thread");
+}
+
+// Exponential restart delay of a single market
+#[derive(Debug)]
+struct Backoff {
+    delay: Duration,
+    started: Instant,
+}
+
+impl Backoff {
+    fn new() -> Backoff {
+        return Backoff {
+            delay: INITIAL_BACKOFF,
+            started: Instant::now(),
+        };
+    }
+
+    /**
+     * Delay before the next restart, doubled on every consecutive failure
+     */
+    fn next_delay(&mut self) -> Duration {
+        if self.started.elapsed() >= STABLE_PERIOD {
+            self.delay = INITIAL_BACKOFF;
+        }
+        let delay = self.delay;
+        self.delay = (self.delay * 2).min(MAX_BACKOFF);
+        return delay;
+    }
+
+    fn restarted(&mut self) {
+        self.started = Instant::now();
+    }
+}
+
+/**
+ * Owns the running markets. Restarts a market whose worker exited, applies
+ * config reloads and hands the markets over to the shutdown on SIGINT/SIGTERM
+ */
+pub struct Supervisor {
+    config: Arc<AppConfig>,
+    markets: Vec<MarketHandle>,
+    backoffs: HashMap<String, Backoff>,
+    tx_account_stats: Sender<AccountStatsEvent>,
+    tx_event: Sender<SupervisorEvent>,
+    rx_event: Receiver<SupervisorEvent>,
+}
+
+impl Supervisor {
+    pub fn new(config: Arc<AppConfig>, tx_account_stats: Sender<AccountStatsEvent>) -> Supervisor {
+        let (tx_event, rx_event) = mpsc::channel();
+
+        return Supervisor {
+            config,
+            markets: Vec::new(),
+            backoffs: HashMap::new(),
+            tx_account_stats,
+            tx_event,
+            rx_event,
+        };
+    }
+
+    pub fn event_sender(&self) -> Sender<SupervisorEvent> {
+        return self.tx_event.clone();
+    }
+
+    /**
+     * Starts every configured market and supervises them until a shutdown is requested
+     */
+    pub fn run(&mut self) -> ShutdownReason {
+        for market in self.config.markets.clone() {
+            self.start_market(&market.name);
+        }
+
+        loop {
+            let event = self
+                .rx_event
+                .recv()
+                .expect("Supervisor event channel has disconnected!");
+
+            match event {
+                SupervisorEvent::Signal(ProcessSignal::Shutdown(reason)) => return reason,
+                SupervisorEvent::Signal(ProcessSignal::Reload) => self.reload(CONFIG_PATH),
+                SupervisorEvent::WorkerExited(exit) => self.on_worker_exit(exit),
+                SupervisorEvent::RestartDue(market) => {
+                    if self.markets.iter().any(|handle| handle.market.name == market) {
+                        continue;
+                    }
+                    self.start_market(&market);
+                }
+            }
+        }
+    }
+
+    pub fn shutdown(self, reason: ShutdownReason) {
+        shutdown::shutdown(self.markets, &self.config, reason);
+    }
+
+    fn start_market(&mut self, name: &str) {
+        let market = match self.config.market(name) {
+            Some(market) => market.clone(),
+            None => {
+                tracing::info!(market = name, "Market was removed from the config, not restarting");
+                self.backoffs.remove(name);
+                return;
+            }
+        };
+
+        let spawned = panic::catch_unwind(AssertUnwindSafe(|| {
+            spawn_market(market, self.config.clone(), &self.tx_account_stats, &self.tx_event)
+        }));
+
+        match spawned {
+            Ok(Ok(handle)) => {
+                self.backoffs.entry(name.to_string()).or_insert_with(Backoff::new).restarted();
+                self.markets.push(handle);
+            }
+            Ok(Err(e)) => {
+                tracing::error!(market = name, "Could not start market: {}", e);
+                self.schedule_restart(name);
+            }
+            Err(_) => {
+                tracing::error!(market = name, "Could not start market");
+                self.schedule_restart(name);
+            }
+        }
+    }
+
+    fn on_worker_exit(&mut self, exit: WorkerExit) {
+        let position = self
+            .markets
+            .iter()
+            .position(|handle| handle.market.name == exit.market && handle.instance == exit.instance);
+
+        // the market got stopped on purpose
+        let Some(position) = position else {
+            return;
+        };
+
+        tracing::error!(
+            market = exit.market,
+            worker = exit.worker,
+            panicked = exit.panicked,
+            "Worker exited, restarting market"
+        );
+
+        let handle = self.markets.remove(position);
+        let market = handle.market.clone();
+        handle.shutdown();
+
+        // the market maker might be gone already, pull the quotes directly
+        let params = self.config.market_params(&market);
+        let maker = create_maker_venue(market.venues.maker, &self.config.vars, &params);
+        if let Err(e) = block_on(maker.cancel_all_orders(&market)) {
+            tracing::error!(market = market.name, "Could not cancel orders of crashed market: {}", e);
+        }
+
+        self.schedule_restart(&market.name);
+    }
+
+    fn schedule_restart(&mut self, name: &str) {
+        let delay = self
+            .backoffs
+            .entry(name.to_string())
+            .or_insert_with(Backoff::new)
+            .next_delay();
+
+        tracing::info!(market = name, "Restarting market in {:?}", delay);
+
+        let tx_event = self.tx_event.clone();
+        let name = name.to_string();
+        thread::spawn(move || {
+            thread::sleep(delay);
+            let _ = tx_event.send(SupervisorEvent::RestartDue(name));
+        });
+    }
+
+    /**
+     * config.json is re-read on SIGHUP, markets are updated, added or removed in place.
+     * An invalid config is refused as a whole and the running one kept
+     */
+    fn reload(&mut self, config_path: &str) {
+        let new_config = match self.config.reload(config_path) {
+            Ok(new_config) => Arc::new(new_config),
+            Err(e) => {
+                tracing::error!("Ignoring config reload, invalid configuration: {}", e);
+                return;
+            }
+        };
+
+        let diff = self.config.diff(&new_config);
+        if diff.is_empty() {
+            tracing::info!("Config reloaded, nothing changed");
+            return;
+        }
+
+        tracing::info!(
+            added = ?diff.added.iter().map(|market| &market.name).collect::<Vec<_>>(),
+            removed = ?diff.removed,
+            restarted = ?diff.restarted.iter().map(|market| &market.name).collect::<Vec<_>>(),
+            updated = ?diff.updated.iter().map(|market| &market.name).collect::<Vec<_>>(),
+            "Applying reloaded config"
+        );
+        let started = apply_diff(&mut self.markets, diff, new_config.clone());
+        self.config = new_config;
+        for market in started {
+            tracing::info!(market = market.name, "Starting market");
+            self.start_market(&market.name);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::env::EnvVars;
+    use crate::models::common::Config;
+    use std::fs;
+
+    fn config_json(maker: &str) -> String {
+        return format!(
+            r#"{{
+                "circuit_breaker_config": {{"num_retries": 3, "failure_threshold": 3, "loss_threshold_bps": 3.0}},
+                "markets": [{{
+                    "name": "eth", "mm_lot_upper_bound": 10, "lot_size": 100, "min_size": "0.01",
+                    "price_precision": 2, "skewing_coefficient": 0.1,
+                    "symbols": {{"binance": "ethusdt", "kucoin": "ETHUSDTM", "bluefin": "ETH-PERP"}},
+                    "venues": {{"maker": "{}", "taker": "bluefin", "reference": "binance"}}
+                }}]
+            }}"#,
+            maker
+        );
+    }
+
+    #[test]
+    fn test_reload_refuses_unsupported_venue_roles() {
+        let config: Config = serde_json::from_str(&config_json("kucoin")).unwrap();
+        let config = Arc::new(AppConfig::new(EnvVars::for_tests(), config).unwrap());
+        let (tx_account_stats, _rx_account_stats) = mpsc::channel();
+        let mut supervisor = Supervisor::new(config.clone(), tx_account_stats);
+
+        let path = std::env::temp_dir().join(format!("chita-bot-reload-{}.json", std::process::id()));
+        fs::write(&path, config_json("binance")).unwrap();
+        supervisor.reload(path.to_str().unwrap());
+        let _ = fs::remove_file(&path);
+
+        // the binance maker would panic on spawn, the running config is kept instead
+        assert!(Arc::ptr_eq(&supervisor.config, &config));
+        assert!(supervisor.markets.is_empty());
+    }
+
+    #[test]
+    fn test_backoff_doubles_up_to_max() {
+        let mut backoff = Backoff::new();
+
+        assert_eq!(backoff.next_delay(), Duration::from_secs(1));
+        assert_eq!(backoff.next_delay(), Duration::from_secs(2));
+        assert_eq!(backoff.next_delay(), Duration::from_secs(4));
+        for _ in 0..10 {
+            backoff.next_delay();
+        }
+        assert_eq!(backoff.next_delay(), MAX_BACKOFF);
+    }
+
+    #[test]
+    fn test_backoff_resets_after_stable_period() {
+        let mut backoff = Backoff::new();
+        backoff.next_delay();
+        backoff.next_delay();
+
+        backoff.started = Instant::now() - STABLE_PERIOD;
+        assert_eq!(backoff.next_delay(), INITIAL_BACKOFF);
+    }
+
+    #[test]
+    fn test_worker_exit_is_reported() {
+        let (tx, rx) = mpsc::channel();
+
+        spawn_worker("ETH-PERP", "panicking", 7, &tx, || panic!("worker failed"));
+
+        match rx.recv_timeout(Duration::from_secs(1)).unwrap() {
+            SupervisorEvent::WorkerExited(exit) => {
+                assert_eq!(exit.market, "ETH-PERP");
+                assert_eq!(exit.worker, "panicking");
+                assert_eq!(exit.instance, 7);
+                assert!(exit.panicked);
+            }
+            other => panic!("unexpected event {:?}", other),
+        }
+    }
+}
@@ -1,8 +1,8 @@
+pub mod binance_book;
 pub mod binance_ob_socket;
-pub mod kucoin_socket;
+pub mod kucoin_book;
+pub mod kucoin_session;
 pub mod bluefin_ob_socket;
-pub mod bluefin_private_socket;
-pub mod kucoin_ticker_socket;
-pub mod kucoin_utils;
+pub mod bluefin_session;
 pub mod common;
-
+pub mod connection;
@@ -0,0 +1,186 @@
+use crate::binance::BinanceClient;
+use crate::clients::runtime::runtime;
+use crate::models::binance_models::{DepthSnapshot, DepthUpdate};
+use crate::models::order_book::OrderBook;
+use crate::sockets::binance_ob_socket::BinanceOrderBookStream;
+use crate::sockets::common::{BookError, BookForwarder};
+use crate::sockets::connection;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc::unbounded_channel;
+
+// pause before requesting the snapshot again after a failed request
+static SNAPSHOT_RETRY_DELAY: Duration = Duration::from_secs(1);
+
+/**
+ * Full depth Binance futures book, built from a REST snapshot and the `@depth` diffs
+ * following Binance's procedure: diffs older than the snapshot are dropped, the first
+ * applied diff has to span the snapshot's lastUpdateId and every later diff has to
+ * continue where the previous one ended
+ */
+#[derive(Debug)]
+pub struct DepthBook {
+    last_update_id: u64,
+    // whether a diff was applied on top of the snapshot yet
+    synced: bool,
+    book: OrderBook,
+}
+
+impl DepthBook {
+    pub fn from_snapshot(snapshot: DepthSnapshot) -> DepthBook {
+        return DepthBook {
+            last_update_id: snapshot.last_update_id,
+            synced: false,
+            book: OrderBook::from_levels(snapshot.asks, snapshot.bids),
+        };
+    }
+
+    /**
+     * Applies the next diff. Returns false for diffs the snapshot already contains,
+     * a broken chain of update ids means the book has to be rebuilt
+     */
+    pub fn apply(&mut self, update: &DepthUpdate) -> Result<bool, BookError> {
+        if !self.synced {
+            if update.u2_id < self.last_update_id {
+                return Ok(false);
+            }
+            // the snapshot is older than the first buffered diff
+            if update.u_id > self.last_update_id {
+                return Err(BookError::SequenceGap {
+                    expected: self.last_update_id,
+                    received: update.u_id,
+                });
+            }
+            self.synced = true;
+        } else if update.pu_id != self.last_update_id {
+            return Err(BookError::SequenceGap {
+                expected: self.last_update_id,
+                received: update.pu_id,
+            });
+        }
+
+        for (is_bid, levels) in [(false, &update.ask_orders), (true, &update.bid_orders)] {
+            for &(price, size) in levels {
+                self.book.set_level(is_bid, price, size);
+            }
+        }
+        self.last_update_id = update.u2_id;
+        return Ok(true);
+    }
+
+    pub fn to_order_book(&self, depth: usize) -> OrderBook {
+        return self.book.truncated(depth);
+    }
+}
+
+/**
+ * Keeps a local book of `symbol` from the diff stream at `url` and forwards its best
+ * `depth` levels after every diff. Diffs are buffered while the snapshot is fetched,
+ * a broken update chain, e.g. after a reconnect, fetches a new snapshot. Returns once
+ * the receivers are gone
+ */
+pub async fn stream_binance_book(
+    client: Arc<BinanceClient>,
+    url: String,
+    symbol: String,
+    depth: usize,
+    mut forwarder: BookForwarder,
+) {
+    let (tx_updates, mut rx_updates) = unbounded_channel();
+    runtime().spawn(connection::run(BinanceOrderBookStream::new(&url), move |msg: &str| {
+        return match serde_json::from_str::<DepthUpdate>(msg) {
+            Ok(update) => tx_updates.send(update).is_ok(),
+            Err(e) => {
+                tracing::warn!("Could not parse binance depth update {}: {}", msg, e);
+                true
+            }
+        };
+    }));
+
+    loop {
+        let mut book = match client.get_depth_snapshot(&symbol).await {
+            Ok(snapshot) => DepthBook::from_snapshot(snapshot),
+            Err(e) => {
+                tracing::warn!(market = symbol, "Could not fetch binance book snapshot: {}", e);
+                tokio::time::sleep(SNAPSHOT_RETRY_DELAY).await;
+                continue;
+            }
+        };
+        tracing::info!(market = symbol, last_update_id = book.last_update_id, "Binance book seeded from snapshot");
+
+        // dropping the receiver closes the socket
+        while let Some(update) = rx_updates.recv().await {
+            match book.apply(&update) {
+                Ok(true) => {
+                    let exchange_time = Some(update.event_time as u128);
+                    if !forwarder.forward(book.to_order_book(depth).stamped(exchange_time)) {
+                        return;
+                    }
+                }
+                Ok(false) => {}
+                Err(e) => {
+                    tracing::warn!(market = symbol, "Binance book out of sync, resyncing: {}", e);
+                    break;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::units::{Price, Qty};
+    use std::str::FromStr;
+
+    fn level(price: &str, size: &str) -> (Price, Qty) {
+        return (Price::from_str(price).unwrap(), Qty::from_str(size).unwrap());
+    }
+
+    fn update(first: u64, last: u64, previous: u64, bids: &str, asks: &str) -> DepthUpdate {
+        let json = format!(
+            r#"{{"e":"depthUpdate","E":1,"T":1,"s":"BTCUSDT","U":{},"u":{},"pu":{},"b":{},"a":{}}}"#,
+            first, last, previous, bids, asks
+        );
+        return serde_json::from_str(&json).expect("Could not parse depth update");
+    }
+
+    fn book() -> DepthBook {
+        let snapshot: DepthSnapshot = serde_json::from_str(
+            r#"{"lastUpdateId":100,"E":1,"T":1,"bids":[["99.9","2"],["99.8","1"]],"asks":[["100.1","3"],["100.2","4"]]}"#,
+        )
+        .expect("Could not parse snapshot");
+        return DepthBook::from_snapshot(snapshot);
+    }
+
+    #[test]
+    fn test_follows_the_snapshot_with_chained_diffs() {
+        let mut book = book();
+
+        // already part of the snapshot
+        assert_eq!(book.apply(&update(90, 99, 89, "[]", r#"[["100.1","0"]]"#)), Ok(false));
+        // spans lastUpdateId
+        assert_eq!(book.apply(&update(95, 105, 94, r#"[["99.95","5"]]"#, r#"[["100.1","0"]]"#)), Ok(true));
+        assert_eq!(book.apply(&update(106, 110, 105, r#"[["99.8","0"]]"#, "[]")), Ok(true));
+
+        let ob = book.to_order_book(5);
+        assert_eq!(ob.asks().collect::<Vec<_>>(), vec![level("100.2", "4")]);
+        assert_eq!(ob.bids().collect::<Vec<_>>(), vec![level("99.95", "5"), level("99.9", "2")]);
+    }
+
+    #[test]
+    fn test_broken_chain_needs_resync() {
+        let mut stale = book();
+        assert_eq!(
+            stale.apply(&update(102, 105, 101, "[]", "[]")),
+            Err(BookError::SequenceGap { expected: 100, received: 102 })
+        );
+
+        let mut book = book();
+        assert_eq!(book.apply(&update(100, 105, 99, "[]", "[]")), Ok(true));
+        assert_eq!(
+            book.apply(&update(108, 110, 107, "[]", "[]")),
+            Err(BookError::SequenceGap { expected: 105, received: 107 })
+        );
+    }
+}
@@ -0,0 +1,190 @@
+use crate::bluefin::BluefinPrivateEvent;
+use crate::clients::error::VenueError;
+use crate::clients::runtime::runtime;
+use crate::sockets::connection::{self, ConnectionError, Endpoint, Protocol};
+use async_trait::async_trait;
+use serde_json::json;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use tokio::task::JoinHandle;
+use tokio_tungstenite::tungstenite::protocol::Message;
+
+// userUpdates room of the authenticated account
+struct BluefinPrivateProtocol {
+    url: String,
+    auth_token: String,
+}
+
+#[async_trait]
+impl Protocol for BluefinPrivateProtocol {
+    fn name(&self) -> String {
+        return "Bluefin userUpdates".to_string();
+    }
+
+    async fn endpoint(&mut self) -> Result<Endpoint, VenueError> {
+        return Ok(Endpoint::new(&self.url));
+    }
+
+    fn subscriptions(&mut self) -> Vec<Message> {
+        let sub_message = json!([
+            "SUBSCRIBE",
+            [
+                {
+                    "e": "userUpdates",
+                    "t": self.auth_token
+                }
+            ]
+        ]);
+        return vec![Message::Text(sub_message.to_string())];
+    }
+
+    fn is_data(&mut self, _text: &str) -> Result<bool, ConnectionError> {
+        return Ok(true);
+    }
+}
+
+type Handler = Box<dyn FnMut(&BluefinPrivateEvent) -> bool + Send>;
+
+struct Subscriber {
+    // None receives the events of every market and the account wide ones
+    market: Option<String>,
+    handler: Handler,
+}
+
+/**
+ * Hands the event to the subscribers of its market, subscribers whose handler returns
+ * false are dropped
+ */
+fn route(subscribers: &mut Vec<Subscriber>, event: &BluefinPrivateEvent) {
+    subscribers.retain_mut(|subscriber| match &subscriber.market {
+        Some(market) if Some(market.as_str()) != event.symbol() => true,
+        _ => (subscriber.handler)(event),
+    });
+}
+
+// a handler that panicked leaves the subscribers consistent, the others keep being served
+fn lock(subscribers: &Mutex<Vec<Subscriber>>) -> MutexGuard<'_, Vec<Subscriber>> {
+    return subscribers.lock().unwrap_or_else(PoisonError::into_inner);
+}
+
+/**
+ * The single userUpdates connection of the process. Every frame is parsed once into a
+ * `BluefinPrivateEvent` and fanned out to the subscribers interested in its market
+ */
+pub struct BluefinSession {
+    url: String,
+    auth_token: String,
+    subscribers: Arc<Mutex<Vec<Subscriber>>>,
+    connection: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl BluefinSession {
+    pub fn new(url: &str, auth_token: &str) -> BluefinSession {
+        return BluefinSession {
+            url: url.to_string(),
+            auth_token: auth_token.to_string(),
+            subscribers: Arc::new(Mutex::new(Vec::new())),
+            connection: Mutex::new(None),
+        };
+    }
+
+    /**
+     * Hands every event of `market`, or of all markets if None, to `handler`. Handlers
+     * ignore the event kinds they do not use and return false once their receiver is gone.
+     * The connection is opened with the first subscriber
+     */
+    pub fn subscribe<H>(&self, market: Option<&str>, handler: H)
+    where
+        H: FnMut(&BluefinPrivateEvent) -> bool + Send + 'static,
+    {
+        lock(&self.subscribers).push(Subscriber {
+            market: market.map(str::to_string),
+            handler: Box::new(handler),
+        });
+
+        let mut connection = self.connection.lock().expect("Bluefin session poisoned");
+        // a panic on a malformed event ends the task, the next subscriber opens the connection
+        // again for everyone subscribed so far
+        let running = connection.as_ref().is_some_and(|task| !task.is_finished());
+        if !running {
+            *connection = Some(self.open());
+        }
+    }
+
+    fn open(&self) -> JoinHandle<()> {
+        let protocol = BluefinPrivateProtocol {
+            url: self.url.clone(),
+            auth_token: self.auth_token.clone(),
+        };
+        let subscribers = self.subscribers.clone();
+        return runtime().spawn(connection::run(protocol, move |text: &str| {
+            if let Some(event) = BluefinPrivateEvent::parse(text) {
+                route(&mut lock(&subscribers), &event);
+            }
+            // the session lives as long as the process
+            return true;
+        }));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bluefin::models::AccountData;
+    use std::sync::mpsc;
+
+    fn subscriber(market: Option<&str>, tx: mpsc::Sender<Option<String>>) -> Subscriber {
+        return Subscriber {
+            market: market.map(str::to_string),
+            handler: Box::new(move |event: &BluefinPrivateEvent| tx.send(event.symbol().map(str::to_string)).is_ok()),
+        };
+    }
+
+    #[test]
+    fn test_routes_events_by_market() {
+        let trade = r#"{"eventName":"UserTrade","data":{"trade":{"symbol":"ETH-PERP","commission":"1","realizedPnl":"-2"}}}"#;
+        let event = BluefinPrivateEvent::parse(trade).expect("Could not parse the trade");
+        let (eth_tx, eth_rx) = mpsc::channel();
+        let (btc_tx, btc_rx) = mpsc::channel();
+        let (all_tx, all_rx) = mpsc::channel();
+        let mut subscribers = vec![
+            subscriber(Some("ETH-PERP"), eth_tx),
+            subscriber(Some("BTC-PERP"), btc_tx),
+            subscriber(None, all_tx),
+        ];
+
+        route(&mut subscribers, &event);
+        assert_eq!(eth_rx.try_iter().collect::<Vec<_>>(), vec![Some("ETH-PERP".to_string())]);
+        assert_eq!(btc_rx.try_iter().count(), 0);
+        assert_eq!(all_rx.try_iter().count(), 1);
+
+        // account wide events only reach subscribers of every market
+        let account = BluefinPrivateEvent::AccountDataUpdate(AccountData {
+            address: String::new(),
+            can_trade: true,
+            update_time: 0,
+            fee_tier: String::new(),
+            wallet_balance: 0.0,
+            total_position_qty_reduced: 0.0,
+            total_position_qty_reducible: 0.0,
+            total_position_margin: 0.0,
+            total_unrealized_profit: 0.0,
+            total_expected_pnl: 0.0,
+            free_collateral: 0.0,
+            account_value: 10.0,
+            account_data_by_market: Vec::new(),
+        });
+        route(&mut subscribers, &account);
+        assert_eq!(eth_rx.try_iter().count(), 0);
+        assert_eq!(all_rx.try_iter().collect::<Vec<_>>(), vec![None]);
+
+        drop(eth_rx);
+        route(&mut subscribers, &event);
+        assert_eq!(subscribers.len(), 2);
+    }
+
+    #[test]
+    fn test_ignores_unused_events() {
+        assert!(BluefinPrivateEvent::parse(r#"{"eventName":"OrderCancelled","data":{}}"#).is_none());
+        assert!(BluefinPrivateEvent::parse("not json").is_none());
+    }
+}
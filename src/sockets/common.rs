@@ -1,88 +1,106 @@
-use crate::env;
-use crate::env::EnvVars;
-use crate::models::common::OrderBook;
+use crate::models::order_book::OrderBook;
+use crate::models::units::Price;
+use crate::sockets::connection::{self, Protocol};
+use crate::utils::FeedSender;
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
 use serde::de::DeserializeOwned;
-use std::net::TcpStream;
-use std::sync::mpsc::Sender;
-use tungstenite::protocol::Message;
-use tungstenite::stream::MaybeTlsStream;
+use thiserror::Error;
+use tokio::net::TcpStream;
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
 
-pub trait OrderBookStream<T>
-where
-    T: DeserializeOwned + Into<OrderBook>,
-{
-    fn get_ob_socket(
-        &self,
-        url: &str,
-        market: &str,
-    ) -> tungstenite::WebSocket<MaybeTlsStream<TcpStream>>;
-    fn stream_ob_socket(
-        &self,
-        url: &str,
-        market: &str,
-        tx: Sender<OrderBook>,
-        tx_diff: Sender<OrderBook>,
-    ) {
-        let vars: EnvVars = env::env_variables();
-        let mut socket = self.get_ob_socket(url, market);
-        let mut last_first_ask_price: Option<f64> = None;
-        let mut last_first_bid_price: Option<f64> = None;
+// websocket every venue stream reads from on the shared runtime
+pub type WsStream = WebSocketStream<MaybeTlsStream<TcpStream>>;
 
-        loop {
-            let read = socket.read();
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum BookError {
+    #[error("expected sequence {expected}, received {received}")]
+    SequenceGap { expected: u64, received: u64 },
+    #[error("malformed book update {0:?}")]
+    Malformed(String),
+}
 
-            match read {
-                Ok(message) => {
-                    match message {
-                        Message::Text(msg) => {
-                            let parsed: T = serde_json::from_str(&msg).expect("Can't parse");
-                            let ob: OrderBook = parsed.into();
+/**
+ * Sends every book to `tx` and the books whose best bid or ask moved by at least
+ * `trigger_bps` to `tx_diff`
+ */
+pub struct BookForwarder {
+    market: String,
+    trigger_bps: f64,
+    tx: FeedSender<OrderBook>,
+    tx_diff: FeedSender<OrderBook>,
+    last_first_ask_price: Option<Price>,
+    last_first_bid_price: Option<Price>,
+}
 
-                            let current_first_ask_price = ob.asks.first().map(|ask| ask.0.clone());
-                            let current_first_bid_price = ob.bids.first().map(|bid| bid.0.clone());
+impl BookForwarder {
+    pub fn new(market: &str, trigger_bps: f64, tx: FeedSender<OrderBook>, tx_diff: FeedSender<OrderBook>) -> BookForwarder {
+        return BookForwarder {
+            market: market.to_string(),
+            trigger_bps,
+            tx,
+            tx_diff,
+            last_first_ask_price: None,
+            last_first_bid_price: None,
+        };
+    }
 
-                            let is_first_ask_price_changed =
-                                match (current_first_ask_price, last_first_ask_price) {
-                                    (Some(current), Some(last)) => {
-                                        (current - last).abs() / last * 10000.0
-                                            >= vars.market_making_trigger_bps
-                                    }
-                                    _ => false, // Consider unchanged if either current or last price is None
-                                };
+    // returns false once the receivers are gone
+    pub fn forward(&mut self, ob: OrderBook) -> bool {
+        let current_first_ask_price = ob.best_ask().map(|ask| ask.0);
+        let current_first_bid_price = ob.best_bid().map(|bid| bid.0);
 
-                            let is_first_bid_price_changed =
-                                match (current_first_bid_price, last_first_bid_price) {
-                                    (Some(current), Some(last)) => {
-                                        (current - last).abs() / last * 10000.0
-                                            >= vars.market_making_trigger_bps
-                                    }
-                                    _ => false, // Consider unchanged if either current or last price is None
-                                };
+        let moved = |current: Option<Price>, last: Option<Price>| match (current, last) {
+            (Some(current), Some(last)) => match (current - last).abs().value().checked_div(last.value()) {
+                Some(ratio) => (ratio * Decimal::from(10000)).to_f64().unwrap_or_default() >= self.trigger_bps,
+                // a book without a price before
+                None => current != last,
+            },
+            _ => false, // Consider unchanged if either current or last price is None
+        };
+        let is_first_ask_price_changed = moved(current_first_ask_price, self.last_first_ask_price);
+        let is_first_bid_price_changed = moved(current_first_bid_price, self.last_first_bid_price);
 
-                            // Update the last known prices
-                            last_first_ask_price = current_first_ask_price;
-                            last_first_bid_price = current_first_bid_price;
+        // Update the last known prices
+        self.last_first_ask_price = current_first_ask_price;
+        self.last_first_bid_price = current_first_bid_price;
 
-                            if is_first_ask_price_changed || is_first_bid_price_changed {
-                                // Send the order book through the channel
-                                tx_diff.send(ob.clone()).unwrap();
-                            }
-                            tx.send(ob).unwrap();
-                        }
-                        Message::Ping(ping_data) => {
-                            // Handle the Ping message, e.g., by sending a Pong response
-                            socket.write(Message::Pong(ping_data)).unwrap();
-                        }
-                        other => {
-                            tracing::error!("Error: Received unexpected message type: {:?}", other);
-                        }
-                    }
-                }
-                Err(e) => {
-                    tracing::error!("Error during message handling: {:?}", e);
-                    socket = self.get_ob_socket(url, market);
-                }
-            }
+        if (is_first_ask_price_changed || is_first_bid_price_changed) && self.tx_diff.send(ob.clone()).is_err() {
+            tracing::info!("Order book receiver is gone, closing stream for {}", self.market);
+            return false;
         }
+        if self.tx.send(ob).is_err() {
+            tracing::info!("Order book receiver is gone, closing stream for {}", self.market);
+            return false;
+        }
+        return true;
     }
 }
+
+/**
+ * Streams the order books of `protocol` decoded as `T`. Every book goes to `tx`, books
+ * whose best bid or ask moved by at least `trigger_bps` also go to `tx_diff`
+ */
+pub async fn stream_order_book<T, P>(
+    protocol: P,
+    market: &str,
+    trigger_bps: f64,
+    tx: FeedSender<OrderBook>,
+    tx_diff: FeedSender<OrderBook>,
+) where
+    T: DeserializeOwned + Into<OrderBook>,
+    P: Protocol,
+{
+    let mut forwarder = BookForwarder::new(market, trigger_bps, tx, tx_diff);
+    connection::run(protocol, |msg: &str| {
+        // acks and error events share the socket with the books, they are skipped
+        return match serde_json::from_str::<T>(msg) {
+            Ok(parsed) => forwarder.forward(parsed.into()),
+            Err(e) => {
+                tracing::warn!("Could not parse order book of {} {}: {}", forwarder.market, msg, e);
+                true
+            }
+        };
+    })
+    .await;
+}
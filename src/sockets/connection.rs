@@ -0,0 +1,267 @@
+use crate::clients::error::VenueError;
+use crate::sockets::common::WsStream;
+use async_trait::async_trait;
+use futures_util::{SinkExt, StreamExt};
+use rand::Rng;
+use std::time::{Duration, Instant};
+use thiserror::Error;
+use tokio_tungstenite::connect_async;
+use tokio_tungstenite::tungstenite;
+use tokio_tungstenite::tungstenite::protocol::Message;
+
+static INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+static MAX_BACKOFF: Duration = Duration::from_secs(30);
+// a connection that stayed up this long starts over from the initial backoff
+static STABLE_CONNECTION: Duration = Duration::from_secs(60);
+static DEFAULT_PING_INTERVAL: Duration = Duration::from_secs(20);
+static DEFAULT_PING_TIMEOUT: Duration = Duration::from_secs(20);
+
+/**
+ * How often the connection is pinged, and how long it may stay silent after a ping
+ * before it is considered dead
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Heartbeat {
+    pub interval: Duration,
+    pub timeout: Duration,
+}
+
+impl Default for Heartbeat {
+    fn default() -> Heartbeat {
+        return Heartbeat {
+            interval: DEFAULT_PING_INTERVAL,
+            timeout: DEFAULT_PING_TIMEOUT,
+        };
+    }
+}
+
+// Where a connection goes, resolved again on every reconnect
+#[derive(Debug, Clone)]
+pub struct Endpoint {
+    pub url: String,
+    pub heartbeat: Heartbeat,
+}
+
+impl Endpoint {
+    pub fn new(url: &str) -> Endpoint {
+        return Endpoint {
+            url: url.to_string(),
+            heartbeat: Heartbeat::default(),
+        };
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum ConnectionError {
+    #[error("could not resolve the socket endpoint: {0}")]
+    Endpoint(#[from] VenueError),
+    #[error("websocket failure: {0}")]
+    Socket(Box<tungstenite::Error>),
+    #[error("invalid socket url {0}")]
+    Url(String),
+    #[error("no frame received for {0:?}")]
+    Silent(Duration),
+    #[error("socket closed by the venue")]
+    Closed,
+}
+
+impl From<tungstenite::Error> for ConnectionError {
+    fn from(error: tungstenite::Error) -> ConnectionError {
+        return ConnectionError::Socket(Box::new(error));
+    }
+}
+
+/**
+ * Venue specific part of a websocket stream. The connection manager owns the socket,
+ * the adapter only says where to connect, what to subscribe to and which text frames
+ * carry data
+ */
+#[async_trait]
+pub trait Protocol: Send {
+    // name of the stream in logs
+    fn name(&self) -> String;
+
+    async fn endpoint(&mut self) -> Result<Endpoint, VenueError>;
+
+    // frames the venue sends before it accepts subscriptions, read and dropped
+    fn welcome_frames(&self) -> usize {
+        return 0;
+    }
+
+    // sent on every (re)connect
+    fn subscriptions(&mut self) -> Vec<Message>;
+
+    // application level ping, venues answering websocket pings keep the default
+    fn ping(&mut self) -> Message {
+        return Message::Ping(Vec::new());
+    }
+
+    // frames to send on the live connection, such as subscriptions added after connecting
+    async fn outgoing(&mut self) -> Option<Message> {
+        return std::future::pending().await;
+    }
+
+    /**
+     * Whether a text frame carries data for the stream. Acks, pongs and welcome
+     * messages are dropped, an error restarts the connection
+     */
+    fn is_data(&mut self, text: &str) -> Result<bool, ConnectionError>;
+}
+
+/**
+ * Exponential reconnect delay, jittered between half and the full value so streams
+ * of the same venue do not reconnect in lockstep
+ */
+struct ReconnectBackoff {
+    delay: Duration,
+}
+
+impl ReconnectBackoff {
+    fn new() -> ReconnectBackoff {
+        return ReconnectBackoff {
+            delay: INITIAL_BACKOFF,
+        };
+    }
+
+    fn next_delay(&mut self, connected_for: Duration) -> Duration {
+        if connected_for >= STABLE_CONNECTION {
+            self.delay = INITIAL_BACKOFF;
+        }
+        let half = self.delay / 2;
+        let delay = half + half.mul_f64(rand::thread_rng().gen::<f64>());
+        self.delay = (self.delay * 2).min(MAX_BACKOFF);
+        return delay;
+    }
+}
+
+enum Exit {
+    ReceiverGone,
+    Disconnected(ConnectionError),
+}
+
+async fn connect<P: Protocol>(protocol: &mut P) -> Result<(WsStream, Heartbeat), ConnectionError> {
+    let endpoint = protocol.endpoint().await?;
+    let url = url::Url::parse(&endpoint.url).map_err(|_| ConnectionError::Url(endpoint.url.clone()))?;
+    let (mut socket, _response) = connect_async(url).await?;
+
+    for _ in 0..protocol.welcome_frames() {
+        socket.next().await.ok_or(ConnectionError::Closed)??;
+    }
+
+    for subscription in protocol.subscriptions() {
+        socket.send(subscription).await?;
+    }
+
+    tracing::info!("Connected {} stream", protocol.name());
+    return Ok((socket, endpoint.heartbeat));
+}
+
+/**
+ * Reads the socket until it fails, goes silent or the handler stops, answering
+ * pings and sending the protocol's heartbeat in between
+ */
+async fn pump<P, H>(protocol: &mut P, socket: &mut WsStream, heartbeat: Heartbeat, on_data: &mut H) -> Exit
+where
+    P: Protocol,
+    H: FnMut(&str) -> bool + Send,
+{
+    let mut pings = tokio::time::interval(heartbeat.interval);
+    // the first tick completes right away
+    pings.tick().await;
+    let mut last_frame = Instant::now();
+    let liveness = heartbeat.interval + heartbeat.timeout;
+
+    loop {
+        tokio::select! {
+            frame = socket.next() => {
+                last_frame = Instant::now();
+                match frame {
+                    Some(Ok(Message::Text(text))) => match protocol.is_data(&text) {
+                        Ok(true) => {
+                            if !on_data(&text) {
+                                return Exit::ReceiverGone;
+                            }
+                        }
+                        Ok(false) => {}
+                        Err(e) => return Exit::Disconnected(e),
+                    },
+                    Some(Ok(Message::Ping(data))) => {
+                        if let Err(e) = socket.send(Message::Pong(data)).await {
+                            return Exit::Disconnected(e.into());
+                        }
+                    }
+                    Some(Ok(Message::Close(_))) | None => return Exit::Disconnected(ConnectionError::Closed),
+                    Some(Ok(_)) => {}
+                    Some(Err(e)) => return Exit::Disconnected(e.into()),
+                }
+            }
+            Some(message) = protocol.outgoing() => {
+                if let Err(e) = socket.send(message).await {
+                    return Exit::Disconnected(e.into());
+                }
+            }
+            _ = pings.tick() => {
+                if last_frame.elapsed() >= liveness {
+                    return Exit::Disconnected(ConnectionError::Silent(last_frame.elapsed()));
+                }
+                if let Err(e) = socket.send(protocol.ping()).await {
+                    return Exit::Disconnected(e.into());
+                }
+            }
+        }
+    }
+}
+
+/**
+ * Keeps a stream connected until `on_data` returns false, which it does once the
+ * receiving side is gone. Every data frame is handed to `on_data`, failures reconnect
+ * with backoff and subscribe again
+ */
+pub async fn run<P, H>(mut protocol: P, mut on_data: H)
+where
+    P: Protocol,
+    H: FnMut(&str) -> bool + Send,
+{
+    let mut backoff = ReconnectBackoff::new();
+
+    loop {
+        let connected_at = Instant::now();
+        let error = match connect(&mut protocol).await {
+            Ok((mut socket, heartbeat)) => match pump(&mut protocol, &mut socket, heartbeat, &mut on_data).await {
+                Exit::ReceiverGone => {
+                    tracing::info!("{} receiver is gone, closing stream", protocol.name());
+                    let _ = socket.close(None).await;
+                    return;
+                }
+                Exit::Disconnected(e) => e,
+            },
+            Err(e) => e,
+        };
+
+        let delay = backoff.next_delay(connected_at.elapsed());
+        tracing::error!("{} stream failed, reconnecting in {:?}: {}", protocol.name(), delay, error);
+        tokio::time::sleep(delay).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff_grows_and_resets_after_stable_connection() {
+        let mut backoff = ReconnectBackoff::new();
+
+        let first = backoff.next_delay(Duration::ZERO);
+        assert!(first >= INITIAL_BACKOFF / 2 && first <= INITIAL_BACKOFF);
+        let second = backoff.next_delay(Duration::ZERO);
+        assert!(second >= INITIAL_BACKOFF && second <= INITIAL_BACKOFF * 2);
+
+        for _ in 0..20 {
+            assert!(backoff.next_delay(Duration::ZERO) <= MAX_BACKOFF);
+        }
+
+        let reset = backoff.next_delay(STABLE_CONNECTION);
+        assert!(reset <= INITIAL_BACKOFF);
+    }
+}
@@ -0,0 +1,174 @@
+use crate::kucoin::KuCoinClient;
+use crate::models::order_book::OrderBook;
+use crate::models::kucoin_models::{Level2Snapshot, Level2Update, Level2UpdateData};
+use crate::sockets::common::BookError;
+use crate::sockets::kucoin_session::KucoinSession;
+use crate::utils::FeedSender;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc::unbounded_channel;
+
+static LEVEL2_TOPIC: &str = "/contractMarket/level2";
+// pause before requesting the snapshot again after a failed request
+static SNAPSHOT_RETRY_DELAY: Duration = Duration::from_secs(1);
+
+/**
+ * Full depth KuCoin book, built from a REST snapshot and kept in sync with the level2
+ * updates that follow it
+ */
+#[derive(Debug)]
+pub struct Level2Book {
+    sequence: u64,
+    book: OrderBook,
+}
+
+impl Level2Book {
+    pub fn from_snapshot(snapshot: Level2Snapshot) -> Level2Book {
+        return Level2Book {
+            sequence: snapshot.sequence,
+            book: OrderBook::from_levels(snapshot.asks, snapshot.bids),
+        };
+    }
+
+    /**
+     * Applies the next update. Returns false for updates the book already contains,
+     * a skipped sequence means updates were lost and the book has to be rebuilt
+     */
+    pub fn apply(&mut self, update: &Level2UpdateData) -> Result<bool, BookError> {
+        if update.sequence <= self.sequence {
+            return Ok(false);
+        }
+        if update.sequence != self.sequence + 1 {
+            return Err(BookError::SequenceGap {
+                expected: self.sequence + 1,
+                received: update.sequence,
+            });
+        }
+
+        let change = update
+            .parse_change()
+            .ok_or_else(|| BookError::Malformed(update.change.clone()))?;
+        self.book.set_level(change.is_bid, change.price, change.size);
+        self.sequence = update.sequence;
+        return Ok(true);
+    }
+
+    pub fn to_order_book(&self, depth: usize) -> OrderBook {
+        return self.book.truncated(depth);
+    }
+}
+
+/**
+ * Streams the best `depth` levels of the KuCoin book after every update, even the ones
+ * below those levels, so a quiet top of book is not taken for a stale feed. Updates are
+ * buffered from the session while the snapshot is fetched, a sequence gap fetches a new
+ * snapshot. Returns once the receiver is gone
+ */
+pub async fn stream_kucoin_book(
+    client: Arc<KuCoinClient>,
+    session: Arc<KucoinSession>,
+    symbol: String,
+    depth: usize,
+    tx: FeedSender<OrderBook>,
+) {
+    let (tx_updates, mut rx_updates) = unbounded_channel();
+    session.subscribe(&format!("{}:{}", LEVEL2_TOPIC, symbol), None, false, move |msg: &str| {
+        return match serde_json::from_str::<Level2Update>(msg) {
+            Ok(update) => tx_updates.send(update.data).is_ok(),
+            Err(e) => {
+                tracing::warn!("Could not parse kucoin level2 update {}: {}", msg, e);
+                true
+            }
+        };
+    });
+
+    loop {
+        let mut book = match client.get_level2_snapshot(&symbol).await {
+            Ok(snapshot) => Level2Book::from_snapshot(snapshot),
+            Err(e) => {
+                tracing::warn!(market = symbol, "Could not fetch kucoin book snapshot: {}", e);
+                tokio::time::sleep(SNAPSHOT_RETRY_DELAY).await;
+                continue;
+            }
+        };
+        tracing::info!(market = symbol, sequence = book.sequence, "Kucoin book synced from snapshot");
+
+        if tx.send(book.to_order_book(depth).stamped(None)).is_err() {
+            return;
+        }
+
+        loop {
+            // the session keeps the subscriber until its handler fails, which needs this receiver gone
+            let Some(update) = rx_updates.recv().await else {
+                return;
+            };
+            match book.apply(&update) {
+                Ok(true) => {
+                    let exchange_time = update.timestamp.map(u128::from);
+                    if tx.send(book.to_order_book(depth).stamped(exchange_time)).is_err() {
+                        return;
+                    }
+                }
+                Ok(false) => {}
+                Err(e) => {
+                    tracing::warn!(market = symbol, "Kucoin book out of sync, resyncing: {}", e);
+                    break;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::units::{Price, Qty};
+    use std::str::FromStr;
+
+    fn level(price: &str, size: &str) -> (Price, Qty) {
+        return (Price::from_str(price).unwrap(), Qty::from_str(size).unwrap());
+    }
+
+    fn update(sequence: u64, change: &str) -> Level2UpdateData {
+        return Level2UpdateData {
+            sequence,
+            change: change.to_string(),
+            timestamp: None,
+        };
+    }
+
+    fn book() -> Level2Book {
+        let snapshot: Level2Snapshot = serde_json::from_str(
+            r#"{"sequence":10,"asks":[[100.5,3],[101,1]],"bids":[[100,2],[99.5,4]],"ts":1}"#,
+        )
+        .expect("Could not parse snapshot");
+        return Level2Book::from_snapshot(snapshot);
+    }
+
+    #[test]
+    fn test_applies_updates_in_sequence() {
+        let mut book = book();
+
+        assert_eq!(book.apply(&update(9, "100.5,sell,0")), Ok(false));
+        assert_eq!(book.apply(&update(11, "100.5,sell,0")), Ok(true));
+        assert_eq!(book.apply(&update(12, "100.25,buy,5")), Ok(true));
+        assert_eq!(book.apply(&update(13, "99.5,buy,1")), Ok(true));
+
+        let ob = book.to_order_book(2);
+        assert_eq!(ob.asks().collect::<Vec<_>>(), vec![level("101", "1")]);
+        assert_eq!(ob.bids().collect::<Vec<_>>(), vec![level("100.25", "5"), level("100", "2")]);
+        let ob = book.to_order_book(5);
+        assert_eq!(ob.bids().last(), Some(level("99.5", "1")));
+    }
+
+    #[test]
+    fn test_detects_sequence_gap() {
+        let mut book = book();
+
+        assert_eq!(
+            book.apply(&update(12, "100,buy,1")),
+            Err(BookError::SequenceGap { expected: 11, received: 12 })
+        );
+        assert_eq!(book.apply(&update(11, "100,hold,1")), Err(BookError::Malformed("100,hold,1".to_string())));
+    }
+}
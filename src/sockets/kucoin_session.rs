@@ -0,0 +1,332 @@
+use crate::clients::error::VenueError;
+use crate::clients::runtime::runtime;
+use crate::kucoin::KuCoinClient;
+use crate::models::kucoin_models::Comm;
+use crate::sockets::connection::{self, ConnectionError, Endpoint, Protocol};
+use async_trait::async_trait;
+use rand::Rng;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+use tokio::task::JoinHandle;
+use tokio_tungstenite::tungstenite::protocol::Message;
+
+// KuCoin accepts at most this many topics on one connection
+static MAX_TOPICS_PER_CONNECTION: usize = 100;
+
+fn generate_random_number_of_digits(digits: u32) -> i64 {
+    if !(1..=18).contains(&digits) {
+        panic!("Number of digits must be between 1 and 18");
+    }
+
+    let min = 10_i64.pow(digits - 1);
+    let max = 10_i64.pow(digits) - 1;
+    let mut rng = rand::thread_rng();
+
+    rng.gen_range(min..=max)
+}
+
+// `kind` is either subscribe or unsubscribe
+fn topic_message(kind: &str, topic: &str, is_private: bool) -> Message {
+    let message = format!(
+        r#"{{
+        "id":{},
+        "type": "{}",
+        "topic":"{}",
+        "privateChannel": {},
+        "response": true
+    }}"#,
+        generate_random_number_of_digits(13),
+        kind,
+        topic,
+        is_private
+    );
+    return Message::Text(message);
+}
+
+type Handler = Box<dyn FnMut(&str) -> bool + Send>;
+
+struct Subscriber {
+    // only messages with this subject are handed to the handler
+    subject: Option<String>,
+    handler: Handler,
+}
+
+// subscribers of one connection by topic
+type Routes = HashMap<String, Vec<Subscriber>>;
+
+// a handler that panicked leaves the routes consistent, the others keep being served
+fn lock(routes: &Mutex<Routes>) -> MutexGuard<'_, Routes> {
+    return routes.lock().unwrap_or_else(PoisonError::into_inner);
+}
+
+/**
+ * Hands a data frame to the subscribers of its topic and subject. Subscribers whose
+ * handler returns false are dropped, a topic left without subscribers is removed and
+ * returned so it can be unsubscribed
+ */
+fn route(routes: &mut Routes, text: &str) -> Option<String> {
+    let frame: Value = serde_json::from_str(text).unwrap_or_default();
+    let topic = frame["topic"].as_str()?;
+    let subject = frame["subject"].as_str();
+
+    let subscribers = routes.get_mut(topic)?;
+    subscribers.retain_mut(|subscriber| match &subscriber.subject {
+        Some(wanted) if Some(wanted.as_str()) != subject => true,
+        _ => (subscriber.handler)(text),
+    });
+
+    if subscribers.is_empty() {
+        routes.remove(topic);
+        return Some(topic.to_string());
+    }
+    return None;
+}
+
+/**
+ * One multiplexed socket of the session. The topics are subscribed again from the routes
+ * on every reconnect, a fresh bullet token is requested each time
+ */
+struct SessionProtocol {
+    client: Arc<KuCoinClient>,
+    is_private: bool,
+    id: usize,
+    routes: Arc<Mutex<Routes>>,
+    outbox: UnboundedReceiver<Message>,
+}
+
+#[async_trait]
+impl Protocol for SessionProtocol {
+    fn name(&self) -> String {
+        let kind = if self.is_private { "private" } else { "public" };
+        return format!("Kucoin {} session {}", kind, self.id);
+    }
+
+    async fn endpoint(&mut self) -> Result<Endpoint, VenueError> {
+        if self.is_private {
+            return self.client.get_kucoin_private_socket_url().await;
+        }
+        return self.client.get_public_socket_url().await;
+    }
+
+    fn welcome_frames(&self) -> usize {
+        return 1;
+    }
+
+    fn subscriptions(&mut self) -> Vec<Message> {
+        let routes = lock(&self.routes);
+        // queued (un)subscriptions are covered by subscribing the current topics
+        while self.outbox.try_recv().is_ok() {}
+
+        tracing::info!("Subscribing {} to kucoin topics: {:?}", self.name(), routes.keys());
+        return routes
+            .keys()
+            .map(|topic| topic_message("subscribe", topic, self.is_private))
+            .collect();
+    }
+
+    fn ping(&mut self) -> Message {
+        let ping = Comm {
+            id: generate_random_number_of_digits(13).to_string(),
+            type_: "ping".to_string(),
+            data: None,
+        };
+        return Message::Text(serde_json::to_string(&ping).unwrap());
+    }
+
+    async fn outgoing(&mut self) -> Option<Message> {
+        return self.outbox.recv().await;
+    }
+
+    fn is_data(&mut self, text: &str) -> Result<bool, ConnectionError> {
+        let frame: Value = serde_json::from_str(text).unwrap_or_default();
+        return match frame["type"].as_str() {
+            Some("message") => Ok(true),
+            Some("error") => {
+                // reconnecting would interrupt every other topic on the connection
+                tracing::error!("{} rejected a request: {}", self.name(), frame["data"]);
+                Ok(false)
+            }
+            // welcome, ack and pong
+            _ => Ok(false),
+        };
+    }
+}
+
+struct Connection {
+    id: usize,
+    routes: Arc<Mutex<Routes>>,
+    outbox: UnboundedSender<Message>,
+    task: JoinHandle<()>,
+}
+
+impl Connection {
+    // the topics in `routes` are subscribed once connected
+    fn open(client: Arc<KuCoinClient>, is_private: bool, id: usize, routes: Arc<Mutex<Routes>>) -> Connection {
+        let (outbox, pending) = unbounded_channel();
+        let protocol = SessionProtocol {
+            client,
+            is_private,
+            id,
+            routes: routes.clone(),
+            outbox: pending,
+        };
+
+        let frames = routes.clone();
+        let unsubscribe = outbox.clone();
+        let task = runtime().spawn(connection::run(protocol, move |text: &str| {
+            if let Some(topic) = route(&mut lock(&frames), text) {
+                tracing::info!("No subscribers left, unsubscribing kucoin topic {}", topic);
+                let _ = unsubscribe.send(topic_message("unsubscribe", &topic, is_private));
+            }
+            // the session lives as long as the process
+            return true;
+        }));
+
+        return Connection {
+            id,
+            routes,
+            outbox,
+            task,
+        };
+    }
+}
+
+/**
+ * Shares KuCoin's websockets between every stream of the process. Topics are
+ * multiplexed over one public and one private connection, further connections are
+ * only opened once a connection holds KuCoin's topic limit. Messages are routed to the
+ * subscribers of their topic and subject
+ */
+pub struct KucoinSession {
+    client: Arc<KuCoinClient>,
+    public: Mutex<Vec<Connection>>,
+    private: Mutex<Vec<Connection>>,
+}
+
+impl KucoinSession {
+    pub fn new(client: Arc<KuCoinClient>) -> KucoinSession {
+        return KucoinSession {
+            client,
+            public: Mutex::new(Vec::new()),
+            private: Mutex::new(Vec::new()),
+        };
+    }
+
+    /**
+     * Hands every message of `topic` to `handler`, only those of `subject` if one is given.
+     * The subscriber is dropped once the handler returns false, which it does once its
+     * receiver is gone. Must be called from within the shared runtime or a synchronous thread
+     */
+    pub fn subscribe<H>(&self, topic: &str, subject: Option<&str>, is_private: bool, handler: H)
+    where
+        H: FnMut(&str) -> bool + Send + 'static,
+    {
+        let subscriber = Subscriber {
+            subject: subject.map(str::to_string),
+            handler: Box::new(handler),
+        };
+        let connections = if is_private { &self.private } else { &self.public };
+        let mut connections = connections.lock().expect("Kucoin session poisoned");
+
+        // a handler panic ends the task of its connection, it is opened again with its topics
+        for connection in connections.iter_mut() {
+            if connection.task.is_finished() {
+                tracing::warn!("Kucoin session connection {} ended, reopening it", connection.id);
+                *connection = Connection::open(self.client.clone(), is_private, connection.id, connection.routes.clone());
+            }
+        }
+
+        // a topic subscribed already only gets another subscriber
+        for connection in connections.iter() {
+            let mut routes = lock(&connection.routes);
+            if let Some(subscribers) = routes.get_mut(topic) {
+                subscribers.push(subscriber);
+                return;
+            }
+        }
+
+        let free = connections
+            .iter()
+            .position(|connection| lock(&connection.routes).len() < MAX_TOPICS_PER_CONNECTION);
+        let index = match free {
+            Some(index) => index,
+            None => {
+                let id = connections.len();
+                let routes = Arc::new(Mutex::new(Routes::new()));
+                connections.push(Connection::open(self.client.clone(), is_private, id, routes));
+                id
+            }
+        };
+
+        let connection = &connections[index];
+        let mut routes = lock(&connection.routes);
+        routes.insert(topic.to_string(), vec![subscriber]);
+        // a connection that is not up yet subscribes its routes once connected
+        let _ = connection.outbox.send(topic_message("subscribe", topic, is_private));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+
+    fn subscriber(subject: Option<&str>, tx: mpsc::Sender<String>) -> Subscriber {
+        return Subscriber {
+            subject: subject.map(str::to_string),
+            handler: Box::new(move |text: &str| tx.send(text.to_string()).is_ok()),
+        };
+    }
+
+    #[test]
+    fn test_routes_by_topic_and_subject() {
+        let (position_tx, position_rx) = mpsc::channel();
+        let (any_tx, any_rx) = mpsc::channel();
+        let mut routes = Routes::new();
+        routes.insert(
+            "/contract/position:XBTUSDTM".to_string(),
+            vec![subscriber(Some("position.change"), position_tx), subscriber(None, any_tx)],
+        );
+
+        let change = r#"{"type":"message","topic":"/contract/position:XBTUSDTM","subject":"position.change"}"#;
+        let settlement = r#"{"type":"message","topic":"/contract/position:XBTUSDTM","subject":"position.settlement"}"#;
+        let other = r#"{"type":"message","topic":"/contract/position:ETHUSDTM","subject":"position.change"}"#;
+        assert_eq!(route(&mut routes, change), None);
+        assert_eq!(route(&mut routes, settlement), None);
+        assert_eq!(route(&mut routes, other), None);
+
+        assert_eq!(position_rx.try_iter().count(), 1);
+        assert_eq!(any_rx.try_iter().count(), 2);
+
+        // the topic is released once every receiver is gone
+        drop(position_rx);
+        drop(any_rx);
+        assert_eq!(route(&mut routes, change), Some("/contract/position:XBTUSDTM".to_string()));
+        assert!(routes.is_empty());
+    }
+
+    #[test]
+    fn test_routes_survive_a_panicking_handler() {
+        let (tx, rx) = mpsc::channel();
+        let routes = Arc::new(Mutex::new(Routes::new()));
+        let panicking = Subscriber {
+            subject: Some("position.change".to_string()),
+            handler: Box::new(|_: &str| panic!("Can't parse")),
+        };
+        lock(&routes).insert(
+            "/contract/position:XBTUSDTM".to_string(),
+            vec![panicking, subscriber(Some("position.settlement"), tx)],
+        );
+
+        let change = r#"{"type":"message","topic":"/contract/position:XBTUSDTM","subject":"position.change"}"#;
+        let frames = routes.clone();
+        let panicked = std::thread::spawn(move || route(&mut lock(&frames), change)).join();
+        assert!(panicked.is_err() && routes.is_poisoned());
+
+        let settlement = r#"{"type":"message","topic":"/contract/position:XBTUSDTM","subject":"position.settlement"}"#;
+        assert_eq!(route(&mut lock(&routes), settlement), None);
+        assert_eq!(rx.try_iter().count(), 1);
+    }
+}
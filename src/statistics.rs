@@ -1,2 +1,3 @@
 pub mod stats;
-pub mod account_stats;
\ No newline at end of file
+pub mod account_stats;
+pub mod volatility;
\ No newline at end of file
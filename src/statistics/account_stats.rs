@@ -1,19 +1,19 @@
+use std::collections::HashMap;
 use std::ops::Div;
 use std::sync::mpsc;
-use std::sync::mpsc::Sender;
-use std::thread;
+use std::sync::mpsc::{Receiver, Sender};
+use std::sync::Arc;
 use crate::bluefin::models::TradeOrderUpdate;
-use crate::bluefin::{AccountData, AccountUpdateEventData, BluefinClient};
-use crate::env;
-use crate::env::EnvVars;
-use crate::kucoin::{AvailableBalance, Credentials, KuCoinClient, TransactionHistory};
-use crate::models::common::Config;
+use crate::bluefin::BluefinPrivateEvent;
+use crate::clients::registry::clients;
+use crate::config::app_config::AppConfig;
+use crate::kucoin::TransactionHistory;
+use crate::models::common::{Config, VenueKind};
 use crate::models::kucoin_models::PositionList;
-use crate::sockets::bluefin_private_socket::stream_bluefin_private_socket;
-use crate::sockets::kucoin_socket::stream_kucoin_socket;
+use crate::sockets::bluefin_session::BluefinSession;
+use crate::utils::{forward_into, FeedSender};
+use crate::venues::venue::{create_maker_venue, create_taker_venue};
 use bigdecimal::{FromPrimitive, ToPrimitive};
-use serde_json::Value;
-use crate::bluefin::models::parse_user_trade_order_update;
 use rust_decimal::Decimal;
 use std::time::Duration;
 use std::time::Instant;
@@ -26,207 +26,180 @@ pub trait AccountStatistics {
     fn sum_unrealised_pnl(position_list: &PositionList) -> f64;
     fn get_bluefin_symbol(config: &Config, kucoin_symbol: &str) -> Option<String>;
 }
+// Every input the account stats react to, multiplexed onto a single channel
+#[derive(Debug)]
+pub enum AccountStatsEvent {
+    MakerBalance(VenueKind, f64),
+    TakerBalance(VenueKind, f64),
+    TakerTrade(TradeOrderUpdate),
+    Disconnected(AccountStatsFeed),
+    // a market subscribing to the account wide feeds of its venues
+    Register(AccountStatsSubscriber),
+}
+
+#[derive(Debug)]
+pub struct AccountStatsSubscriber {
+    pub maker: VenueKind,
+    pub taker: VenueKind,
+    pub tx_maker_balance: FeedSender<f64>,
+    pub tx_taker_balance: FeedSender<f64>,
+    pub tx_taker_trade: FeedSender<TradeOrderUpdate>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccountStatsFeed {
+    MakerBalance(VenueKind),
+    TakerBalance(VenueKind),
+    TakerTrade,
+}
+
 pub struct AccountStats {
-    bluefin_client: BluefinClient,
-    kucoin_client: KuCoinClient,
-    config: Config,
-    v_tx_account_data: Vec<Sender<AccountData>>,
-    v_tx_account_data_kc: Vec<Sender<AvailableBalance>>,
-    v_tx_account_data_bluefin_user_trade: Vec<Sender<TradeOrderUpdate>>,
+    bluefin_session: Arc<BluefinSession>,
+    config: Arc<AppConfig>,
+    // balance senders of every market, keyed by the venue the balance is streamed from
+    tx_taker_balances: HashMap<VenueKind, Vec<FeedSender<f64>>>,
+    tx_maker_balances: HashMap<VenueKind, Vec<FeedSender<f64>>>,
+    v_tx_account_data_bluefin_user_trade: Vec<FeedSender<TradeOrderUpdate>>,
+    tx_event: Sender<AccountStatsEvent>,
+    rx_event: Receiver<AccountStatsEvent>,
 }
 
 impl AccountStats {
-    pub fn new(
-        config: Config, 
-        v_tx_account_data: Vec<Sender<AccountData>>,
-        v_tx_account_data_kc: Vec<Sender<AvailableBalance>>,
-        v_tx_account_data_bluefin_user_trade: Vec<Sender<TradeOrderUpdate>>) -> AccountStats {
-        
-        let vars: EnvVars = env::env_variables();
+    pub fn new(config: Arc<AppConfig>) -> AccountStats {
 
-        let bluefin_client = BluefinClient::new(
-            &vars.bluefin_wallet_key,
-            &vars.bluefin_endpoint,
-            &vars.bluefin_on_boarding_url,
-            &vars.bluefin_websocket_url,
-            vars.bluefin_leverage,
-        );
+        let bluefin_session = clients(&config.vars).bluefin_session();
 
-        let kucoin_client = KuCoinClient::new(
-            Credentials::new(
-                &vars.kucoin_api_key,
-                &vars.kucoin_api_secret,
-                &vars.kucoin_api_phrase,
-            ),
-            &vars.kucoin_endpoint,
-            &vars.kucoin_on_boarding_url,
-            &vars.kucoin_websocket_url,
-            vars.kucoin_leverage,
-        );
+        let (tx_event, rx_event) = mpsc::channel();
 
         AccountStats {
-            bluefin_client,
-            kucoin_client,
+            bluefin_session,
             config,
-            v_tx_account_data,
-            v_tx_account_data_kc,
-            v_tx_account_data_bluefin_user_trade
+            tx_taker_balances: HashMap::new(),
+            tx_maker_balances: HashMap::new(),
+            v_tx_account_data_bluefin_user_trade: Vec::new(),
+            tx_event,
+            rx_event,
         }
     }
-}
-
-impl AccountStatistics for AccountStats{
-    fn log(&mut self) {
-        let vars: EnvVars = env::env_variables();
-        let (tx_bluefin_account_data_update, rx_bluefin_account_data_update) = mpsc::channel();
-        let (tx_kucoin_available_balance, rx_kucoin_available_balance) = mpsc::channel();
-        let (tx_bluefin_trade_order_update, rx_bluefin_trade_order_update) = mpsc::channel();
 
+    pub fn event_sender(&self) -> Sender<AccountStatsEvent> {
+        return self.tx_event.clone();
+    }
 
-        let bluefin_auth_token = self.bluefin_client.auth_token.clone();
-        let bluefin_websocket_url = vars.bluefin_websocket_url.clone();
-        let _handle_bluefin_account_data_update = thread::spawn(move || {
-            stream_bluefin_private_socket(
-                &bluefin_websocket_url,
-                &"",
-                &bluefin_auth_token,
-                "AccountDataUpdate",
-                tx_bluefin_account_data_update, // Sender channel of the appropriate type
-                |msg: &str| -> AccountData {
-                    let account_event_update_data: AccountUpdateEventData =
-                        serde_json::from_str(&msg).unwrap();
-                    let ad = account_event_update_data.data.account_data;
-                    tracing::info!(
-                        wallet_balance = ad.wallet_balance,
-                        total_position_qty_reduced = ad.total_position_qty_reduced,
-                        total_position_qty_reducible = ad.total_position_qty_reducible,
-                        total_position_margin = ad.total_position_margin,
-                        total_unrealized_profit = ad.total_unrealized_profit,
-                        total_expected_pnl = ad.total_expected_pnl,
-                        free_collateral = ad.free_collateral,
-                        account_value = ad.account_value,
-                        "Bluefin Account Data"
-                    );
-                    ad
-                },
+    /**
+     * Adds the market's senders, the balance stream of a venue is started
+     * the first time a market uses it
+     */
+    fn register(&mut self, subscriber: AccountStatsSubscriber) {
+        // balances are account wide, market overrides do not apply
+        let params = self.config.default_params();
+        let vars = &self.config.vars;
+
+        let taker = subscriber.taker;
+        if !self.tx_taker_balances.contains_key(&taker) {
+            let venue = create_taker_venue(taker, vars, &params);
+            let tx_balance = forward_into(
+                &self.tx_event,
+                move |balance| AccountStatsEvent::TakerBalance(taker, balance),
+                AccountStatsEvent::Disconnected(AccountStatsFeed::TakerBalance(taker)),
             );
-        });
-
-        let bluefin_auth_token = self.bluefin_client.auth_token.clone();
-        let bluefin_websocket_url = vars.bluefin_websocket_url.clone();
-        let _handle_bluefin_account_data_update = thread::spawn(move || {
-            stream_bluefin_private_socket(
-                &bluefin_websocket_url,
-                &"",
-                &bluefin_auth_token,
-                "UserTrade",
-                tx_bluefin_trade_order_update, // Sender channel of the appropriate type
-                |msg: &str| -> TradeOrderUpdate {
-                    tracing::info!("User Trade: {}", msg);
+            venue.stream_balance(tx_balance);
+        }
 
-                    let v: Value = serde_json::from_str(&msg).unwrap();
-                    let user_trade: TradeOrderUpdate = parse_user_trade_order_update(serde_json::from_value(v["data"]["trade"].clone()).unwrap());
+        let maker = subscriber.maker;
+        if !self.tx_maker_balances.contains_key(&maker) {
+            let venue = create_maker_venue(maker, vars, &params);
+            let tx_balance = forward_into(
+                &self.tx_event,
+                move |balance| AccountStatsEvent::MakerBalance(maker, balance),
+                AccountStatsEvent::Disconnected(AccountStatsFeed::MakerBalance(maker)),
+            );
+            venue.stream_balance(tx_balance);
+        }
 
-                    let user_trade_commission_dec = Decimal::from_u128(user_trade.commission).unwrap()
-                        .div(Decimal::from_u128(1000000000000000000).unwrap());
-                    let user_trade_display = user_trade_commission_dec.to_f64().unwrap();
+        self.tx_taker_balances.entry(taker).or_default().push(subscriber.tx_taker_balance);
+        self.tx_maker_balances.entry(maker).or_default().push(subscriber.tx_maker_balance);
+        self.v_tx_account_data_bluefin_user_trade.push(subscriber.tx_taker_trade);
+    }
+}
 
-                    let realized_pnl_dec = Decimal::from_i128(user_trade.realized_pnl).unwrap()
-                    .div(Decimal::from_u128(1000000000000000000).unwrap());
+impl AccountStatistics for AccountStats{
+    fn log(&mut self) {
+        let vars = &self.config.vars;
 
-                    tracing::info!(
-                        bluefin_market=user_trade.symbol,
-                        bluefin_commission_fee=user_trade_display,
-                        bluefin_realized_pnl = realized_pnl_dec.to_f64().unwrap(),
-                        "Bluefin User Trade"
-                    );
+        let tx_bluefin_trade_order_update = forward_into(
+            &self.tx_event,
+            AccountStatsEvent::TakerTrade,
+            AccountStatsEvent::Disconnected(AccountStatsFeed::TakerTrade),
+        );
 
-                    user_trade
-                },
-            );
-        });
+        self.bluefin_session.subscribe(None, move |event: &BluefinPrivateEvent| {
+            let BluefinPrivateEvent::UserTrade(user_trade) = event else {
+                return true;
+            };
 
+            let user_trade_commission_dec = Decimal::from_u128(user_trade.commission).unwrap()
+                .div(Decimal::from_u128(1000000000000000000).unwrap());
+            let user_trade_display = user_trade_commission_dec.to_f64().unwrap();
 
+            let realized_pnl_dec = Decimal::from_i128(user_trade.realized_pnl).unwrap()
+            .div(Decimal::from_u128(1000000000000000000).unwrap());
 
-        let topic = format!("/contractAccount/wallet");
-        let kucoin_private_socket_url = self.kucoin_client.get_kucoin_private_socket_url().clone();
-        let _handle_kucoin_of = thread::spawn(move || {
-            tracing::info!("Creating Kucoin Account Balance handler...");
-            stream_kucoin_socket(
-                &kucoin_private_socket_url,
-                &"",
-                &topic,
-                tx_kucoin_available_balance, // Sender channel of the appropriate type
-                |msg: &str| -> AvailableBalance {
-                    let available_balance: AvailableBalance =
-                        serde_json::from_str(&msg).expect("Can't parse");
-                    tracing::info!(available_balance=available_balance.data.available_balance,
-                                   hold_balance=available_balance.data.hold_balance,
-                                   "Kucoin Account Balance");
-                    available_balance
-                },
-                &"availableBalance.change",
-                true
+            tracing::info!(
+                bluefin_market=user_trade.symbol,
+                bluefin_commission_fee=user_trade_display,
+                bluefin_realized_pnl = realized_pnl_dec.to_f64().unwrap(),
+                "Bluefin User Trade"
             );
-        });
 
+            tx_bluefin_trade_order_update.send(user_trade.clone()).is_ok()
+        });
 
         let last_account_balance_check = Instant::now();
+        let clients = clients(vars);
 
         loop {
-
-            match rx_bluefin_trade_order_update.try_recv() {
-                Ok(value) => {
-                    let _ = self.v_tx_account_data_bluefin_user_trade.iter()
-                        .try_for_each(|sender| {
-                            let clone = value.clone();
-                            sender.send(clone)
-                        });
-                }
-                Err(mpsc::TryRecvError::Empty) => {
-                    // No message from kucoin yet
+            let event = self.rx_event
+                .recv()
+                .expect("Account stats event channel has disconnected!");
+
+            match event {
+                AccountStatsEvent::TakerTrade(value) => {
+                    // senders of removed markets are dropped
+                    self.v_tx_account_data_bluefin_user_trade
+                        .retain(|sender| sender.send(value.clone()).is_ok());
                 }
-                Err(mpsc::TryRecvError::Disconnected) => {
-                    tracing::info!("Bluefin UserTrade socket has disconnected!");
-                }
-            }
-           
-            match rx_kucoin_available_balance.try_recv() {
-                Ok(value) => {
-                    let balance = value.1;
-                    tracing::info!("Kucoin Available Balance: {:?}", balance);
+                AccountStatsEvent::MakerBalance(kind, balance) => {
+                    tracing::info!(
+                        rest_requests = clients.requests(kind).map(|requests| requests.total()),
+                        "{:?} Available Balance: {:?}",
+                        kind,
+                        balance
+                    );
                     if last_account_balance_check.elapsed() >= Duration::from_secs(ACCOUNT_STATS_PERIOD_DURATION) {
-                        let _ = self.v_tx_account_data_kc.iter()
-                        .try_for_each(|sender| {
-                            let clone = balance.clone();
-                            sender.send(clone)
-                        });
+                        if let Some(senders) = self.tx_maker_balances.get_mut(&kind) {
+                            senders.retain(|sender| sender.send(balance).is_ok());
+                        }
                     }
                 }
-                Err(mpsc::TryRecvError::Empty) => {
-                    // No message from kucoin yet
-                }
-                Err(mpsc::TryRecvError::Disconnected) => {
-                    tracing::info!("Kucoin AvailableBalance socket has disconnected!");
-                }
-            }
-
-            match rx_bluefin_account_data_update.try_recv() {
-                Ok(value) => {
-                    tracing::info!("Bluefin AccountData: {:?}", value);
+                AccountStatsEvent::TakerBalance(kind, balance) => {
+                    tracing::info!(
+                        rest_requests = clients.requests(kind).map(|requests| requests.total()),
+                        "{:?} Account Value: {:?}",
+                        kind,
+                        balance
+                    );
                     if last_account_balance_check.elapsed() >= Duration::from_secs(ACCOUNT_STATS_PERIOD_DURATION) {
-                        let _ = self.v_tx_account_data.iter()
-                        .try_for_each(|sender| {
-                            let clone = value.clone();
-                            sender.send(clone)
-                        });
+                        if let Some(senders) = self.tx_taker_balances.get_mut(&kind) {
+                            senders.retain(|sender| sender.send(balance).is_ok());
+                        }
                     }
                 }
-                Err(mpsc::TryRecvError::Empty) => {
-                    // No message from bluefin yet
+                AccountStatsEvent::Register(subscriber) => {
+                    self.register(subscriber);
                 }
-                Err(mpsc::TryRecvError::Disconnected) => {
-                    tracing::info!("Bluefin AccountData socket has disconnected!");
+                AccountStatsEvent::Disconnected(feed) => {
+                    tracing::info!("{:?} socket has disconnected!", feed);
                 }
             }
         }
@@ -1,42 +1,50 @@
-use crate::env;
-use crate::env::EnvVars;
-use crate::kucoin::{Credentials, KuCoinClient};
+use crate::clients::runtime::block_on;
+use crate::config::app_config::AppConfig;
 use crate::models::common::Market;
-use std::sync::mpsc::Sender;
-use std::thread;
+use crate::utils::FeedSender;
+use crate::venues::venue::{create_maker_venue, MakerVenue};
+use std::sync::mpsc::{Receiver, RecvTimeoutError};
+use std::sync::Arc;
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 static PERIOD_DURATION: u64 = 30;
 
 pub struct Stats {
     market: Market,
-    kucoin_client: KuCoinClient,
-    tx_stats: Sender<f64>,
+    maker: Arc<dyn MakerVenue>,
+    tx_stats: FeedSender<f64>,
+    // a message or the sender being dropped stops the thread
+    rx_shutdown: Receiver<()>,
     genesis: SystemTime,
 }
 
 impl Stats {
-    pub fn new(market: Market, tx_stats: Sender<f64>) -> Stats {
-        let vars: EnvVars = env::env_variables();
-        let kucoin_client = KuCoinClient::new(
-            Credentials::new(
-                &vars.kucoin_api_key,
-                &vars.kucoin_api_secret,
-                &vars.kucoin_api_phrase,
-            ),
-            &vars.kucoin_endpoint,
-            &vars.kucoin_on_boarding_url,
-            &vars.kucoin_websocket_url,
-            vars.kucoin_leverage,
+    pub fn new(market: Market, config: Arc<AppConfig>, tx_stats: FeedSender<f64>, rx_shutdown: Receiver<()>) -> Stats {
+        let maker = create_maker_venue(
+            market.venues.maker,
+            &config.vars,
+            &config.market_params(&market),
         );
 
         Stats {
             market,
-            kucoin_client,
+            maker,
             tx_stats,
+            rx_shutdown,
             genesis: SystemTime::now(),
         }
     }
+
+    /**
+     * Waits for the next period, false once the market is shutting down so the
+     * thread can be joined without sitting out the rest of the period
+     */
+    fn wait_period(&self) -> bool {
+        return match self.rx_shutdown.recv_timeout(Duration::from_secs(PERIOD_DURATION)) {
+            Err(RecvTimeoutError::Timeout) => true,
+            Ok(()) | Err(RecvTimeoutError::Disconnected) => false,
+        };
+    }
 }
 
 pub trait Statistics {
@@ -52,16 +60,25 @@ impl Statistics for Stats {
                 .expect("could not get current time since unix epoch")
                 .as_millis();
             let bluefin_market = self.market.symbols.bluefin.to_owned();
-            let total_buy_size = self.kucoin_client.get_fill_size_for_time_window(
-                &bluefin_market,
-                "buy",
-                service_start,
-            );
-            let total_sell_size = self.kucoin_client.get_fill_size_for_time_window(
-                &bluefin_market,
-                "sell",
-                service_start,
-            );
+            let (total_buy_size, total_sell_size) = block_on(async {
+                futures::join!(
+                    self.maker
+                        .get_fill_size_for_time_window(&self.market, true, service_start),
+                    self.maker
+                        .get_fill_size_for_time_window(&self.market, false, service_start)
+                )
+            });
+            let (total_buy_size, total_sell_size) = match (total_buy_size, total_sell_size) {
+                (Ok(buy), Ok(sell)) => (buy, sell),
+                // the market maker keeps its last buy percent until the next period
+                (Err(e), _) | (_, Err(e)) => {
+                    tracing::warn!(market = bluefin_market, "Could not fetch fills for statistics: {}", e);
+                    if !self.wait_period() {
+                        return;
+                    }
+                    continue;
+                }
+            };
             let buy_percent = if total_buy_size + total_sell_size == 0 {
                 50.0
             } else {
@@ -75,11 +92,15 @@ impl Statistics for Stats {
                 "Statistics"
             );
 
-            self.tx_stats
-                .send(buy_percent)
-                .expect("Error in sending stats");
+            if self.tx_stats.send(buy_percent).is_err() {
+                tracing::info!(market = bluefin_market, "Market maker is gone, stopping statistics");
+                return;
+            }
 
-            thread::sleep(Duration::from_secs(PERIOD_DURATION));
+            if !self.wait_period() {
+                tracing::info!(market = bluefin_market, "Market is shutting down, stopping statistics");
+                return;
+            }
         }
     }
 }
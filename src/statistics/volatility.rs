@@ -0,0 +1,163 @@
+use crate::models::common::VolatilityConfig;
+use crate::models::units::Price;
+use rust_decimal::prelude::FromPrimitive;
+use rust_decimal::Decimal;
+
+/**
+ * Realised volatility of a mid as an exponentially weighted average of its squared log
+ * returns. Mids arrive at irregular times, so every return is weighted by the time it
+ * covers: the estimate is a variance per second that decays by half every half-life,
+ * whatever the number of updates in between
+ */
+#[derive(Debug, Clone)]
+pub struct EwmaVolatility {
+    config: VolatilityConfig,
+    // variance of log returns per second, averaged from zero
+    variance_rate: f64,
+    // weight the returns so far carry in `variance_rate`, the rest is the zero it started from
+    total_weight: f64,
+    // log of the last mid and when it was seen, ms since epoch
+    last: Option<(f64, u128)>,
+}
+
+impl EwmaVolatility {
+    pub fn new(config: VolatilityConfig) -> EwmaVolatility {
+        return EwmaVolatility {
+            config,
+            variance_rate: 0.0,
+            total_weight: 0.0,
+            last: None,
+        };
+    }
+
+    // picks up reloaded settings, the estimate so far is kept
+    pub fn reconfigure(&mut self, config: VolatilityConfig) {
+        self.config = config;
+    }
+
+    // `time` in ms since epoch, mids seen in the same ms as the last one are folded into the next
+    pub fn update(&mut self, mid: Price, time: u128) {
+        let log_mid = mid.to_f64().ln();
+        if !log_mid.is_finite() {
+            return;
+        }
+        let Some((last_log_mid, last_time)) = self.last else {
+            self.last = Some((log_mid, time));
+            return;
+        };
+        if time <= last_time {
+            return;
+        }
+
+        let elapsed = (time - last_time) as f64 / 1000.0;
+        let sample = (log_mid - last_log_mid).powi(2) / elapsed;
+        let weight = 1.0 - 0.5_f64.powf(elapsed / self.config.half_life_secs);
+        // a return over a few ms weighs as little as the time it covers, it can not seed the estimate
+        self.variance_rate += weight * (sample - self.variance_rate);
+        self.total_weight += weight * (1.0 - self.total_weight);
+        self.last = Some((log_mid, time));
+    }
+
+    /**
+     * Volatility over the configured horizon in bps. None until the returns cover a
+     * half-life and carry half of the weight, the first ones say too little
+     */
+    pub fn volatility_bps(&self) -> Option<f64> {
+        if self.total_weight < 0.5 {
+            return None;
+        }
+        let variance_rate = self.variance_rate / self.total_weight;
+        return Some((variance_rate * self.config.horizon_secs).sqrt() * 10000.0);
+    }
+
+    // half-spread the quotes keep at least around `mid`
+    pub fn min_half_spread(&self, mid: Price) -> Option<Price> {
+        let volatility_bps = self.volatility_bps()?;
+        let factor = Decimal::from_f64(volatility_bps * self.config.min_spread_multiplier / 10000.0)?;
+        return Some(mid * factor);
+    }
+
+    // factor on quote sizes, 1 while calm and calm_bps / volatility above that
+    pub fn size_factor(&self) -> Decimal {
+        return match self.volatility_bps() {
+            Some(volatility_bps) if volatility_bps > self.config.calm_bps => {
+                Decimal::from_f64(self.config.calm_bps / volatility_bps).unwrap_or(Decimal::ONE)
+            }
+            _ => Decimal::ONE,
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    fn estimator() -> EwmaVolatility {
+        return EwmaVolatility::new(VolatilityConfig {
+            half_life_secs: 10.0,
+            horizon_secs: 1.0,
+            min_spread_multiplier: 2.0,
+            calm_bps: 5.0,
+        });
+    }
+
+    fn mid(value: f64) -> Price {
+        return Price::from(Decimal::from_f64(value).unwrap());
+    }
+
+    #[test]
+    fn test_flat_mid_has_no_volatility() {
+        let mut volatility = estimator();
+        for second in 0..=10 {
+            volatility.update(Price::from_str("2000").unwrap(), second * 1000);
+            if second < 10 {
+                assert_eq!(volatility.volatility_bps(), None, "not warmed up after {}s", second);
+            }
+        }
+        assert_eq!(volatility.volatility_bps(), Some(0.0));
+        assert_eq!(volatility.size_factor(), Decimal::ONE);
+    }
+
+    #[test]
+    fn test_measures_returns_per_horizon() {
+        let mut volatility = estimator();
+        // 10bps log returns every second, alternating up and down
+        for second in 0..=20 {
+            let log_mid = 2000f64.ln() + if second % 2 == 0 { 0.0 } else { 0.001 };
+            volatility.update(mid(log_mid.exp()), second * 1000);
+        }
+        let volatility_bps = volatility.volatility_bps().expect("Warmed up");
+        assert!((volatility_bps - 10.0).abs() < 0.01, "{}", volatility_bps);
+
+        // twice the volatility is kept as half-spread, sizes shrink to calm / volatility
+        let half_spread = volatility.min_half_spread(Price::from_str("2000").unwrap()).unwrap();
+        assert!((half_spread.to_f64() - 4.0).abs() < 0.01, "{}", half_spread);
+        assert!((volatility.size_factor() - Decimal::from_f64(0.5).unwrap()).abs() < Decimal::new(1, 3));
+
+        // the same moves spread over twice the time are less volatile
+        let mut slower = estimator();
+        for step in 0..=20 {
+            let log_mid = 2000f64.ln() + if step % 2 == 0 { 0.0 } else { 0.001 };
+            slower.update(mid(log_mid.exp()), step * 2000);
+        }
+        let slower_bps = slower.volatility_bps().expect("Warmed up");
+        assert!((slower_bps - 10.0 / 2f64.sqrt()).abs() < 0.01, "{}", slower_bps);
+    }
+
+    #[test]
+    fn test_quick_first_return_does_not_seed_the_estimate() {
+        let mut volatility = estimator();
+        // a 10bps jump 1ms after the first mid, then 10s of a flat mid
+        volatility.update(mid(2000.0), 0);
+        volatility.update(mid(2002.0), 1);
+        assert_eq!(volatility.volatility_bps(), None);
+        for second in 1..=10 {
+            volatility.update(mid(2002.0), second * 1000);
+        }
+
+        // seeded with the jump over 1ms the estimate would be over 200bps
+        let volatility_bps = volatility.volatility_bps().expect("Warmed up");
+        assert!(volatility_bps < 3.0, "{}", volatility_bps);
+    }
+}
@@ -1,6 +1,9 @@
 use rand::{distributions::Alphanumeric, Rng};
 use std::{
     collections::HashMap,
+    fmt,
+    sync::mpsc::{self, Sender},
+    sync::{Arc, Mutex, PoisonError},
     time::{SystemTime, UNIX_EPOCH},
 }; // 0.8
 
@@ -42,4 +45,108 @@ pub fn format_query<S: ::std::hash::BuildHasher>(params: &HashMap<String, String
         }
     }
     query
-}
\ No newline at end of file
+}
+
+/// Typed sender of a feed whose messages go straight into an event channel, wrapped by
+/// the function it was created with. Once its last clone is dropped the feed's disconnect
+/// event is sent, so the consumer can block on a single receiver and still learn about
+/// dead feeds.
+pub struct FeedSender<T> {
+    inner: Arc<FeedInner<T>>,
+}
+
+struct FeedInner<T> {
+    send: Box<dyn Fn(T) -> bool + Send + Sync>,
+    on_disconnect: Mutex<Option<Box<dyn FnOnce() + Send>>>,
+}
+
+impl<T> FeedSender<T> {
+    /// Fails once the receiving end is gone.
+    pub fn send(&self, msg: T) -> Result<(), mpsc::SendError<()>> {
+        if (self.inner.send)(msg) {
+            return Ok(());
+        }
+        return Err(mpsc::SendError(()));
+    }
+}
+
+impl<T> Clone for FeedSender<T> {
+    fn clone(&self) -> Self {
+        return FeedSender {
+            inner: self.inner.clone(),
+        };
+    }
+}
+
+impl<T> fmt::Debug for FeedSender<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return f.write_str("FeedSender");
+    }
+}
+
+impl<T> Drop for FeedInner<T> {
+    fn drop(&mut self) {
+        let on_disconnect = self.on_disconnect.get_mut().unwrap_or_else(PoisonError::into_inner).take();
+        if let Some(on_disconnect) = on_disconnect {
+            on_disconnect();
+        }
+    }
+}
+
+/// A plain channel as a feed, nothing is sent when it disconnects.
+impl<T: Send + 'static> From<Sender<T>> for FeedSender<T> {
+    fn from(tx: Sender<T>) -> FeedSender<T> {
+        return FeedSender {
+            inner: Arc::new(FeedInner {
+                send: Box::new(move |msg| tx.send(msg).is_ok()),
+                on_disconnect: Mutex::new(None),
+            }),
+        };
+    }
+}
+
+/// Creates a typed sender whose messages end up in `tx` as events, see `FeedSender`.
+pub fn forward_into<T, E, F>(tx: &Sender<E>, wrap: F, on_disconnect: E) -> FeedSender<T>
+where
+    T: Send + 'static,
+    E: Send + 'static,
+    F: Fn(T) -> E + Send + Sync + 'static,
+{
+    let tx_disconnect = tx.clone();
+    let tx = tx.clone();
+    return FeedSender {
+        inner: Arc::new(FeedInner {
+            send: Box::new(move |msg| tx.send(wrap(msg)).is_ok()),
+            on_disconnect: Mutex::new(Some(Box::new(move || {
+                let _ = tx_disconnect.send(on_disconnect);
+            }))),
+        }),
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq)]
+    enum Event {
+        Value(u8),
+        Disconnected,
+    }
+
+    #[test]
+    fn test_forward_into_sends_disconnect_after_values() {
+        let (tx, rx) = mpsc::channel();
+        let tx_typed = forward_into(&tx, Event::Value, Event::Disconnected);
+
+        let tx_clone = tx_typed.clone();
+        tx_typed.send(1).unwrap();
+        drop(tx_typed);
+        tx_clone.send(2).unwrap();
+        // the feed is still alive through the clone
+        assert_eq!(rx.try_iter().collect::<Vec<_>>(), vec![Event::Value(1), Event::Value(2)]);
+
+        drop(tx_clone);
+        assert_eq!(rx.try_recv().unwrap(), Event::Disconnected);
+    }
+}
@@ -0,0 +1,4 @@
+pub mod venue;
+pub mod binance_venue;
+pub mod bluefin_venue;
+pub mod kucoin_venue;
\ No newline at end of file
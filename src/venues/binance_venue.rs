@@ -0,0 +1,78 @@
+use crate::binance::BinanceClient;
+use crate::clients::registry::clients;
+use crate::clients::runtime::runtime;
+use crate::env::EnvVars;
+use crate::models::binance_models::{BinanceBookMode, BookTicker, DepthUpdate};
+use crate::models::common::{Market, MarketParams, VenueKind};
+use crate::models::order_book::OrderBook;
+use crate::sockets::binance_book::stream_binance_book;
+use crate::sockets::binance_ob_socket::BinanceOrderBookStream;
+use crate::sockets::common::{stream_order_book, BookForwarder};
+use crate::utils::FeedSender;
+use crate::venues::venue::ReferenceFeed;
+use std::sync::Arc;
+
+pub struct BinanceVenue {
+    kind: VenueKind,
+    client: Arc<BinanceClient>,
+    websocket_url: String,
+    book_mode: BinanceBookMode,
+    book_depth: usize,
+    trigger_bps: f64,
+}
+
+impl BinanceVenue {
+    pub fn new(vars: &EnvVars, params: &MarketParams) -> BinanceVenue {
+        BinanceVenue {
+            kind: VenueKind::Binance,
+            client: clients(vars).binance(),
+            websocket_url: vars.binance_websocket_url.clone(),
+            book_mode: vars.binance_book_mode,
+            book_depth: vars.binance_book_depth,
+            trigger_bps: params.market_making_trigger_bps,
+        }
+    }
+
+    /**
+     * Spot market of the same symbol. Only its best bid and ask are streamed, the diff
+     * book is seeded from the futures REST api
+     */
+    pub fn spot(vars: &EnvVars, params: &MarketParams) -> BinanceVenue {
+        BinanceVenue {
+            kind: VenueKind::BinanceSpot,
+            websocket_url: vars.binance_spot_websocket_url.clone(),
+            book_mode: BinanceBookMode::BookTicker,
+            ..BinanceVenue::new(vars, params)
+        }
+    }
+}
+
+impl ReferenceFeed for BinanceVenue {
+    fn kind(&self) -> VenueKind {
+        self.kind
+    }
+
+    fn stream_order_book(&self, market: &Market, tx: FeedSender<OrderBook>, tx_diff: FeedSender<OrderBook>) {
+        let symbol = market.symbols.binance.clone();
+        let trigger_bps = self.trigger_bps;
+        match self.book_mode {
+            BinanceBookMode::DiffDepth => {
+                let url = format!("{}/ws/{}@depth@100ms", &self.websocket_url, &symbol);
+                let forwarder = BookForwarder::new(&symbol, trigger_bps, tx, tx_diff);
+                runtime().spawn(stream_binance_book(self.client.clone(), url, symbol, self.book_depth, forwarder));
+            }
+            BinanceBookMode::Depth5 => {
+                let url = format!("{}/ws/{}@depth5@100ms", &self.websocket_url, &symbol);
+                runtime().spawn(async move {
+                    stream_order_book::<DepthUpdate, _>(BinanceOrderBookStream::new(&url), &symbol, trigger_bps, tx, tx_diff).await;
+                });
+            }
+            BinanceBookMode::BookTicker => {
+                let url = format!("{}/ws/{}@bookTicker", &self.websocket_url, &symbol);
+                runtime().spawn(async move {
+                    stream_order_book::<BookTicker, _>(BinanceOrderBookStream::new(&url), &symbol, trigger_bps, tx, tx_diff).await;
+                });
+            }
+        }
+    }
+}
@@ -0,0 +1,227 @@
+use crate::bluefin::{BluefinClient, BluefinPrivateEvent, UserPosition};
+use crate::clients::error::VenueError;
+use crate::clients::registry::clients;
+use crate::clients::runtime::runtime;
+use crate::env::EnvVars;
+use crate::models::bluefin_models::OrderbookDepthUpdate;
+use crate::models::common::{Market, MarketParams, VenueFees, VenueKind};
+use crate::models::order_book::OrderBook;
+use crate::models::units::{Price, Qty};
+use crate::sockets::bluefin_ob_socket::BluefinOrderBookStream;
+use crate::sockets::bluefin_session::BluefinSession;
+use crate::sockets::common::stream_order_book;
+use crate::utils::FeedSender;
+use crate::venues::venue::{OrderResponse, ReferenceFeed, TakerVenue};
+use async_trait::async_trait;
+use rust_decimal::prelude::{FromPrimitive, ToPrimitive};
+use rust_decimal::Decimal;
+use std::ops::Div;
+use std::sync::Arc;
+
+static BIGNUMBER_BASE: u128 = 1000000000000000000;
+
+pub struct BluefinVenue {
+    client: Arc<BluefinClient>,
+    session: Arc<BluefinSession>,
+    leverage: u128,
+    websocket_url: String,
+    trigger_bps: f64,
+}
+
+impl BluefinVenue {
+    pub fn new(vars: &EnvVars, params: &MarketParams) -> BluefinVenue {
+        BluefinVenue {
+            client: clients(vars).bluefin(),
+            session: clients(vars).bluefin_session(),
+            leverage: params.bluefin_leverage,
+            websocket_url: vars.bluefin_websocket_url.clone(),
+            trigger_bps: params.market_making_trigger_bps,
+        }
+    }
+
+    // None for an amount too large for a Decimal, Bluefin sends them with 18 decimals
+    fn from_bignumber(value: u128) -> Option<Decimal> {
+        Decimal::from_u128(value).map(|value| value / Decimal::from(BIGNUMBER_BASE))
+    }
+
+    fn signed_quantity(position: &UserPosition) -> Option<Decimal> {
+        let quantity = BluefinVenue::from_bignumber(position.quantity)?;
+        if position.side {
+            Some(quantity)
+        } else {
+            Some(-quantity)
+        }
+    }
+}
+
+#[async_trait]
+impl TakerVenue for BluefinVenue {
+    fn kind(&self) -> VenueKind {
+        VenueKind::Bluefin
+    }
+
+    async fn place_ioc_order(&self, market: &Market, is_buy: bool, price: Price, quantity: Qty) -> Result<OrderResponse, VenueError> {
+        let order = self.client.create_limit_ioc_order(
+            &market.symbols.bluefin,
+            is_buy,
+            false,
+            price,
+            quantity,
+            Some(self.leverage),
+        );
+
+        tracing::info!("order {:#?}", order);
+        let signature = self.client.sign_order(order.clone());
+        let status = self.client.post_signed_order(order, signature).await;
+        tracing::info!("status {:?}", status);
+
+        status.map(|_| OrderResponse { order_id: None })
+    }
+
+    async fn place_reduce_only_market_order(&self, market: &Market, is_buy: bool, quantity: Qty) -> Result<OrderResponse, VenueError> {
+        let order = self.client.create_market_order(
+            &market.symbols.bluefin,
+            is_buy,
+            true,
+            quantity,
+            Some(self.leverage),
+        );
+
+        tracing::info!("order {:#?}", order);
+        let signature = self.client.sign_order(order.clone());
+        let status = self.client.post_signed_order(order, signature).await;
+        tracing::info!("status {:?}", status);
+
+        status.map(|_| OrderResponse { order_id: None })
+    }
+
+    async fn get_position(&self, market: &Market) -> Result<Decimal, VenueError> {
+        let position = self.client.get_user_position(&market.symbols.bluefin).await?;
+        BluefinVenue::signed_quantity(&position).ok_or_else(|| VenueError::Decode {
+            venue: VenueKind::Bluefin,
+            source: serde::de::Error::custom(format!("position quantity {} out of range", position.quantity)),
+        })
+    }
+
+    // the account only reports its fee tier by name, the configured fees apply
+    async fn get_fees(&self, _market: &Market) -> Result<Option<VenueFees>, VenueError> {
+        Ok(None)
+    }
+
+    fn stream_order_book(&self, market: &Market, tx: FeedSender<OrderBook>, tx_diff: FeedSender<OrderBook>) {
+        let ob_stream = BluefinOrderBookStream::new(&self.websocket_url, &market.symbols.bluefin);
+        let symbol = market.symbols.bluefin.clone();
+        let trigger_bps = self.trigger_bps;
+        runtime().spawn(async move {
+            stream_order_book::<OrderbookDepthUpdate, _>(ob_stream, &symbol, trigger_bps, tx, tx_diff).await;
+        });
+    }
+
+    fn stream_fills(&self, market: &Market, tx: FeedSender<Decimal>) {
+        self.session.subscribe(Some(&market.symbols.bluefin), move |event: &BluefinPrivateEvent| {
+            let BluefinPrivateEvent::OrderSettlementUpdate(order_settlement) = event else {
+                return true;
+            };
+
+            tracing::info!(
+                quantity_sent_for_settlement = order_settlement.quantity_sent_for_settlement,
+                is_buy = order_settlement.is_buy,
+                "Bluefin Order Settlement Update"
+            );
+
+            let Some(quantity) = BluefinVenue::from_bignumber(order_settlement.quantity_sent_for_settlement) else {
+                tracing::warn!("Bluefin settlement quantity {} out of range", order_settlement.quantity_sent_for_settlement);
+                return true;
+            };
+
+            tx.send(if order_settlement.is_buy { quantity } else { -quantity }).is_ok()
+        });
+    }
+
+    fn stream_filled_volume(&self, market: &Market, tx: FeedSender<f64>) {
+        self.session.subscribe(Some(&market.symbols.bluefin), move |event: &BluefinPrivateEvent| {
+            let BluefinPrivateEvent::OrderUpdate(order_update) = event else {
+                return true;
+            };
+
+            if order_update.open_qty != 0 {
+                return true;
+            }
+
+            let (Some(quantity), Some(avg_fill_price)) = (
+                BluefinVenue::from_bignumber(order_update.quantity),
+                BluefinVenue::from_bignumber(order_update.avg_fill_price),
+            ) else {
+                tracing::warn!("Bluefin order update of {} out of range", order_update.symbol);
+                return true;
+            };
+            let Some(volume) = quantity.checked_mul(avg_fill_price).and_then(|volume| volume.to_f64()) else {
+                tracing::warn!("Bluefin volume of {} out of range", order_update.symbol);
+                return true;
+            };
+            tracing::info!(
+                market = order_update.symbol,
+                bluefin_volume = volume,
+                bluefin_order_status = order_update.order_status,
+                "Bluefin Volume"
+            );
+            tx.send(volume).is_ok()
+        });
+    }
+
+    fn stream_positions(&self, market: &Market, tx: FeedSender<Decimal>) {
+        self.session.subscribe(Some(&market.symbols.bluefin), move |event: &BluefinPrivateEvent| {
+            let BluefinPrivateEvent::PositionUpdate(user_position) = event else {
+                return true;
+            };
+
+            let Some(quantity) = BluefinVenue::signed_quantity(user_position) else {
+                tracing::warn!("Bluefin position quantity {} out of range", user_position.quantity);
+                return true;
+            };
+            // only logged, a profit out of range is left out
+            let unrealized_pnl = Decimal::from_i128(user_position.unrealized_profit)
+                .map(|profit| profit.div(Decimal::from(BIGNUMBER_BASE)))
+                .and_then(|profit| profit.to_f64());
+
+            tracing::info!(
+                market = user_position.symbol,
+                bluefin_real_quantity = quantity.to_f64().unwrap_or_default(),
+                bluefin_unrealized_pnl = unrealized_pnl,
+                "Bluefin Position Update"
+            );
+            tx.send(quantity).is_ok()
+        });
+    }
+
+    fn stream_balance(&self, tx: FeedSender<f64>) {
+        self.session.subscribe(None, move |event: &BluefinPrivateEvent| {
+            let BluefinPrivateEvent::AccountDataUpdate(ad) = event else {
+                return true;
+            };
+
+            tracing::info!(
+                wallet_balance = ad.wallet_balance,
+                total_position_qty_reduced = ad.total_position_qty_reduced,
+                total_position_qty_reducible = ad.total_position_qty_reducible,
+                total_position_margin = ad.total_position_margin,
+                total_unrealized_profit = ad.total_unrealized_profit,
+                total_expected_pnl = ad.total_expected_pnl,
+                free_collateral = ad.free_collateral,
+                account_value = ad.account_value,
+                "Bluefin Account Data"
+            );
+            tx.send(ad.account_value).is_ok()
+        });
+    }
+}
+
+impl ReferenceFeed for BluefinVenue {
+    fn kind(&self) -> VenueKind {
+        VenueKind::Bluefin
+    }
+
+    fn stream_order_book(&self, market: &Market, tx: FeedSender<OrderBook>, tx_diff: FeedSender<OrderBook>) {
+        TakerVenue::stream_order_book(self, market, tx, tx_diff);
+    }
+}
@@ -0,0 +1,228 @@
+use crate::clients::error::VenueError;
+use crate::clients::registry::clients;
+use crate::clients::runtime::runtime;
+use crate::env::EnvVars;
+use crate::kucoin::{AvailableBalance, KuCoinClient, PositionChangeEvent, TradeOrderMessage};
+use crate::models::common::{Market, MarketParams, VenueFees, VenueKind};
+use crate::models::order_book::OrderBook;
+use crate::models::units::Price;
+use crate::models::kucoin_models::TickerV2;
+use crate::sockets::kucoin_book::stream_kucoin_book;
+use crate::sockets::kucoin_session::KucoinSession;
+use crate::utils::FeedSender;
+use crate::venues::venue::{MakerVenue, OrderResponse, OrderUpdate};
+use async_trait::async_trait;
+use rust_decimal::prelude::{FromPrimitive, ToPrimitive};
+use rust_decimal::Decimal;
+use std::ops::Mul;
+use std::sync::Arc;
+
+pub struct KuCoinVenue {
+    client: Arc<KuCoinClient>,
+    session: Arc<KucoinSession>,
+    leverage: u128,
+    book_depth: usize,
+    ticker_topic: String,
+}
+
+impl KuCoinVenue {
+    pub fn new(vars: &EnvVars, params: &MarketParams) -> KuCoinVenue {
+        KuCoinVenue {
+            client: clients(vars).kucoin(),
+            session: clients(vars).kucoin_session(),
+            leverage: params.kucoin_leverage,
+            book_depth: vars.kucoin_book_depth,
+            ticker_topic: vars.kucoin_ticker_v2_socket_topic.clone(),
+        }
+    }
+}
+
+#[async_trait]
+impl MakerVenue for KuCoinVenue {
+    fn kind(&self) -> VenueKind {
+        VenueKind::Kucoin
+    }
+
+    async fn place_limit_order(&self, market: &Market, is_buy: bool, price: Price, quantity: u128) -> Result<OrderResponse, VenueError> {
+        // kucoin client resolves its own symbols from the bluefin market name
+        let order_id = self
+            .client
+            .place_limit_order(&market.symbols.bluefin, is_buy, price, quantity, Some(self.leverage))
+            .await?;
+
+        Ok(OrderResponse { order_id: Some(order_id) })
+    }
+
+    async fn cancel_all_orders(&self, market: &Market) -> Result<(), VenueError> {
+        self.client.cancel_all_orders(Some(&market.symbols.bluefin)).await
+    }
+
+    // an order that is gone already fails with `ErrorCode::OrderNotFound`
+    async fn cancel_order(&self, _market: &Market, order_id: &str) -> Result<(), VenueError> {
+        self.client.cancel_order_by_id(order_id).await
+    }
+
+    async fn place_reduce_only_market_order(&self, market: &Market, is_buy: bool, quantity: u128) -> Result<OrderResponse, VenueError> {
+        let order_id = self
+            .client
+            .place_reduce_only_market_order(&market.symbols.bluefin, is_buy, quantity, Some(self.leverage))
+            .await?;
+
+        Ok(OrderResponse { order_id: Some(order_id) })
+    }
+
+    async fn get_position(&self, market: &Market) -> Result<Decimal, VenueError> {
+        let position = self.client.get_position(&market.symbols.bluefin).await?;
+
+        tracing::info!(
+            market = market.symbols.bluefin,
+            avg_entry_price = position.avg_entry_price,
+            realised_pnl = position.realised_pnl,
+            unrealised_pnl = position.unrealised_pnl,
+            unrealised_pnl_pcnt = position.unrealised_pnl_pcnt,
+            unrealised_roe_pcnt = position.unrealised_roe_pcnt,
+            liquidation_price = position.liquidation_price,
+            "Kucoin position"
+        );
+
+        Ok(Decimal::from(position.current_qty) / Decimal::from(market.lot_size))
+    }
+
+    async fn get_fill_size_for_time_window(&self, market: &Market, is_buy: bool, since: u128) -> Result<i32, VenueError> {
+        let side = if is_buy { "buy" } else { "sell" };
+        self.client
+            .get_fill_size_for_time_window(&market.symbols.bluefin, side, since)
+            .await
+    }
+
+    async fn get_fees(&self, market: &Market) -> Result<Option<VenueFees>, VenueError> {
+        let fees = self.client.get_trade_fees(&market.symbols.kucoin).await?;
+        Ok(Some(VenueFees {
+            maker_bps: fees.maker_fee_rate * 10000.0,
+            taker_bps: fees.taker_fee_rate * 10000.0,
+        }))
+    }
+
+    fn can_afford(&self, requests: u32) -> bool {
+        self.client.budget().can_afford(requests)
+    }
+
+    fn stream_order_book(&self, market: &Market, tx: FeedSender<OrderBook>) {
+        let client = self.client.clone();
+        let session = self.session.clone();
+        let symbol = market.symbols.kucoin.clone();
+        let depth = self.book_depth;
+        runtime().spawn(stream_kucoin_book(client, session, symbol, depth, tx));
+    }
+
+    /**
+     * Forwards the ticker whenever the best bid or ask price changes
+     */
+    fn stream_ticker(&self, market: &Market, tx: FeedSender<OrderBook>) {
+        let topic = format!("{}:{}", self.ticker_topic, market.symbols.kucoin);
+        let mut last_best_bid_price: Option<String> = None;
+        let mut last_best_ask_price: Option<String> = None;
+
+        self.session.subscribe(&topic, None, false, move |msg: &str| {
+            let parsed_kucoin_ticker: TickerV2 = match serde_json::from_str(msg) {
+                Ok(ticker) => ticker,
+                Err(e) => {
+                    tracing::warn!("Could not parse kucoin ticker {}: {}", msg, e);
+                    return true;
+                }
+            };
+
+            let price_changed = last_best_bid_price.as_ref() != Some(&parsed_kucoin_ticker.data.best_bid_price)
+                || last_best_ask_price.as_ref() != Some(&parsed_kucoin_ticker.data.best_ask_price);
+
+            if price_changed {
+                last_best_bid_price = Some(parsed_kucoin_ticker.data.best_bid_price.clone());
+                last_best_ask_price = Some(parsed_kucoin_ticker.data.best_ask_price.clone());
+                return tx.send(parsed_kucoin_ticker.into()).is_ok();
+            }
+            true
+        });
+    }
+
+    /**
+     * Forwards every change of an order, sizes on KuCoin futures are in lots already
+     */
+    fn stream_order_updates(&self, market: &Market, tx: FeedSender<OrderUpdate>) {
+        let topic = format!("/contractMarket/tradeOrders:{}", market.symbols.kucoin);
+        self.session.subscribe(&topic, Some("symbolOrderChange"), true, move |msg: &str| {
+            let message: TradeOrderMessage = match serde_json::from_str(msg) {
+                Ok(message) => message,
+                Err(e) => {
+                    tracing::warn!("Could not parse kucoin order change {}: {}", msg, e);
+                    return true;
+                }
+            };
+            let update = OrderUpdate {
+                order_id: message.data.order_id,
+                remaining: message.data.remain_size as u128,
+                done: message.data.status == "done",
+            };
+            tx.send(update).is_ok()
+        });
+    }
+
+    fn stream_positions(&self, market: &Market, tx: FeedSender<Decimal>) {
+        let lot_size = market.lot_size;
+        let topic = format!("/contract/position:{}", market.symbols.kucoin);
+        self.session.subscribe(&topic, Some("position.change"), true, move |msg: &str| {
+            let kucoin_user_pos: PositionChangeEvent = match serde_json::from_str(msg) {
+                Ok(position) => position,
+                Err(e) => {
+                    tracing::warn!("Could not parse kucoin position change {}: {}", msg, e);
+                    return true;
+                }
+            };
+
+            let Some(current_qty) = Decimal::from_i128(kucoin_user_pos.data.current_qty) else {
+                tracing::warn!("Kucoin position of {} out of range", kucoin_user_pos.data.current_qty);
+                return true;
+            };
+            let quantity = current_qty / Decimal::from(lot_size);
+
+            // the volume is only logged, an entry price that is not a number leaves it at zero
+            let avg_entry_price = Decimal::from_f64(kucoin_user_pos.data.avg_entry_price).unwrap_or_default();
+
+            let volume = quantity.mul(avg_entry_price).abs().to_f64().unwrap_or_default();
+
+            tracing::info!(
+                market = kucoin_user_pos.data.symbol,
+                kucoin_real_quantity = quantity.to_f64().unwrap_or_default(),
+                kucoin_volume = volume,
+                kucoin_unrealized_pnl = kucoin_user_pos.data.unrealised_pnl,
+                kucoin_realized_pnl = kucoin_user_pos.data.realised_pnl,
+                "Kucoin Position Update"
+            );
+
+            tx.send(quantity).is_ok()
+        });
+    }
+
+    fn stream_balance(&self, tx: FeedSender<f64>) {
+        tracing::info!("Creating Kucoin Account Balance handler...");
+        self.session.subscribe("/contractAccount/wallet", Some("availableBalance.change"), true, move |msg: &str| {
+            let available_balance: AvailableBalance = match serde_json::from_str(msg) {
+                Ok(balance) => balance,
+                Err(e) => {
+                    tracing::warn!("Could not parse kucoin balance {}: {}", msg, e);
+                    return true;
+                }
+            };
+            tracing::info!(available_balance=available_balance.data.available_balance,
+                           hold_balance=available_balance.data.hold_balance,
+                           "Kucoin Account Balance");
+            let (Ok(available), Ok(hold)) = (
+                available_balance.data.available_balance.parse::<f64>(),
+                available_balance.data.hold_balance.parse::<f64>(),
+            ) else {
+                tracing::warn!("Kucoin balance is not a number: {}", msg);
+                return true;
+            };
+            tx.send(available + hold).is_ok()
+        });
+    }
+}
@@ -0,0 +1,127 @@
+use crate::clients::error::VenueError;
+use crate::env::EnvVars;
+use crate::models::common::{Market, MarketParams, VenueFees, VenueKind};
+use crate::models::order_book::OrderBook;
+use crate::models::units::{Price, Qty};
+use crate::utils::FeedSender;
+use crate::venues::binance_venue::BinanceVenue;
+use crate::venues::bluefin_venue::BluefinVenue;
+use crate::venues::kucoin_venue::KuCoinVenue;
+use async_trait::async_trait;
+use rust_decimal::Decimal;
+use std::sync::Arc;
+
+// Role a venue plays for a market
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum VenueRole {
+    Maker,
+    Taker,
+    Reference,
+}
+
+// Venue agnostic result of an accepted order call, rejections come back as `VenueError`
+#[derive(Debug, Clone, Default)]
+pub struct OrderResponse {
+    pub order_id: Option<String>,
+}
+
+// Change of a resting maker order, `remaining` in venue lots
+#[derive(Debug, Clone, PartialEq)]
+pub struct OrderUpdate {
+    pub order_id: String,
+    pub remaining: u128,
+    // filled or cancelled, the order no longer rests
+    pub done: bool,
+}
+
+/**
+ * Venue the market maker rests its quotes on. Quantities are expressed in venue lots
+ * (see `Market::lot_size`) and positions in signed base asset units.
+ * REST calls are async, synchronous callers run them with `clients::runtime::block_on`,
+ * and fail with a `VenueError` callers can branch on.
+ * Stream methods start a task on the shared runtime and return right away, the task
+ * ends once the receiver is dropped.
+ */
+#[async_trait]
+pub trait MakerVenue: Send + Sync {
+    fn kind(&self) -> VenueKind;
+    async fn place_limit_order(&self, market: &Market, is_buy: bool, price: Price, quantity: u128) -> Result<OrderResponse, VenueError>;
+    async fn cancel_all_orders(&self, market: &Market) -> Result<(), VenueError>;
+    async fn cancel_order(&self, market: &Market, order_id: &str) -> Result<(), VenueError>;
+    async fn place_reduce_only_market_order(&self, market: &Market, is_buy: bool, quantity: u128) -> Result<OrderResponse, VenueError>;
+    async fn get_position(&self, market: &Market) -> Result<Decimal, VenueError>;
+    async fn get_fill_size_for_time_window(&self, market: &Market, is_buy: bool, since: u128) -> Result<i32, VenueError>;
+    // fees the account pays on the market, None when the venue does not tell
+    async fn get_fees(&self, market: &Market) -> Result<Option<VenueFees>, VenueError>;
+    // whether the venue's request budget covers `requests` more REST calls right now
+    fn can_afford(&self, requests: u32) -> bool;
+    fn stream_order_book(&self, market: &Market, tx: FeedSender<OrderBook>);
+    fn stream_ticker(&self, market: &Market, tx: FeedSender<OrderBook>);
+    // fills and cancels of the account's orders on the market
+    fn stream_order_updates(&self, market: &Market, tx: FeedSender<OrderUpdate>);
+    fn stream_positions(&self, market: &Market, tx: FeedSender<Decimal>);
+    fn stream_balance(&self, tx: FeedSender<f64>);
+}
+
+/**
+ * Venue the hedger offsets maker fills on. Quantities and positions are expressed in
+ * signed base asset units. REST calls and streams behave as on `MakerVenue`.
+ */
+#[async_trait]
+pub trait TakerVenue: Send + Sync {
+    fn kind(&self) -> VenueKind;
+    async fn place_ioc_order(&self, market: &Market, is_buy: bool, price: Price, quantity: Qty) -> Result<OrderResponse, VenueError>;
+    async fn place_reduce_only_market_order(&self, market: &Market, is_buy: bool, quantity: Qty) -> Result<OrderResponse, VenueError>;
+    async fn get_position(&self, market: &Market) -> Result<Decimal, VenueError>;
+    // fees the account pays on the market, None when the venue does not tell
+    async fn get_fees(&self, market: &Market) -> Result<Option<VenueFees>, VenueError>;
+    fn stream_order_book(&self, market: &Market, tx: FeedSender<OrderBook>, tx_diff: FeedSender<OrderBook>);
+    fn stream_fills(&self, market: &Market, tx: FeedSender<Decimal>);
+    fn stream_filled_volume(&self, market: &Market, tx: FeedSender<f64>);
+    fn stream_positions(&self, market: &Market, tx: FeedSender<Decimal>);
+    fn stream_balance(&self, tx: FeedSender<f64>);
+}
+
+/**
+ * Venue whose order book is used as the pricing reference. The stream runs as a task
+ * on the shared runtime.
+ */
+pub trait ReferenceFeed: Send + Sync {
+    fn kind(&self) -> VenueKind;
+    fn stream_order_book(&self, market: &Market, tx: FeedSender<OrderBook>, tx_diff: FeedSender<OrderBook>);
+}
+
+/**
+ * Whether `kind` can play `role`, the factories below panic on any other combination.
+ * Checked when the config is loaded, see `AppConfig::validate_market`
+ */
+pub fn supports_role(kind: VenueKind, role: VenueRole) -> bool {
+    return match role {
+        VenueRole::Maker => kind == VenueKind::Kucoin,
+        VenueRole::Taker => kind == VenueKind::Bluefin,
+        VenueRole::Reference => matches!(kind, VenueKind::Binance | VenueKind::BinanceSpot | VenueKind::Bluefin),
+    };
+}
+
+pub fn create_maker_venue(kind: VenueKind, vars: &EnvVars, params: &MarketParams) -> Arc<dyn MakerVenue> {
+    match kind {
+        VenueKind::Kucoin => Arc::new(KuCoinVenue::new(vars, params)),
+        other => panic!("{:?} can not be used as a maker venue", other),
+    }
+}
+
+pub fn create_taker_venue(kind: VenueKind, vars: &EnvVars, params: &MarketParams) -> Arc<dyn TakerVenue> {
+    match kind {
+        VenueKind::Bluefin => Arc::new(BluefinVenue::new(vars, params)),
+        other => panic!("{:?} can not be used as a taker venue", other),
+    }
+}
+
+pub fn create_reference_feed(kind: VenueKind, vars: &EnvVars, params: &MarketParams) -> Arc<dyn ReferenceFeed> {
+    match kind {
+        VenueKind::Binance => Arc::new(BinanceVenue::new(vars, params)),
+        VenueKind::BinanceSpot => Arc::new(BinanceVenue::spot(vars, params)),
+        VenueKind::Bluefin => Arc::new(BluefinVenue::new(vars, params)),
+        other => panic!("{:?} can not be used as a reference feed", other),
+    }
+}